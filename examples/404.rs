@@ -4,5 +4,5 @@ use iron::prelude::*;
 use iron::StatusCode;
 
 fn main() {
-    Iron::new(|_: &mut Request| Ok(Response::with(StatusCode::NOT_FOUND))).http("localhost:3000");
+    Iron::new(|_: &mut Request| Ok(Response::with(StatusCode::NOT_FOUND))).http("localhost:3000").unwrap();
 }
@@ -65,9 +65,9 @@ fn main() {
     let silent = Iron::new(Logger::new(LoggerMode::Silent).around(Box::new(hello_world)));
     let large = Iron::new(Logger::new(LoggerMode::Large).around(Box::new(hello_world)));
 
-    let _tiny_listening = tiny.http("localhost:2000");
-    let _silent_listening = silent.http("localhost:3000");
-    let _large_listening = large.http("localhost:4000");
+    let _tiny = tiny.http("localhost:2000").unwrap();
+    let _silent = silent.http("localhost:3000").unwrap();
+    let _large = large.http("localhost:4000").unwrap();
 
     println!("Servers listening on 2000, 3000, and 4000");
 }
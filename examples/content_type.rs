@@ -10,10 +10,7 @@ use iron::StatusCode;
 
 fn variant1(_: &mut Request) -> IronResult<Response> {
     Ok(Response::with((
-        iron::modifiers::Header(
-            headers::CONTENT_TYPE,
-            iron::mime::APPLICATION_JSON.as_ref().parse().unwrap(),
-        ),
+        iron::modifiers::Header(headers::CONTENT_TYPE, "application/json"),
         StatusCode::OK,
         "{}",
     )))
@@ -45,5 +42,5 @@ fn main() {
         _ => panic!("No such variant"),
     };
     println!("Using variant{}", variant_index);
-    Iron::new(handler).http("localhost:3000");
+    Iron::new(handler).http("localhost:3000").unwrap();
 }
@@ -19,5 +19,5 @@ fn echo(request: &mut Request) -> IronResult<Response> {
 }
 
 fn main() {
-    Iron::new(echo).http("localhost:3000");
+    Iron::new(echo).http("localhost:3000").unwrap();
 }
@@ -15,5 +15,5 @@ fn echo_request(request: &mut Request) -> IronResult<Response> {
 }
 
 fn main() {
-    Iron::new(echo_request).http("localhost:3000");
+    Iron::new(echo_request).http("localhost:3000").unwrap();
 }
@@ -21,7 +21,7 @@ impl fmt::Display for StringError {
 
 impl Error for StringError {
     fn description(&self) -> &str {
-        &*self.0
+        &self.0
     }
 }
 
@@ -52,5 +52,5 @@ fn main() {
     // Link our error maker.
     chain.link_before(ErrorProducer);
 
-    Iron::new(chain).http("localhost:3000");
+    Iron::new(chain).http("localhost:3000").unwrap();
 }
@@ -30,7 +30,7 @@ impl fmt::Display for StringError {
 
 impl Error for StringError {
     fn description(&self) -> &str {
-        &*self.0
+        &self.0
     }
 }
 
@@ -103,5 +103,5 @@ fn main() {
     chain.link_after(ErrorProducer);
     chain.link_after(ErrorRecover);
 
-    Iron::new(chain).http("localhost:3000");
+    Iron::new(chain).http("localhost:3000").unwrap();
 }
@@ -6,7 +6,7 @@ struct DefaultContentType;
 impl AfterMiddleware for DefaultContentType {
     // This is run for every requests, AFTER all handlers have been executed
     fn after(&self, _req: &mut Request, mut resp: Response) -> IronResult<Response> {
-        if resp.headers.get(iron::headers::CONTENT_TYPE) == None {
+        if resp.headers.get(iron::headers::CONTENT_TYPE).is_none() {
             // Set a standard header
             resp.headers.insert(
                 iron::headers::CONTENT_TYPE,
@@ -36,5 +36,5 @@ fn info(req: &mut Request) -> IronResult<Response> {
 fn main() {
     let mut chain = Chain::new(info);
     chain.link_after(DefaultContentType);
-    Iron::new(chain).http(format!("localhost:{}", 3000));
+    Iron::new(chain).http(format!("localhost:{}", 3000)).unwrap();
 }
@@ -5,5 +5,5 @@ use iron::StatusCode;
 
 fn main() {
     Iron::new(|_: &mut Request| Ok(Response::with((StatusCode::OK, "Hello world!"))))
-        .http("localhost:3000");
+        .http("localhost:3000").unwrap();
 }
@@ -16,10 +16,12 @@ fn main() {
     iron.pool = CpuPool::new(8);
     iron.timeouts = Timeouts {
         keep_alive: Some(Duration::from_secs(10)),
+        handler: Some(Duration::from_secs(30)),
+        header_read: Some(Duration::from_secs(10)),
     };
 
     let addr = "127.0.0.1:3000".parse().unwrap();
     iron.local_address = Some(addr);
 
-    iron.http("127.0.0.1:3000");
+    iron.http("127.0.0.1:3000").unwrap();
 }
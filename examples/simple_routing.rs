@@ -54,5 +54,5 @@ fn main() {
         Ok(Response::with(StatusCode::BAD_REQUEST))
     });
 
-    Iron::new(router).http("localhost:3000");
+    Iron::new(router).http("localhost:3000").unwrap();
 }
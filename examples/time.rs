@@ -34,5 +34,5 @@ fn main() {
     let mut chain = Chain::new(hello_world);
     chain.link_before(ResponseTime);
     chain.link_after(ResponseTime);
-    Iron::new(chain).http("localhost:3000");
+    Iron::new(chain).http("localhost:3000").unwrap();
 }
@@ -0,0 +1,128 @@
+//! The server's accept loop.
+
+use std::io;
+use std::time::{Duration, Instant};
+
+use futures::{Async, Future, Poll, Stream};
+use tokio_timer::Delay;
+
+/// A stream of accepted connections which survives transient accept errors.
+///
+/// Errors concerning a single connection are skipped. When the process runs
+/// out of resources, e.g. file descriptors, accepting pauses for `backoff`
+/// before it is retried. Any other error is fatal and ends the stream.
+pub struct AcceptLoop<S> {
+    incoming: S,
+    backoff: Duration,
+    delay: Option<Delay>,
+}
+
+impl<S> AcceptLoop<S> {
+    pub fn new(incoming: S, backoff: Duration) -> AcceptLoop<S> {
+        AcceptLoop {
+            incoming,
+            backoff,
+            delay: None,
+        }
+    }
+}
+
+impl<S: Stream<Error = io::Error>> Stream for AcceptLoop<S> {
+    type Item = S::Item;
+    type Error = io::Error;
+
+    fn poll(&mut self) -> Poll<Option<S::Item>, io::Error> {
+        loop {
+            if let Some(ref mut delay) = self.delay {
+                match delay.poll() {
+                    Ok(Async::NotReady) => return Ok(Async::NotReady),
+                    Ok(Async::Ready(())) => (),
+                    Err(e) => error!("Accept backoff timer failed: {}", e),
+                }
+            }
+            self.delay = None;
+
+            match self.incoming.poll() {
+                Err(ref e) if is_connection_error(e) => {
+                    debug!("Accepted connection already failed: {}", e);
+                }
+                Err(ref e) if is_resource_exhaustion(e) => {
+                    warn!("Accept failed, retrying in {:?}: {}", self.backoff, e);
+                    self.delay = Some(Delay::new(Instant::now() + self.backoff));
+                }
+                Err(e) => {
+                    error!("Accept failed, shutting down: {}", e);
+                    return Err(e);
+                }
+                accepted => return accepted,
+            }
+        }
+    }
+}
+
+fn is_connection_error(e: &io::Error) -> bool {
+    matches!(
+        e.kind(),
+        io::ErrorKind::ConnectionRefused
+            | io::ErrorKind::ConnectionAborted
+            | io::ErrorKind::ConnectionReset
+            | io::ErrorKind::Interrupted
+    )
+}
+
+#[cfg(unix)]
+fn is_resource_exhaustion(e: &io::Error) -> bool {
+    use libc::{EMFILE, ENFILE, ENOBUFS, ENOMEM};
+
+    e.kind() == io::ErrorKind::OutOfMemory
+        || matches!(e.raw_os_error(), Some(EMFILE) | Some(ENFILE) | Some(ENOBUFS) | Some(ENOMEM))
+}
+
+#[cfg(not(unix))]
+fn is_resource_exhaustion(e: &io::Error) -> bool {
+    e.kind() == io::ErrorKind::OutOfMemory
+}
+
+#[cfg(all(test, unix))]
+mod test {
+    use super::*;
+
+    use std::sync::mpsc;
+
+    use futures::stream;
+    use hyper;
+
+    fn accept_all(results: Vec<Result<u32, io::Error>>) -> Result<Vec<u32>, io::ErrorKind> {
+        let (tx, rx) = mpsc::channel();
+        let accept = AcceptLoop::new(stream::iter_result(results), Duration::from_millis(10));
+        hyper::rt::run(accept.collect().then(move |result| {
+            tx.send(result.map_err(|e| e.kind())).unwrap();
+            Ok(())
+        }));
+        rx.recv().unwrap()
+    }
+
+    #[test]
+    fn test_transient_errors() {
+        let start = Instant::now();
+        let accepted = accept_all(vec![
+            Ok(1),
+            Err(io::Error::from_raw_os_error(::libc::EMFILE)),
+            Ok(2),
+            Err(io::ErrorKind::ConnectionReset.into()),
+            Ok(3),
+        ]);
+        assert_eq!(accepted, Ok(vec![1, 2, 3]));
+        assert!(start.elapsed() >= Duration::from_millis(10));
+    }
+
+    #[test]
+    fn test_fatal_error() {
+        let accepted = accept_all(vec![
+            Ok(1),
+            Err(io::ErrorKind::InvalidInput.into()),
+            Ok(2),
+        ]);
+        assert_eq!(accepted, Err(io::ErrorKind::InvalidInput));
+    }
+}
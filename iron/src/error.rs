@@ -1,7 +1,7 @@
 use std::fmt;
 
 use modifier::Modifier;
-use Response;
+use {Response, StatusCode};
 
 pub use hyper::error::Result as HttpResult;
 pub use hyper::Error as HttpError;
@@ -44,6 +44,17 @@ impl IronError {
     }
 }
 
+/// Errors without a specific response are answered with
+/// `500 Internal Server Error`.
+impl From<Box<dyn Error + Send>> for IronError {
+    fn from(error: Box<dyn Error + Send>) -> IronError {
+        IronError {
+            error,
+            response: Response::with(StatusCode::INTERNAL_SERVER_ERROR),
+        }
+    }
+}
+
 impl fmt::Display for IronError {
     fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
         fmt::Display::fmt(&*self.error, f)
@@ -51,6 +62,7 @@ impl fmt::Display for IronError {
 }
 
 impl Error for IronError {
+    #[allow(deprecated)]
     fn description(&self) -> &str {
         self.error.description()
     }
@@ -59,3 +71,18 @@ impl Error for IronError {
         self.error.source()
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::io;
+
+    #[test]
+    fn test_from_boxed_error() {
+        let error: Box<dyn Error + Send> = Box::new(io::Error::other("disk on fire"));
+        let err = IronError::from(error);
+        assert_eq!(err.response.status, Some(StatusCode::INTERNAL_SERVER_ERROR));
+        assert_eq!(err.to_string(), "disk on fire");
+    }
+}
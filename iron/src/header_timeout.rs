@@ -0,0 +1,246 @@
+//! Timing out connections which send request headers too slowly.
+
+use std::io::{self, Read, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use futures::{stream, Async, Future, Poll, Stream};
+use hyper::body::Payload;
+use hyper::Body;
+use tokio_io::{AsyncRead, AsyncWrite};
+use tokio_timer::Delay;
+
+// The start of the connection preface of HTTP/2 with prior knowledge.
+const H2_PREFACE: &[u8] = b"PRI * HTTP/2.0";
+
+/// A connection which fails reads once the request headers have taken
+/// longer than `timeout` to arrive.
+///
+/// The clock starts when the connection is accepted and again once the
+/// response to the previous request has been sent in full, as reported
+/// through `Idle`, and stops at the blank line ending the header block.
+/// Connections sitting idle between keep-alive requests are thus timed out
+/// as well, while request bodies and interim `1xx` responses are not.
+/// HTTP/2 connections frame requests differently and are never timed out.
+pub struct HeaderTimeout<T> {
+    io: T,
+    timeout: Option<Duration>,
+    deadline: Option<Delay>,
+    idle: Idle,
+    // The last bytes read, to find the end of the header block.
+    tail: u32,
+    // The first bytes read on the connection, to recognize HTTP/2.
+    preface: Vec<u8>,
+}
+
+impl<T> HeaderTimeout<T> {
+    pub fn new(io: T, timeout: Option<Duration>) -> HeaderTimeout<T> {
+        let mut conn = HeaderTimeout {
+            io,
+            timeout,
+            deadline: None,
+            idle: Idle::default(),
+            tail: 0,
+            preface: Vec::with_capacity(H2_PREFACE.len()),
+        };
+        conn.arm();
+        conn
+    }
+
+    /// The wrapped connection.
+    pub fn get_ref(&self) -> &T {
+        &self.io
+    }
+
+    /// The handle to report this connection idle with.
+    pub fn idle(&self) -> Idle {
+        self.idle.clone()
+    }
+
+    fn arm(&mut self) {
+        self.deadline = self
+            .timeout
+            .map(|timeout| Delay::new(Instant::now() + timeout));
+        self.tail = 0;
+    }
+
+    // Whether `data` completes the header block.
+    fn ends_headers(&mut self, data: &[u8]) -> bool {
+        data.iter().any(|&byte| {
+            self.tail = self.tail << 8 | u32::from(byte);
+            self.tail == 0x0d0a_0d0a || self.tail & 0xffff == 0x0a0a
+        })
+    }
+
+    // Turn the timeout off for good if `data` starts the HTTP/2 preface.
+    fn check_preface(&mut self, data: &[u8]) {
+        let missing = H2_PREFACE.len() - self.preface.len();
+        self.preface
+            .extend_from_slice(&data[..missing.min(data.len())]);
+        if self.preface == H2_PREFACE {
+            self.timeout = None;
+            self.deadline = None;
+        }
+    }
+}
+
+impl<T: Read> Read for HeaderTimeout<T> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let idle = self.idle.is_set();
+        if idle && self.deadline.is_none() {
+            self.arm();
+        }
+        if let Some(ref mut deadline) = self.deadline {
+            match deadline.poll() {
+                Ok(Async::NotReady) => (),
+                Ok(Async::Ready(())) => {
+                    debug!("Request headers not received in time, closing connection");
+                    return Err(io::ErrorKind::TimedOut.into());
+                }
+                Err(e) => error!("Header timeout timer failed: {}", e),
+            }
+        }
+
+        let read = self.io.read(buf)?;
+        if read > 0 && idle {
+            // The next request has started.
+            self.idle.clear();
+        }
+        if self.preface.len() < H2_PREFACE.len() {
+            self.check_preface(&buf[..read]);
+        }
+        if self.deadline.is_some() && self.ends_headers(&buf[..read]) {
+            self.deadline = None;
+        }
+        Ok(read)
+    }
+}
+
+impl<T: Write> Write for HeaderTimeout<T> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.io.write(buf)?;
+        // The rest of a complete response may take a while to reach a slow
+        // client, so the clock only starts once it has.
+        if written > 0 && self.idle.is_set() {
+            self.arm();
+        }
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.io.flush()
+    }
+}
+
+/// Reports a `HeaderTimeout` connection idle, restarting its clock for the
+/// next request.
+#[derive(Clone, Default)]
+pub struct Idle(Arc<AtomicBool>);
+
+impl Idle {
+    /// Report the connection idle once `body`, the body of the response to
+    /// the current request, has been sent in full.
+    pub fn after(self, body: Body) -> Body {
+        // A body of known length is already complete.
+        if body.content_length().is_some() {
+            self.0.store(true, Ordering::Release);
+            return body;
+        }
+        let end = stream::poll_fn(move || {
+            self.0.store(true, Ordering::Release);
+            Ok(Async::Ready(None))
+        });
+        Body::wrap_stream(body.chain(end))
+    }
+
+    fn is_set(&self) -> bool {
+        self.0.load(Ordering::Acquire)
+    }
+
+    fn clear(&self) {
+        self.0.store(false, Ordering::Release);
+    }
+}
+
+impl<T: AsyncRead> AsyncRead for HeaderTimeout<T> {}
+
+impl<T: AsyncWrite> AsyncWrite for HeaderTimeout<T> {
+    fn shutdown(&mut self) -> Poll<(), io::Error> {
+        self.io.shutdown()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::net::{self, Shutdown};
+    use std::thread;
+
+    use futures::Stream;
+    use hyper::server::conn::Http;
+    use hyper::service::NewService;
+    use tokio_reactor::Handle;
+    use tokio_tcp::TcpListener;
+
+    use {Iron, Request, Response, StatusCode};
+
+    // Serve one connection with a header timeout of 100 milliseconds, and
+    // return what the client receives after `client` has written its request.
+    fn serve_one<F>(client: F) -> Vec<u8>
+    where
+        F: FnOnce(&mut net::TcpStream) + Send + 'static,
+    {
+        let listener = net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let listener = TcpListener::from_std(listener, &Handle::default()).unwrap();
+
+        let client = thread::spawn(move || {
+            let mut stream = net::TcpStream::connect(addr).unwrap();
+            client(&mut stream);
+            stream.shutdown(Shutdown::Write).ok();
+            let mut received = vec![];
+            stream.read_to_end(&mut received).ok();
+            received
+        });
+
+        let iron = Iron::new(|_: &mut Request| Ok(Response::with((StatusCode::OK, "done"))));
+        let service = iron.new_service().wait().unwrap();
+        let server = listener
+            .incoming()
+            .into_future()
+            .map_err(|(e, _)| panic!("accept failed: {}", e))
+            .and_then(move |(socket, _)| {
+                let conn = HeaderTimeout::new(socket.unwrap(), Some(Duration::from_millis(100)));
+                Http::new().serve_connection(conn, service).then(|_| Ok(()))
+            });
+        ::hyper::rt::run(server);
+
+        client.join().unwrap()
+    }
+
+    #[test]
+    fn test_headers_in_time() {
+        let received = serve_one(|stream| {
+            stream
+                .write_all(b"GET / HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n")
+                .unwrap();
+        });
+        assert!(received.starts_with(b"HTTP/1.1 200 OK"));
+    }
+
+    #[test]
+    fn test_slow_headers() {
+        let start = Instant::now();
+        let received = serve_one(|stream| {
+            stream
+                .write_all(b"GET / HTTP/1.1\r\nHost: localhost\r\n")
+                .unwrap();
+            thread::sleep(Duration::from_millis(300));
+            stream.write_all(b"Connection: close\r\n\r\n").ok();
+        });
+        assert!(received.is_empty());
+        assert!(start.elapsed() < Duration::from_secs(5));
+    }
+}
@@ -1,22 +1,49 @@
 //! Exposes the `Iron` type, the main entrance point of the
 //! `Iron` library.
 
-use std::net::{SocketAddr, ToSocketAddrs};
-use std::sync::Arc;
-use std::time::Duration;
+use std::any::Any;
+use std::fmt;
+use std::io;
+use std::net::{IpAddr, SocketAddr, TcpListener, ToSocketAddrs};
+use std::panic::{self, AssertUnwindSafe};
+use std::str::FromStr;
+use std::sync::{Arc, OnceLock};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
-use futures::{future, Future};
+use futures::{future, stream, Future, Stream};
 use futures_cpupool::CpuPool;
 
 use hyper;
-use hyper::service::{NewService, Service};
+use hyper::service::{make_service_fn, NewService, Service};
 use hyper::Server;
 use hyper::{Body, Error};
+#[cfg(feature = "rustls")]
+use rustls::ServerConfig;
+#[cfg(unix)]
+use signal_hook::consts::{SIGINT, SIGTERM};
+#[cfg(unix)]
+use signal_hook::iterator::Signals;
+use socket2::{Domain, Socket, Type};
+use tokio::runtime;
+use tokio_io::{AsyncRead, AsyncWrite};
+use tokio_reactor::Handle;
+use tokio_tcp;
+use tokio_timer::Timeout;
 
-use request::HttpRequest;
-use response::HttpResponse;
+use accept::AcceptLoop;
+use header_timeout::{HeaderTimeout, Idle};
+use metrics::{count_in, count_out, ServerMetrics, ServerMetricsSnapshot};
+use shutdown::Shutdown;
+#[cfg(feature = "rustls")]
+use tls::TlsStream;
 
-use {Handler, Request, StatusCode};
+use request::{DeadlineKey, HttpRequest, MinDataRate, TlsInfo};
+use response::{BodyStream, HttpResponse};
+
+use headers::{HeaderMap, HeaderName, HeaderValue};
+use http::uri::Authority;
+use {Handler, Request, Response, StatusCode};
 
 /// The primary entrance point to `Iron`, a `struct` to instantiate a new server.
 ///
@@ -30,12 +57,24 @@ pub struct Iron<H> {
     /// Server timeouts.
     pub timeouts: Timeouts,
 
+    /// HTTP/1 connection behavior.
+    pub http1: Http1Options,
+
     /// Cpu pool to run synchronus requests on.
     ///
     /// Defaults to `num_cpus`.  Note that reading/writing to the client is
-    /// handled asyncronusly in a single thread.
+    /// handled asyncronusly on the `threads` of the server's runtime, and
+    /// streaming bodies are written on threads of their own.
     pub pool: CpuPool,
 
+    /// The number of worker threads of the runtime driving the connections.
+    ///
+    /// These threads read requests and write responses, but handlers run on
+    /// `pool`, so blocking handlers do not hold them up; a single thread can
+    /// serve many connections. Has to be at least 1. Defaults to the number
+    /// of CPUs.
+    pub threads: usize,
+
     /// Protocol of the incoming requests
     ///
     /// This is automatically set by the `http` and `https` functions, but
@@ -48,8 +87,118 @@ pub struct Iron<H> {
     /// provide one.  When unset, any request without a host specified
     /// will fail.
     pub local_address: Option<SocketAddr>,
+
+    /// Maximum size in bytes of a request body read through `Request::get_body_contents`.
+    ///
+    /// This is the default for `Request::body_limit`, which middleware can
+    /// override per request. Defaults to `None`, meaning no limit.
+    pub max_body_size: Option<usize>,
+
+    /// Minimum rate at which request bodies read through
+    /// `Request::get_body_contents` have to arrive.
+    ///
+    /// This is the default for `Request::min_body_rate`, which middleware can
+    /// override per request. Slower bodies are answered with `408 Request
+    /// Timeout` and the connection is closed. Defaults to `None`, meaning
+    /// bodies may arrive at any rate.
+    pub min_body_rate: Option<MinDataRate>,
+
+    /// Log a warning for any request whose handler takes longer than this.
+    ///
+    /// The warning includes the request's method, path and handling time.
+    /// Defaults to `None`, meaning slow requests are not logged.
+    pub slow_request_threshold: Option<Duration>,
+
+    /// Bind the listening socket with `SO_REUSEPORT`.
+    ///
+    /// This lets several processes listen on the same address, with the
+    /// kernel balancing new connections between them. Only supported on
+    /// Linux and the BSDs; binding fails elsewhere. Defaults to `false`.
+    pub reuse_port: bool,
+
+    /// How long to pause accepting connections after running out of resources.
+    ///
+    /// When accepting fails because the process is out of file descriptors or
+    /// memory, the server waits this long before trying again instead of
+    /// shutting down. Defaults to 100 milliseconds.
+    pub accept_backoff: Duration,
+
+    /// A header through which clients send a deadline for their request.
+    ///
+    /// The header's value is the deadline in milliseconds since the Unix
+    /// epoch, e.g. `X-Request-Deadline: 1767225600000`. If it is earlier than
+    /// `Timeouts::handler` allows, it bounds the handler instead, and
+    /// middleware can read it through `Request::deadline`. Defaults to `None`,
+    /// meaning no deadline header is read.
+    pub deadline_header: Option<HeaderName>,
+
+    /// Counters for the requests this server has answered.
+    ///
+    /// Clone the `Arc` before starting the server to read the counters while
+    /// it runs, e.g. `metrics.snapshot().client_error`.
+    pub metrics: Arc<ServerMetrics>,
+
+    /// The number of requests served on one connection before it is closed.
+    ///
+    /// The response to the last request carries `Connection: close`.
+    /// Defaults to `None`, meaning connections serve any number of requests.
+    pub max_requests_per_connection: Option<u32>,
+
+    /// Advertise the keep-alive settings in a `Keep-Alive` header.
+    ///
+    /// Responses carry e.g. `Keep-Alive: timeout=5, max=100`, where `timeout`
+    /// is `Timeouts::keep_alive` in seconds and `max` the number of requests
+    /// the connection has left under `max_requests_per_connection`. No header
+    /// is sent while keep alive is turned off. Defaults to `false`.
+    pub keep_alive_header: bool,
+
+    /// The handle for shutting down the server gracefully.
+    ///
+    /// Clone it before starting the server, and call `Shutdown::initiate`
+    /// on the clone to stop the server.
+    pub shutdown: Shutdown,
+
+    /// Decide from its remote address whether to serve a connection.
+    ///
+    /// Called as soon as a connection is accepted, before any TLS handshake
+    /// or request parsing. Connections for which it returns `false` are
+    /// closed right away, which makes for cheap allow and deny lists:
+    ///
+    /// ```ignore
+    /// iron.accept_filter = Some(Arc::new(move |addr: SocketAddr| !blocked.contains(&addr.ip())));
+    /// ```
+    ///
+    /// Defaults to `None`, meaning all connections are served.
+    pub accept_filter: Option<Arc<dyn Fn(SocketAddr) -> bool + Send + Sync>>,
+
+    /// Called as the server goes through the stages of its life.
+    ///
+    /// Each `Lifecycle` event carries the bound address. The events are also
+    /// logged at the `info` level. Defaults to `None`.
+    pub on_lifecycle: Option<Arc<dyn Fn(Lifecycle) + Send + Sync>>,
+
+    /// Reject requests whose target is an absolute URI for a foreign host.
+    ///
+    /// A request like `GET http://other.example/ HTTP/1.1` is answered with
+    /// `400 Bad Request` unless its authority names this server: one of
+    /// `server_names`, or the address the server is bound to. A missing port
+    /// is the scheme's default port. Servers acting as a forward proxy can
+    /// turn this off. Defaults to `true`.
+    pub reject_foreign_absolute_uris: bool,
+
+    /// The host names this server is reached by, each with an optional port,
+    /// e.g. `example.com` or `example.com:8080`.
+    ///
+    /// A name without a port stands for the default port of the server's
+    /// protocol. Used by `reject_foreign_absolute_uris`. Defaults to none.
+    pub server_names: Vec<String>,
 }
 
+/// The conventional name of the header for `Iron::deadline_header`.
+pub const REQUEST_DEADLINE: &str = "x-request-deadline";
+
+const KEEP_ALIVE: &str = "keep-alive";
+
 /// A settings struct containing a set of timeouts which can be applied to a server.
 #[derive(Debug, PartialEq, Clone, Copy)]
 pub struct Timeouts {
@@ -57,26 +206,121 @@ pub struct Timeouts {
     ///
     /// The default is `Some(Duration::from_secs(5))`.
     ///
-    /// NOTE: Setting this to None will have the effect of turning off keep alive.
+    /// This sets hyper's HTTP/1 keep alive and the TCP keepalive interval of
+    /// accepted connections.
+    ///
+    /// NOTE: Setting this to None will have the effect of turning off keep alive,
+    /// so connections are closed after one request.
     pub keep_alive: Option<Duration>,
+
+    /// Controls how long a request may take to be handled.
+    ///
+    /// Requests which take longer are answered with `504 Gateway Timeout`.
+    /// The handler itself is not interrupted and runs to completion in the
+    /// background.
+    ///
+    /// The default is `None`, meaning handlers are not timed out.
+    pub handler: Option<Duration>,
+
+    /// Controls how long a client may take to send the request headers.
+    ///
+    /// Connections whose request line and headers don't arrive within this
+    /// time are closed, which protects against clients trickling headers to
+    /// tie up connections. The time counts from accepting the connection or
+    /// finishing the previous response, so idle keep alive connections are
+    /// closed as well.
+    ///
+    /// The default is `None`, meaning headers may take any time.
+    pub header_read: Option<Duration>,
 }
 
 impl Default for Timeouts {
     fn default() -> Self {
         Timeouts {
             keep_alive: Some(Duration::from_secs(5)),
+            handler: None,
+            header_read: None,
+        }
+    }
+}
+
+impl Timeouts {
+    /// Timeouts with all of them turned off, including keep alive.
+    pub fn none() -> Timeouts {
+        Timeouts {
+            keep_alive: None,
+            handler: None,
+            header_read: None,
+        }
+    }
+
+    /// Set `keep_alive`, e.g. `Timeouts::default().keep_alive(None)`.
+    pub fn keep_alive(self, keep_alive: Option<Duration>) -> Timeouts {
+        Timeouts { keep_alive, ..self }
+    }
+
+    /// Set `handler`.
+    pub fn handler(self, handler: Option<Duration>) -> Timeouts {
+        Timeouts { handler, ..self }
+    }
+
+    /// Set `header_read`.
+    pub fn header_read(self, header_read: Option<Duration>) -> Timeouts {
+        Timeouts {
+            header_read,
+            ..self
         }
     }
 }
 
-#[derive(Clone)]
+/// Settings for the HTTP/1 connections of a server.
+///
+/// The defaults suit most clients; these are for tuning edge cases.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Http1Options {
+    /// Keep serving connections whose client has closed its sending side.
+    ///
+    /// Clients may shut down writing after sending a request while they wait
+    /// for the response. When this is `false`, the connection is closed as
+    /// soon as the end of the client's stream is read instead.
+    ///
+    /// The default is `true`.
+    pub half_close: bool,
+
+    /// Write response heads and bodies with vectored writes.
+    ///
+    /// Turning this off copies everything into a single buffer first, which
+    /// can be faster on transports with poor support for vectored writes,
+    /// like TLS.
+    ///
+    /// The default is `true`.
+    pub writev: bool,
+
+    /// Hold back responses to pipelined requests until all requests read so
+    /// far have been answered, flushing them together.
+    ///
+    /// The default is `false`.
+    pub pipeline_flush: bool,
+}
+
+impl Default for Http1Options {
+    fn default() -> Self {
+        Http1Options {
+            half_close: true,
+            writev: true,
+            pipeline_flush: false,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
 enum _Protocol {
     Http,
     Https,
 }
 
 /// Protocol used to serve content.
-#[derive(Clone)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct Protocol(_Protocol);
 
 impl Protocol {
@@ -99,6 +343,60 @@ impl Protocol {
     }
 }
 
+impl FromStr for Protocol {
+    type Err = String;
+
+    /// Parse a protocol from its name, ignoring case.
+    fn from_str(name: &str) -> Result<Protocol, String> {
+        if name.eq_ignore_ascii_case("http") {
+            Ok(Protocol::http())
+        } else if name.eq_ignore_ascii_case("https") {
+            Ok(Protocol::https())
+        } else {
+            Err(format!("Unknown protocol {:?}", name))
+        }
+    }
+}
+
+/// A stage in the life of a server, reported through `Iron::on_lifecycle`.
+///
+/// A server goes through the stages in order, each with the address it is
+/// bound to. A server listening on several addresses reports each stage
+/// once for every address.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Lifecycle {
+    /// The listening socket has been bound.
+    Bound(SocketAddr),
+
+    /// The server has started accepting connections.
+    Serving(SocketAddr),
+
+    /// Shutdown was initiated, and the open connections are being drained.
+    Draining(SocketAddr),
+
+    /// The server has stopped, with all connections closed.
+    Stopped(SocketAddr),
+}
+
+impl fmt::Display for Lifecycle {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Lifecycle::Bound(addr) => write!(f, "Bound to {}", addr),
+            Lifecycle::Serving(addr) => write!(f, "Serving on {}", addr),
+            Lifecycle::Draining(addr) => write!(f, "Draining connections on {}", addr),
+            Lifecycle::Stopped(addr) => write!(f, "Stopped serving on {}", addr),
+        }
+    }
+}
+
+// Log `event` and pass it to the callback, if any.
+fn notify(on_lifecycle: &Option<Arc<dyn Fn(Lifecycle) + Send + Sync>>, event: Lifecycle) {
+    info!("{}", event);
+    if let Some(ref callback) = *on_lifecycle {
+        callback(event);
+    }
+}
+
 impl<H: Handler> Iron<H> {
     /// Instantiate a new instance of `Iron`.
     ///
@@ -109,27 +407,301 @@ impl<H: Handler> Iron<H> {
             handler: Arc::new(handler),
             protocol: Protocol::http(),
             local_address: None,
+            max_body_size: None,
+            min_body_rate: None,
+            slow_request_threshold: None,
+            reuse_port: false,
+            accept_backoff: Duration::from_millis(100),
+            deadline_header: None,
+            metrics: Arc::new(ServerMetrics::new()),
+            max_requests_per_connection: None,
+            keep_alive_header: false,
+            shutdown: Shutdown::new(),
+            accept_filter: None,
+            on_lifecycle: None,
+            reject_foreign_absolute_uris: true,
+            server_names: Vec::new(),
             timeouts: Timeouts::default(),
+            http1: Http1Options::default(),
             pool: CpuPool::new_num_cpus(),
+            threads: thread::available_parallelism().map_or(1, |n| n.get()),
         }
     }
 
     /// Kick off the server process using the HTTP protocol.
     ///
-    /// Call this once to begin listening for requests on the server.
-    pub fn http<A>(mut self, addr: A)
+    /// Call this once to begin listening for requests on the server. The
+    /// server runs on a thread of its own; the returned `Listening` holds
+    /// the bound address and stops the server on `close`. Dropping it waits
+    /// for the server to be shut down through `Iron::shutdown`.
+    ///
+    /// `addr` is anything that resolves to socket addresses, such as a
+    /// `SocketAddr` or `"localhost:3000"`. The server listens on the first
+    /// of them that can be bound; use `http_multi` to listen on all of them.
+    /// Fails if `addr` cannot be resolved or none of its addresses can be
+    /// bound.
+    pub fn http<A>(mut self, addr: A) -> io::Result<Listening>
+    where
+        A: ToSocketAddrs,
+    {
+        let listener = bind_first(addr, self.reuse_port)?;
+        self.protocol = Protocol::http();
+        let header_read = self.timeouts.header_read;
+        let incoming = self
+            .listen(listener)?
+            .map(move |socket| HeaderTimeout::new(socket, header_read));
+
+        let sockets = self.local_address.into_iter().collect();
+        self.serve(sockets, incoming, |iron, conn| {
+            let mut service = iron.service(conn.get_ref().peer_addr().ok());
+            service.idle = Some(conn.idle());
+            service
+        })
+    }
+
+    /// Like `http`, but listen on all the addresses `addrs` resolves to.
+    ///
+    /// This serves one handler on several interfaces, e.g. on both the IPv4
+    /// and the IPv6 loopback address. The returned `Listening` holds all
+    /// bound addresses in `sockets`, and `close` stops the server on all of
+    /// them. Fails if `addrs` resolves to no address or one cannot be bound.
+    pub fn http_multi<A>(mut self, addrs: A) -> io::Result<Listening>
     where
         A: ToSocketAddrs,
     {
-        let addr: SocketAddr = addr.to_socket_addrs().unwrap().next().unwrap();
-        self.local_address = Some(addr);
+        let addrs: Vec<SocketAddr> = addrs.to_socket_addrs()?.collect();
+        if addrs.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "no address to listen on",
+            ));
+        }
+        self.protocol = Protocol::http();
+        let header_read = self.timeouts.header_read;
+        let mut sockets = vec![];
+        let mut incoming: Box<dyn Stream<Item = _, Error = io::Error> + Send> =
+            Box::new(stream::empty());
+        for addr in addrs {
+            let listener = bind_first(addr, self.reuse_port)?;
+            let listener = self.listen(listener)?;
+            // Set by `listen`, so unwrap is safe here.
+            sockets.push(self.local_address.unwrap());
+            incoming = Box::new(incoming.select(listener));
+        }
+        // Requests get the address of the listener they arrived on, so the
+        // first one only stands in for the server as a whole.
+        self.local_address = Some(sockets[0]);
+        let incoming = incoming.map(move |socket| HeaderTimeout::new(socket, header_read));
 
-        let server = Server::bind(&addr)
-            .tcp_keepalive(self.timeouts.keep_alive)
-            .serve(self)
+        self.serve(sockets, incoming, |iron, conn| {
+            let mut service = iron.service(conn.get_ref().peer_addr().ok());
+            service.addr = conn.get_ref().local_addr().ok();
+            service.idle = Some(conn.idle());
+            service
+        })
+    }
+
+    /// Kick off the server process using the HTTPS protocol.
+    ///
+    /// Connections are encrypted with `tls`, which holds the certificate
+    /// chain and private key, and requests get an `https` URL. The details
+    /// of each connection's TLS session are available through
+    /// `Request::tls_info`. Like `http`, this returns a `Listening` for the
+    /// server running in the background.
+    #[cfg(feature = "rustls")]
+    pub fn https<A>(mut self, addr: A, tls: ServerConfig) -> io::Result<Listening>
+    where
+        A: ToSocketAddrs,
+    {
+        let listener = bind_first(addr, self.reuse_port)?;
+        self.protocol = Protocol::https();
+        let tls = Arc::new(tls);
+        let header_read = self.timeouts.header_read;
+        let incoming = self.listen(listener)?.filter_map(move |socket| {
+            let peer = socket.peer_addr().ok();
+            match TlsStream::new(socket, tls.clone(), peer) {
+                Ok(stream) => Some(HeaderTimeout::new(stream, header_read)),
+                Err(e) => {
+                    error!("Error setting up TLS connection: {}", e);
+                    None
+                }
+            }
+        });
+
+        let sockets = self.local_address.into_iter().collect();
+        self.serve(sockets, incoming, |iron, conn| {
+            let stream = conn.get_ref();
+            let mut service = iron.service(stream.get_ref().peer_addr().ok());
+            service.tls_info = Some(stream.info());
+            service.idle = Some(conn.idle());
+            service
+        })
+    }
+
+    // Accept connections on `listener`.
+    fn listen(
+        &mut self,
+        listener: TcpListener,
+    ) -> io::Result<impl Stream<Item = tokio_tcp::TcpStream, Error = io::Error>> {
+        // The port asked for may have been 0, for any free port.
+        let local_addr = listener.local_addr()?;
+        let listener = tokio_tcp::TcpListener::from_std(listener, &Handle::default())?;
+        self.local_address = Some(local_addr);
+        notify(&self.on_lifecycle, Lifecycle::Bound(local_addr));
+
+        let keep_alive = self.timeouts.keep_alive;
+        let accept_filter = self.accept_filter.clone();
+        let incoming = AcceptLoop::new(listener.incoming(), self.accept_backoff);
+        Ok(incoming.filter_map(move |socket| {
+            if let Some(ref accept) = accept_filter {
+                // Dropping the socket closes the connection.
+                match socket.peer_addr() {
+                    Ok(addr) if accept(addr) => (),
+                    Ok(addr) => {
+                        debug!("Refused connection from {}", addr);
+                        return None;
+                    }
+                    Err(e) => {
+                        debug!("Error reading remote address: {}", e);
+                        return None;
+                    }
+                }
+            }
+            if let Err(e) = socket.set_keepalive(keep_alive) {
+                debug!("Error setting TCP keepalive: {}", e);
+            }
+            Some(socket)
+        }))
+    }
+
+    // Serve the connections of `incoming`, accepted on `sockets`, on a new
+    // thread, each with the service `service` makes for it, until shutdown.
+    fn serve<I, F>(
+        self,
+        sockets: Vec<SocketAddr>,
+        incoming: I,
+        mut service: F,
+    ) -> io::Result<Listening>
+    where
+        I: Stream<Error = io::Error> + Send + 'static,
+        I::Item: AsyncRead + AsyncWrite + Send + 'static,
+        F: FnMut(&Self, &I::Item) -> IronHandler<H> + Send + 'static,
+    {
+        // Every caller listens on at least one socket.
+        let socket = sockets[0];
+        let mut runtime = runtime::Builder::new().core_threads(self.threads).build()?;
+        let handle = self.shutdown.clone();
+        let metrics = self.metrics.clone();
+        let on_lifecycle = self.on_lifecycle.clone();
+        let on_draining = self.on_lifecycle.clone();
+        let draining = sockets.clone();
+        let shutdown = self.shutdown.signal().map(move |()| {
+            for &socket in &draining {
+                notify(&on_draining, Lifecycle::Draining(socket));
+            }
+        });
+        let server = Server::builder(incoming)
+            .http1_keepalive(self.timeouts.keep_alive.is_some())
+            .http1_half_close(self.http1.half_close)
+            .http1_writev(self.http1.writev)
+            .http1_pipeline_flush(self.http1.pipeline_flush)
+            .serve(make_service_fn(move |conn: &I::Item| {
+                future::ok::<_, Error>(service(&self, conn))
+            }))
+            .with_graceful_shutdown(shutdown)
             .map_err(|e| eprintln!("server error: {}", e));
 
-        hyper::rt::run(server);
+        let serving = sockets.clone();
+        Ok(Listening {
+            socket,
+            sockets,
+            metrics,
+            shutdown: handle,
+            thread: Some(thread::spawn(move || {
+                for &socket in &serving {
+                    notify(&on_lifecycle, Lifecycle::Serving(socket));
+                }
+                runtime.spawn(server);
+                // Runs until the server and everything it spawned are done.
+                let _ = runtime.shutdown_on_idle().wait();
+                for &socket in &serving {
+                    notify(&on_lifecycle, Lifecycle::Stopped(socket));
+                }
+            })),
+        })
+    }
+}
+
+/// A handle for a server started by `Iron::http` or `Iron::https`.
+///
+/// Dropping the handle blocks until the server has been shut down, so a
+/// program serving requests until it is killed can simply drop it.
+pub struct Listening {
+    /// The address the server is bound to, with the port the operating
+    /// system assigned if port 0 was requested.
+    pub socket: SocketAddr,
+
+    /// All addresses the server is bound to, of which `socket` is the first.
+    ///
+    /// Only a server started with `Iron::http_multi` has more than one.
+    pub sockets: Vec<SocketAddr>,
+
+    metrics: Arc<ServerMetrics>,
+    shutdown: Shutdown,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Listening {
+    /// Shut the server down gracefully and wait for it to finish.
+    ///
+    /// The server stops accepting connections, but requests already being
+    /// handled are answered before it stops. This is the same as initiating
+    /// `Iron::shutdown` and dropping the handle.
+    pub fn close(self) {
+        self.shutdown.initiate();
+    }
+
+    /// The current value of the server's `Iron::metrics` counters.
+    pub fn metrics(&self) -> ServerMetricsSnapshot {
+        self.metrics.snapshot()
+    }
+
+    /// Shut the server down gracefully on the first `SIGINT` or `SIGTERM`
+    /// the process receives.
+    ///
+    /// Once registered, these signals no longer terminate the process. Drop
+    /// the handle afterwards to wait for the shutdown:
+    ///
+    /// ```ignore
+    /// Iron::new(handler).http("localhost:3000")?.close_on_ctrl_c()?;
+    /// ```
+    #[cfg(unix)]
+    pub fn close_on_ctrl_c(&self) -> io::Result<()> {
+        let mut signals = Signals::new([SIGINT, SIGTERM])?;
+        let shutdown = self.shutdown.clone();
+        thread::spawn(move || {
+            if let Some(signal) = signals.forever().next() {
+                info!("Received signal {}, shutting down", signal);
+                shutdown.initiate();
+            }
+        });
+        Ok(())
+    }
+}
+
+impl Drop for Listening {
+    fn drop(&mut self) {
+        if let Some(thread) = self.thread.take() {
+            if thread.join().is_err() {
+                error!("Server thread panicked");
+            }
+        }
+    }
+}
+
+impl fmt::Debug for Listening {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Listening {{ socket: {} }}", self.socket)
     }
 }
 
@@ -142,12 +714,35 @@ impl<H: Handler> NewService for Iron<H> {
     type Future = future::FutureResult<Self::Service, Self::InitError>;
 
     fn new_service(&self) -> Self::Future {
-        future::ok(IronHandler {
+        future::ok(self.service(None))
+    }
+}
+
+impl<H: Handler> Iron<H> {
+    // The service for a connection from `remote_addr`.
+    fn service(&self, remote_addr: Option<SocketAddr>) -> IronHandler<H> {
+        IronHandler {
             handler: self.handler.clone(),
             addr: self.local_address,
+            remote_addr,
+            tls_info: None,
             protocol: self.protocol.clone(),
             pool: self.pool.clone(),
-        })
+            max_body_size: self.max_body_size,
+            min_body_rate: self.min_body_rate,
+            slow_request_threshold: self.slow_request_threshold,
+            handler_timeout: self.timeouts.handler,
+            deadline_header: self.deadline_header.clone(),
+            metrics: self.metrics.clone(),
+            keep_alive: self.timeouts.keep_alive,
+            keep_alive_header: self.keep_alive_header,
+            max_requests: self.max_requests_per_connection,
+            served: 0,
+            shutdown: self.shutdown.clone(),
+            reject_foreign_absolute_uris: self.reject_foreign_absolute_uris,
+            server_names: self.server_names.clone(),
+            idle: None,
+        }
     }
 }
 
@@ -155,8 +750,78 @@ impl<H: Handler> NewService for Iron<H> {
 pub struct IronHandler<H> {
     handler: Arc<H>,
     addr: Option<SocketAddr>,
+    remote_addr: Option<SocketAddr>,
+    tls_info: Option<Arc<OnceLock<TlsInfo>>>,
     protocol: Protocol,
     pool: CpuPool,
+    max_body_size: Option<usize>,
+    min_body_rate: Option<MinDataRate>,
+    slow_request_threshold: Option<Duration>,
+    handler_timeout: Option<Duration>,
+    deadline_header: Option<HeaderName>,
+    metrics: Arc<ServerMetrics>,
+    keep_alive: Option<Duration>,
+    keep_alive_header: bool,
+    max_requests: Option<u32>,
+    served: u32,
+    shutdown: Shutdown,
+    reject_foreign_absolute_uris: bool,
+    server_names: Vec<String>,
+    idle: Option<Idle>,
+}
+
+impl<H: Handler> IronHandler<H> {
+    // Whether the request target is in absolute form with an authority which
+    // names neither one of the server names nor the bound address. HTTP/2
+    // requests carry their host in the URI.
+    fn is_foreign_absolute_uri(&self, req: &HttpRequest<Body>) -> bool {
+        let authority = match req.uri().authority_part() {
+            Some(authority) if req.version() != ::http::Version::HTTP_2 => authority,
+            _ => return false,
+        };
+        let host = authority.host();
+        let port = authority
+            .port_u16()
+            .unwrap_or_else(|| default_port(req.uri().scheme_str().unwrap_or("http")));
+
+        let server_port = default_port(self.protocol.name());
+        let named = self.server_names.iter().any(|name| {
+            name.parse::<Authority>().is_ok_and(|name| {
+                name.host().eq_ignore_ascii_case(host)
+                    && name.port_u16().unwrap_or(server_port) == port
+            })
+        });
+        let bound = self
+            .addr
+            .is_some_and(|addr| addr.port() == port && is_bound_host(host, addr.ip()));
+        !(named || bound)
+    }
+
+    // The connection headers for the next response on this connection.
+    fn connection_headers(&mut self) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        let keep_alive = match self.keep_alive {
+            Some(keep_alive) => keep_alive,
+            None => return headers,
+        };
+
+        let remaining = self.max_requests.map(|max| max.saturating_sub(self.served));
+        self.served = self.served.saturating_add(1);
+        if remaining.is_some_and(|remaining| remaining <= 1) {
+            headers.insert(::headers::CONNECTION, HeaderValue::from_static("close"));
+            return headers;
+        }
+
+        if self.keep_alive_header {
+            let mut value = format!("timeout={}", keep_alive.as_secs());
+            if let Some(remaining) = remaining {
+                value.push_str(&format!(", max={}", remaining));
+            }
+            // Only digits and separators, so unwrap is safe here.
+            headers.insert(KEEP_ALIVE, value.parse().unwrap());
+        }
+        headers
+    }
 }
 
 impl<H: Handler> Service for IronHandler<H> {
@@ -167,33 +832,1326 @@ impl<H: Handler> Service for IronHandler<H> {
 
     fn call(&mut self, req: HttpRequest<Self::ReqBody>) -> Self::Future {
         let addr = self.addr;
+        let remote_addr = self.remote_addr;
+        let tls_info = self.tls_info.as_ref().and_then(|info| info.get()).cloned();
         let proto = self.protocol.clone();
         let handler = self.handler.clone();
+        let max_body_size = self.max_body_size;
+        let min_body_rate = self.min_body_rate;
+        let slow_request_threshold = self.slow_request_threshold;
+        let metrics = self.metrics.clone();
+        let foreign_uri = self.reject_foreign_absolute_uris && self.is_foreign_absolute_uri(&req);
+        let req = req.map(|body| count_in(body, metrics.clone()));
+        let connection_headers = self.connection_headers();
+        let draining = self.keep_alive.and(Some(self.shutdown.clone()));
+        let idle = self.idle.clone();
+
+        let now = Instant::now();
+        let client_deadline = self
+            .deadline_header
+            .as_ref()
+            .and_then(|name| parse_deadline(req.headers(), name, now));
+        let deadline = match (self.handler_timeout.map(|timeout| now + timeout), client_deadline) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        };
 
-        Box::new(self.pool.spawn_fn(move || {
+        let response = self.pool.spawn_fn(move || {
             let mut http_res = HttpResponse::<Body>::new(Body::empty());
             *http_res.status_mut() = StatusCode::INTERNAL_SERVER_ERROR;
 
-            match Request::from_http(req, addr, &proto) {
+            let req = if foreign_uri {
+                Err(format!("Absolute URI for a foreign host: {}", req.uri()))
+            } else {
+                Request::from_http(req, addr, remote_addr, &proto)
+            };
+
+            let stream = match req {
                 Ok(mut req) => {
+                    req.body_limit = max_body_size;
+                    req.min_body_rate = min_body_rate;
+                    if let Some(tls_info) = tls_info {
+                        req.extensions.insert::<TlsInfo>(tls_info);
+                    }
+                    if let Some(deadline) = deadline {
+                        req.extensions.insert::<DeadlineKey>(deadline);
+                    }
+
                     // Dispatch the request, write the response back to http_res
-                    handler
-                        .handle(&mut req)
-                        .unwrap_or_else(|e| {
-                            error!("Error handling:\n{:?}\nError was: {:?}", req, e.error);
-                            e.response
-                        }).write_back(&mut http_res, req.method)
+                    let start = Instant::now();
+                    let res = panic::catch_unwind(AssertUnwindSafe(|| handler.handle(&mut req)))
+                        .unwrap_or_else(|payload| {
+                            error!(
+                                "Handler panicked on {} {}: {}",
+                                req.method,
+                                req.url.as_ref().path(),
+                                panic_message(&*payload)
+                            );
+                            Ok(Response::with(StatusCode::INTERNAL_SERVER_ERROR))
+                        });
+                    let elapsed = start.elapsed();
+
+                    if slow_request_threshold.is_some_and(|threshold| elapsed > threshold) {
+                        warn!(
+                            "Slow request: {} {} took {:?}",
+                            req.method,
+                            req.url.as_ref().path(),
+                            elapsed
+                        );
+                    }
+
+                    res.unwrap_or_else(|e| {
+                        error!("Error handling:\n{:?}\nError was: {:?}", req, e.error);
+                        e.response
+                    }).write_back(&mut http_res, req.method)
                 }
                 Err(e) => {
                     error!("Error creating request:\n    {}", e);
-                    bad_request(&mut http_res)
+                    bad_request(&mut http_res);
+                    None
                 }
             };
+
+            // Streaming bodies are written once hyper has the response head.
+            if let Some(stream) = stream {
+                spawn_stream(stream);
+            }
+
             future::ok(http_res)
+        });
+
+        let response: Self::Future = match deadline {
+            Some(deadline) => Box::new(Timeout::new_at(response, deadline).or_else(|e| {
+                if e.is_inner() {
+                    return Err(e.into_inner().unwrap());
+                }
+
+                let mut http_res = HttpResponse::<Body>::new(Body::empty());
+                if e.is_elapsed() {
+                    *http_res.status_mut() = StatusCode::GATEWAY_TIMEOUT;
+                } else {
+                    error!("Error timing out request: {}", e);
+                    *http_res.status_mut() = StatusCode::INTERNAL_SERVER_ERROR;
+                }
+                Ok(http_res)
+            })),
+            None => Box::new(response),
+        };
+
+        Box::new(response.map(move |mut http_res| {
+            http_res.headers_mut().extend(connection_headers);
+            // Tell clients not to reuse a connection which is being drained.
+            if draining.is_some_and(|shutdown| shutdown.is_initiated()) {
+                http_res.headers_mut().remove(KEEP_ALIVE);
+                http_res
+                    .headers_mut()
+                    .insert(::headers::CONNECTION, HeaderValue::from_static("close"));
+            }
+            metrics.record(http_res.status());
+            http_res.map(|body| {
+                let body = count_out(body, metrics);
+                match idle {
+                    Some(idle) => idle.after(body),
+                    None => body,
+                }
+            })
         }))
     }
 }
 
+// The message a panic was started with, if it was given one.
+fn panic_message(payload: &(dyn Any + Send)) -> &str {
+    payload
+        .downcast_ref::<&str>()
+        .cloned()
+        .or_else(|| payload.downcast_ref::<String>().map(|s| &**s))
+        .unwrap_or("Box<dyn Any>")
+}
+
+// Read a deadline in milliseconds since the Unix epoch from the header `name`.
+fn parse_deadline(headers: &HeaderMap, name: &HeaderName, now: Instant) -> Option<Instant> {
+    let deadline: u64 = headers.get(name)?.to_str().ok()?.trim().parse().ok()?;
+    let deadline = UNIX_EPOCH + Duration::from_millis(deadline);
+    let remaining = deadline
+        .duration_since(SystemTime::now())
+        .unwrap_or(Duration::from_secs(0));
+    Some(now + remaining)
+}
+
+// Streaming bodies may block for as long as the client stays connected, e.g.
+// event streams, so each gets its own thread instead of a slot in the
+// handler pool.
+fn spawn_stream(stream: BodyStream) {
+    let spawned = thread::Builder::new()
+        .name("iron-stream".to_owned())
+        .spawn(move || stream.write());
+    if let Err(e) = spawned {
+        error!("Error spawning response stream thread: {}", e);
+    }
+}
+
+// Bind to the first address `addr` resolves to that can be bound.
+fn bind_first<A: ToSocketAddrs>(addr: A, reuse_port: bool) -> io::Result<TcpListener> {
+    let addrs = addr
+        .to_socket_addrs()
+        .map_err(|e| io::Error::new(e.kind(), format!("error resolving address: {}", e)))?;
+    let mut error = io::Error::new(io::ErrorKind::InvalidInput, "no address to bind to");
+    for addr in addrs {
+        let bound = if reuse_port {
+            bind_reuse_port(&addr)
+        } else {
+            TcpListener::bind(addr)
+        };
+        match bound {
+            Ok(listener) => return Ok(listener),
+            Err(e) => error = io::Error::new(e.kind(), format!("error binding to {}: {}", addr, e)),
+        }
+    }
+    Err(error)
+}
+
+fn bind_reuse_port(addr: &SocketAddr) -> io::Result<TcpListener> {
+    let socket = Socket::new(Domain::for_address(*addr), Type::STREAM, None)?;
+    socket.set_reuse_address(true)?;
+    set_reuse_port(&socket)?;
+    socket.bind(&(*addr).into())?;
+    socket.listen(1024)?;
+    Ok(socket.into())
+}
+
+#[cfg(all(
+    unix,
+    not(any(target_os = "solaris", target_os = "illumos", target_os = "cygwin"))
+))]
+fn set_reuse_port(socket: &Socket) -> io::Result<()> {
+    socket.set_reuse_port(true)
+}
+
+#[cfg(not(all(
+    unix,
+    not(any(target_os = "solaris", target_os = "illumos", target_os = "cygwin"))
+)))]
+fn set_reuse_port(_: &Socket) -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Other,
+        "SO_REUSEPORT is not supported on this platform",
+    ))
+}
+
+// The port implied by `scheme` when a URI has none.
+fn default_port(scheme: &str) -> u16 {
+    if scheme.eq_ignore_ascii_case("https") {
+        443
+    } else {
+        80
+    }
+}
+
+// Whether `host` names the IP address `ip` a server is bound to.
+fn is_bound_host(host: &str, ip: IpAddr) -> bool {
+    let literal = host.trim_start_matches('[').trim_end_matches(']');
+    match literal.parse::<IpAddr>() {
+        Ok(host) => host == ip || ip.is_unspecified(),
+        Err(_) => {
+            let local = ip.is_loopback() || ip.is_unspecified();
+            local && host.eq_ignore_ascii_case("localhost")
+        }
+    }
+}
+
 fn bad_request(http_res: &mut HttpResponse<Body>) {
     *http_res.status_mut() = StatusCode::BAD_REQUEST;
 }
+
+#[cfg(test)]
+pub(crate) mod test {
+    use super::*;
+
+    use std::sync::{mpsc, Mutex, Once};
+    use std::thread;
+
+    use bytes::Bytes;
+    use log::{self, Log, Metadata, Record};
+
+    use response::{self, Event, EventStream};
+    use {headers, BeforeMiddleware, Chain, IronError, IronResult, Response};
+
+    fn serve<H: Handler>(iron: &Iron<H>, req: HttpRequest<Body>) -> (StatusCode, Vec<u8>) {
+        let mut service = iron.new_service().wait().unwrap();
+        let res = service.call(req).wait().unwrap();
+        let status = res.status();
+        (status, res.into_body().concat2().wait().unwrap().to_vec())
+    }
+
+    fn request(method: ::Method, path: &str, body: &'static str) -> HttpRequest<Body> {
+        let mut req = HttpRequest::new(Body::from(body));
+        *req.method_mut() = method;
+        *req.uri_mut() = path.parse().unwrap();
+        req.headers_mut()
+            .insert(headers::HOST, "localhost".parse().unwrap());
+        req
+    }
+
+    fn post(path: &str, body: &'static str) -> HttpRequest<Body> {
+        request(::Method::POST, path, body)
+    }
+
+    fn echo(req: &mut Request) -> IronResult<Response> {
+        let body = req
+            .get_body_contents()
+            .map_err(|e| IronError::new(e, StatusCode::PAYLOAD_TOO_LARGE))?
+            .clone();
+        Ok(Response::with((StatusCode::OK, body)))
+    }
+
+    struct RaiseUploadLimit;
+
+    impl BeforeMiddleware for RaiseUploadLimit {
+        fn before(&self, req: &mut Request) -> IronResult<()> {
+            if req.url.path() == ["upload"] {
+                req.body_limit = Some(1024);
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_protocol_from_str() {
+        for protocol in &[Protocol::http(), Protocol::https()] {
+            assert_eq!(&Protocol::from_str(protocol.name()).unwrap(), protocol);
+        }
+        assert_eq!(Protocol::from_str("HTTPS"), Ok(Protocol::https()));
+        assert!(Protocol::from_str("ftp").is_err());
+        assert!(Protocol::from_str("").is_err());
+    }
+
+    #[test]
+    fn test_body_limit_override() {
+        let mut chain = Chain::new(echo);
+        chain.link_before(RaiseUploadLimit);
+        let mut iron = Iron::new(chain);
+        iron.max_body_size = Some(4);
+
+        let (status, _) = serve(&iron, post("/echo", "too large"));
+        assert_eq!(status, StatusCode::PAYLOAD_TOO_LARGE);
+
+        let (status, body) = serve(&iron, post("/upload", "too large"));
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body, b"too large");
+    }
+
+    #[test]
+    fn test_body_limit_aborts_early() {
+        use std::io::{Read, Write};
+        use std::net::TcpStream;
+
+        let mut iron = Iron::new(|req: &mut Request| {
+            let len = req.get_body_contents()?.len();
+            Ok(Response::with((StatusCode::OK, len.to_string())))
+        });
+        iron.max_body_size = Some(4);
+        let listening = iron.http("127.0.0.1:0").unwrap();
+
+        // Neither body is ever finished, so the response can only come from
+        // giving up on it.
+        let requests: [&[u8]; 2] = [
+            b"POST / HTTP/1.1\r\nHost: localhost\r\nContent-Length: 1048576\r\n\r\n",
+            b"POST / HTTP/1.1\r\nHost: localhost\r\nTransfer-Encoding: chunked\r\n\r\n\
+              a\r\n0123456789\r\n",
+        ];
+        for request in &requests {
+            let mut stream = TcpStream::connect(listening.socket).unwrap();
+            stream
+                .set_read_timeout(Some(Duration::from_secs(5)))
+                .unwrap();
+            stream.write_all(request).unwrap();
+            let mut received = String::new();
+            stream.read_to_string(&mut received).unwrap();
+
+            assert!(received.starts_with("HTTP/1.1 413 Payload Too Large"));
+            assert!(received.contains("connection: close\r\n"));
+        }
+
+        listening.close();
+    }
+
+    #[test]
+    fn test_handler_response_written_back() {
+        let iron = Iron::new(|_: &mut Request| {
+            let mut res = Response::with((StatusCode::CREATED, "hello"));
+            res.headers
+                .insert("x-custom", "custom value".parse().unwrap());
+            Ok(res)
+        });
+
+        let mut service = iron.new_service().wait().unwrap();
+        let res = service
+            .call(request(::Method::GET, "/", ""))
+            .wait()
+            .unwrap();
+        assert_eq!(res.status(), StatusCode::CREATED);
+        assert_eq!(res.headers()["x-custom"], "custom value");
+        assert_eq!(res.headers()[headers::CONTENT_LENGTH], "5");
+        let body = res.into_body().concat2().wait().unwrap();
+        assert_eq!(&body[..], b"hello");
+    }
+
+    #[test]
+    fn test_handler_error_response() {
+        let iron = Iron::new(|_: &mut Request| -> IronResult<Response> {
+            let error = ::std::io::Error::other("missing field `name`");
+            let response = (StatusCode::BAD_REQUEST, "bad input");
+            Err(IronError::new(error, response))
+        });
+
+        let (status, body) = serve(&iron, request(::Method::GET, "/", ""));
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body, b"bad input");
+    }
+
+    #[test]
+    fn test_response_from_iter() {
+        let iron = Iron::new(|_: &mut Request| {
+            let items = (0..1000).map(|i| Bytes::from(format!("{},", i)));
+            Ok(Response::with((StatusCode::OK, response::from_iter(items))))
+        });
+
+        let (status, body) = serve(&iron, request(::Method::GET, "/", ""));
+        assert_eq!(status, StatusCode::OK);
+        let expected: String = (0..1000).map(|i| format!("{},", i)).collect();
+        assert_eq!(String::from_utf8(body).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_handler_panic() {
+        let iron = Iron::new(|req: &mut Request| {
+            if req.url.path() == ["panic"] {
+                panic!("handler bug");
+            }
+            Ok(Response::with((StatusCode::OK, "fine")))
+        });
+
+        let (status, _) = serve(&iron, request(::Method::GET, "/panic", ""));
+        assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+
+        // The worker survives the panic.
+        let (status, body) = serve(&iron, request(::Method::GET, "/", ""));
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body, b"fine");
+    }
+
+    #[test]
+    fn test_panic_message() {
+        assert_eq!(panic_message(&"static"), "static");
+        assert_eq!(panic_message(&"owned".to_owned()), "owned");
+        assert_eq!(panic_message(&42), "Box<dyn Any>");
+    }
+
+    struct Greeting(&'static str);
+
+    impl Handler for Greeting {
+        fn handle(&self, _: &mut Request) -> IronResult<Response> {
+            Ok(Response::with((StatusCode::OK, self.0)))
+        }
+    }
+
+    #[test]
+    fn test_boxed_handler() {
+        for &(mode, expected) in &[("static", "hello"), ("dynamic", "hello from a closure")] {
+            let handler: Box<dyn Handler> = match mode {
+                "static" => Box::new(Greeting("hello")),
+                _ => Box::new(|_: &mut Request| {
+                    Ok(Response::with((StatusCode::OK, "hello from a closure")))
+                }),
+            };
+            let listening = Iron::new(handler).http("127.0.0.1:0").unwrap();
+
+            let received = fetch(
+                listening.socket,
+                b"GET / HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n",
+            );
+            assert!(received.starts_with("HTTP/1.1 200 OK"));
+            assert!(received.ends_with(&format!("\r\n\r\n{}", expected)));
+
+            listening.close();
+        }
+    }
+
+    // Connect to `addr`, send `request` and read until the server closes the
+    // connection.
+    fn fetch(addr: SocketAddr, request: &[u8]) -> String {
+        use std::io::{Read, Write};
+        use std::net::TcpStream;
+
+        let mut stream = TcpStream::connect(addr).unwrap();
+        stream
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+        stream.write_all(request).unwrap();
+        let mut received = String::new();
+        stream.read_to_string(&mut received).unwrap();
+        received
+    }
+
+    // Send `req` to `addr` over HTTP/2 without TLS, as a client knowing the
+    // server speaks it.
+    fn h2c(addr: SocketAddr, mut req: HttpRequest<Body>) -> (StatusCode, Vec<u8>) {
+        *req.uri_mut() = format!("http://{}{}", addr, req.uri()).parse().unwrap();
+        let client = hyper::Client::builder()
+            .http2_only(true)
+            .build_http::<Body>();
+        let response = client.request(req).and_then(|res| {
+            let status = res.status();
+            res.into_body()
+                .concat2()
+                .map(move |body| (status, body.to_vec()))
+        });
+        let mut runtime = runtime::Runtime::new().unwrap();
+        runtime.block_on(response).unwrap()
+    }
+
+    #[test]
+    fn test_http2_get_with_body() {
+        let listening = Iron::new(echo).http("127.0.0.1:0").unwrap();
+
+        // A body of unknown length goes without a `Content-Length`.
+        let body = stream::once(Ok::<_, io::Error>("hello"));
+        let mut req = HttpRequest::new(Body::wrap_stream(body));
+        *req.uri_mut() = "/".parse().unwrap();
+        let (status, body) = h2c(listening.socket, req);
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body, b"hello");
+
+        listening.close();
+    }
+
+    // Serve `done` with a header timeout of 100 milliseconds.
+    fn header_timeout_server() -> Listening {
+        let mut iron = Iron::new(|req: &mut Request| {
+            let body = req.get_body_contents()?.clone();
+            Ok(Response::with((StatusCode::OK, body)))
+        });
+        iron.timeouts.header_read = Some(Duration::from_millis(100));
+        iron.http("127.0.0.1:0").unwrap()
+    }
+
+    #[test]
+    fn test_header_timeout_slow_body() {
+        use std::io::{Read, Write};
+        use std::net::TcpStream;
+
+        let listening = header_timeout_server();
+        let mut stream = TcpStream::connect(listening.socket).unwrap();
+        stream
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+        stream
+            .write_all(
+                b"POST / HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\
+                  Expect: 100-continue\r\nContent-Length: 10\r\n\r\n",
+            )
+            .unwrap();
+        let mut continued = [0; 25];
+        stream.read_exact(&mut continued).unwrap();
+        assert_eq!(&continued[..], b"HTTP/1.1 100 Continue\r\n\r\n");
+
+        // Neither the interim response nor the body restart the clock.
+        for _ in 0..2 {
+            thread::sleep(Duration::from_millis(150));
+            stream.write_all(b"hello").unwrap();
+        }
+        let mut received = String::new();
+        stream.read_to_string(&mut received).unwrap();
+        assert!(received.starts_with("HTTP/1.1 200 OK"));
+        assert!(received.ends_with("hellohello"));
+
+        listening.close();
+    }
+
+    #[test]
+    fn test_header_timeout_idle_connection() {
+        use std::io::{Read, Write};
+        use std::net::TcpStream;
+
+        let listening = header_timeout_server();
+        let mut stream = TcpStream::connect(listening.socket).unwrap();
+        stream
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+        stream
+            .write_all(b"POST / HTTP/1.1\r\nHost: localhost\r\nContent-Length: 4\r\n\r\ndone")
+            .unwrap();
+        let mut received = vec![];
+        while !received.ends_with(b"done") {
+            let mut buf = [0; 256];
+            let read = stream.read(&mut buf).unwrap();
+            assert!(read > 0);
+            received.extend_from_slice(&buf[..read]);
+        }
+        assert!(received.starts_with(b"HTTP/1.1 200 OK"));
+
+        // The connection kept alive is closed when no next request arrives.
+        let start = Instant::now();
+        assert_eq!(stream.read(&mut [0; 256]).unwrap(), 0);
+        assert!(start.elapsed() < Duration::from_secs(5));
+
+        listening.close();
+    }
+
+    #[test]
+    fn test_header_timeout_http2() {
+        use hyper::client::conn;
+
+        let listening = header_timeout_server();
+        let mut runtime = runtime::Runtime::new().unwrap();
+        let handshake = tokio_tcp::TcpStream::connect(&listening.socket)
+            .map_err(|e| panic!("connect failed: {}", e))
+            .and_then(|tcp| {
+                conn::Builder::new()
+                    .http2_only(true)
+                    .handshake::<_, Body>(tcp)
+            });
+        let (mut client, connection) = runtime.block_on(handshake).unwrap();
+        runtime.spawn(connection.map_err(|e| panic!("connection failed: {}", e)));
+
+        // Both requests go over the one connection, which is not timed out
+        // in between.
+        for _ in 0..2 {
+            let mut req = post("/", "done");
+            *req.uri_mut() = format!("http://{}/", listening.socket).parse().unwrap();
+            let response = client.send_request(req).and_then(|res| {
+                let status = res.status();
+                res.into_body().concat2().map(move |body| (status, body))
+            });
+            let (status, body) = runtime.block_on(response).unwrap();
+            assert_eq!(status, StatusCode::OK);
+            assert_eq!(&body[..], b"done");
+            thread::sleep(Duration::from_millis(300));
+        }
+
+        drop(client);
+        runtime.shutdown_on_idle().wait().unwrap();
+        listening.close();
+    }
+
+    #[test]
+    fn test_http_addrs() {
+        let handler = |_: &mut Request| Ok(Response::with((StatusCode::OK, "done")));
+        let addr: SocketAddr = "127.0.0.1:0".parse().unwrap();
+        let servers = vec![
+            Iron::new(handler).http("localhost:0").unwrap(),
+            Iron::new(handler).http(addr).unwrap(),
+        ];
+        for listening in servers {
+            let received = fetch(
+                listening.socket,
+                b"GET / HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n",
+            );
+            assert!(received.ends_with("\r\n\r\ndone"));
+            listening.close();
+        }
+    }
+
+    #[test]
+    fn test_http_errors() {
+        let handler = |_: &mut Request| Ok(Response::with(StatusCode::OK));
+        assert!(Iron::new(handler).http("localhost:http:80").is_err());
+
+        let err = Iron::new(handler).http_multi(&[][..]).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+
+        let taken = TcpListener::bind("127.0.0.1:0").unwrap();
+        let err = Iron::new(handler)
+            .http(taken.local_addr().unwrap())
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::AddrInUse);
+    }
+
+    #[test]
+    fn test_bind_first() {
+        let taken = TcpListener::bind("127.0.0.1:0").unwrap();
+        let taken = taken.local_addr().unwrap();
+        let free: SocketAddr = "127.0.0.1:0".parse().unwrap();
+        let listener = bind_first(&[taken, free][..], false).unwrap();
+        assert_ne!(listener.local_addr().unwrap(), taken);
+
+        let err = bind_first(&[taken][..], false).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::AddrInUse);
+        let err = bind_first(&[][..], false).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        assert!(bind_first("localhost:http:80", false).is_err());
+    }
+
+    #[test]
+    fn test_http_multi() {
+        let events = Arc::new(Mutex::new(vec![]));
+        let recorded = events.clone();
+        let mut iron = Iron::new(|req: &mut Request| {
+            let local = req.local_addr.unwrap().to_string();
+            Ok(Response::with((StatusCode::OK, local)))
+        });
+        iron.on_lifecycle = Some(Arc::new(move |event| recorded.lock().unwrap().push(event)));
+        let mut addrs: Vec<SocketAddr> = vec!["127.0.0.1:0".parse().unwrap()];
+        // Hosts without IPv6 only get the IPv4 half of the test.
+        if TcpListener::bind("[::1]:0").is_ok() {
+            addrs.push("[::1]:0".parse().unwrap());
+        }
+        let listening = iron.http_multi(&addrs[..]).unwrap();
+        assert_eq!(listening.sockets.len(), addrs.len());
+        assert_eq!(listening.socket, listening.sockets[0]);
+
+        for &addr in &listening.sockets {
+            let received = fetch(
+                addr,
+                b"GET / HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n",
+            );
+            assert!(received.starts_with("HTTP/1.1 200 OK"));
+            assert!(received.ends_with(&format!("\r\n\r\n{}", addr)));
+        }
+
+        let sockets = listening.sockets.clone();
+        listening.close();
+        let events = events.lock().unwrap();
+        for &socket in &sockets {
+            for &event in &[
+                Lifecycle::Bound(socket),
+                Lifecycle::Serving(socket),
+                Lifecycle::Draining(socket),
+                Lifecycle::Stopped(socket),
+            ] {
+                assert_eq!(events.iter().filter(|&&e| e == event).count(), 1);
+            }
+        }
+    }
+
+    #[test]
+    fn test_single_thread() {
+        let mut iron = Iron::new(|_: &mut Request| Ok(Response::with((StatusCode::OK, "done"))));
+        iron.threads = 1;
+        let listening = iron.http("127.0.0.1:0").unwrap();
+
+        // An idle connection does not keep the others from being served.
+        let idle = ::std::net::TcpStream::connect(listening.socket).unwrap();
+        for _ in 0..3 {
+            let received = fetch(
+                listening.socket,
+                b"GET / HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n",
+            );
+            assert!(received.starts_with("HTTP/1.1 200 OK"));
+            assert!(received.ends_with("\r\n\r\ndone"));
+        }
+
+        drop(idle);
+        listening.close();
+    }
+
+    #[test]
+    fn test_listening() {
+        let iron = Iron::new(|_: &mut Request| Ok(Response::with((StatusCode::OK, "done"))));
+        let listening = iron.http("127.0.0.1:0").unwrap();
+        assert_ne!(listening.socket.port(), 0);
+
+        let received = fetch(
+            listening.socket,
+            b"GET / HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n",
+        );
+        assert!(received.starts_with("HTTP/1.1 200 OK"));
+        assert!(received.ends_with("\r\n\r\ndone"));
+
+        listening.close();
+    }
+
+    #[test]
+    fn test_close_finishes_in_flight_requests() {
+        let (started, handler_started) = mpsc::channel();
+        let started = Mutex::new(started);
+        let iron = Iron::new(move |_: &mut Request| {
+            started.lock().unwrap().send(()).unwrap();
+            thread::sleep(Duration::from_millis(300));
+            Ok(Response::with((StatusCode::OK, "done")))
+        });
+        let listening = iron.http("127.0.0.1:0").unwrap();
+
+        let socket = listening.socket;
+        let client = thread::spawn(move || {
+            fetch(
+                socket,
+                b"GET / HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n",
+            )
+        });
+        handler_started.recv().unwrap();
+        listening.close();
+
+        let received = client.join().unwrap();
+        assert!(received.starts_with("HTTP/1.1 200 OK"));
+        assert!(received.ends_with("\r\n\r\ndone"));
+    }
+
+    #[test]
+    fn test_timeouts_builder() {
+        let default = Timeouts::default();
+        let second = Some(Duration::from_secs(1));
+        assert_eq!(
+            default.keep_alive(None),
+            Timeouts {
+                keep_alive: None,
+                ..default
+            }
+        );
+        assert_eq!(
+            default.handler(second),
+            Timeouts {
+                handler: second,
+                ..default
+            }
+        );
+        assert_eq!(
+            default.header_read(second),
+            Timeouts {
+                header_read: second,
+                ..default
+            }
+        );
+        assert_eq!(Timeouts::none().keep_alive(second).keep_alive, second);
+        assert_eq!(Timeouts::none(), default.keep_alive(None));
+    }
+
+    #[test]
+    fn test_lifecycle() {
+        let events = Arc::new(Mutex::new(vec![]));
+        let recorded = events.clone();
+        let mut iron = Iron::new(|_: &mut Request| Ok(Response::with(StatusCode::OK)));
+        iron.on_lifecycle = Some(Arc::new(move |event| recorded.lock().unwrap().push(event)));
+        let listening = iron.http("127.0.0.1:0").unwrap();
+        let addr = listening.socket;
+        listening.close();
+
+        assert_eq!(
+            *events.lock().unwrap(),
+            [
+                Lifecycle::Bound(addr),
+                Lifecycle::Serving(addr),
+                Lifecycle::Draining(addr),
+                Lifecycle::Stopped(addr),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_accept_filter() {
+        use std::collections::HashSet;
+        use std::io::{Read, Write};
+        use std::net::TcpStream;
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::Mutex;
+
+        let handled = Arc::new(AtomicUsize::new(0));
+        let counter = handled.clone();
+        let mut iron = Iron::new(move |_: &mut Request| {
+            counter.fetch_add(1, Ordering::SeqCst);
+            Ok(Response::with((StatusCode::OK, "done")))
+        });
+        // Connections are told apart by their client port.
+        let blocked = Arc::new(Mutex::new(HashSet::new()));
+        let deny = blocked.clone();
+        iron.accept_filter = Some(Arc::new(move |addr: SocketAddr| {
+            !deny.lock().unwrap().contains(&addr)
+        }));
+        let listening = iron.http("127.0.0.1:0").unwrap();
+
+        let socket = Socket::new(Domain::IPV4, Type::STREAM, None).unwrap();
+        let client: SocketAddr = "127.0.0.1:0".parse().unwrap();
+        socket.bind(&client.into()).unwrap();
+        let client = socket.local_addr().unwrap().as_socket().unwrap();
+        blocked.lock().unwrap().insert(client);
+        socket.connect(&listening.socket.into()).unwrap();
+
+        let mut stream = TcpStream::from(socket);
+        stream
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+        let _ = stream.write_all(b"GET / HTTP/1.1\r\nHost: localhost\r\n\r\n");
+        let mut received = vec![];
+        let _ = stream.read_to_end(&mut received);
+        assert!(received.is_empty());
+        assert_eq!(handled.load(Ordering::SeqCst), 0);
+
+        let received = fetch(
+            listening.socket,
+            b"GET / HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n",
+        );
+        assert!(received.starts_with("HTTP/1.1 200 OK"));
+        assert_eq!(handled.load(Ordering::SeqCst), 1);
+
+        listening.close();
+    }
+
+    #[test]
+    fn test_http1_half_close() {
+        use std::io::{Read, Write};
+        use std::net::{Shutdown, TcpStream};
+
+        let mut iron = Iron::new(|_: &mut Request| Ok(Response::with((StatusCode::OK, "done"))));
+        iron.http1 = Http1Options {
+            half_close: true,
+            writev: false,
+            pipeline_flush: true,
+        };
+        let listening = iron.http("127.0.0.1:0").unwrap();
+
+        let mut stream = TcpStream::connect(listening.socket).unwrap();
+        stream
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+        stream
+            .write_all(b"GET / HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n")
+            .unwrap();
+        stream.shutdown(Shutdown::Write).unwrap();
+        let mut received = String::new();
+        stream.read_to_string(&mut received).unwrap();
+
+        assert!(received.starts_with("HTTP/1.1 200 OK"));
+        assert!(received.ends_with("\r\n\r\ndone"));
+
+        listening.close();
+    }
+
+    #[test]
+    fn test_keep_alive_disabled() {
+        let mut iron = Iron::new(|_: &mut Request| Ok(Response::with((StatusCode::OK, "done"))));
+        iron.timeouts.keep_alive = None;
+        let listening = iron.http("127.0.0.1:0").unwrap();
+
+        // Without keep alive the server closes the connection after responding.
+        let received = fetch(
+            listening.socket,
+            b"GET / HTTP/1.1\r\nHost: localhost\r\n\r\n",
+        );
+        assert!(received.starts_with("HTTP/1.1 200 OK"));
+        assert!(received.ends_with("\r\n\r\ndone"));
+
+        listening.close();
+    }
+
+    #[test]
+    fn test_remote_addr() {
+        let iron = Iron::new(|req: &mut Request| {
+            let remote_addr = format!("{:?}", req.remote_addr);
+            Ok(Response::with((StatusCode::OK, remote_addr)))
+        });
+
+        let mut service = iron.service("10.0.0.1:4321".parse().ok());
+        let res = service
+            .call(request(::Method::GET, "/", ""))
+            .wait()
+            .unwrap();
+        let body = res.into_body().concat2().wait().unwrap();
+        assert_eq!(&body[..], b"Some(10.0.0.1:4321)");
+
+        let (_, body) = serve(&iron, request(::Method::GET, "/", ""));
+        assert_eq!(body, b"None");
+    }
+
+    #[test]
+    fn test_connection_info() {
+        use std::io::{Read, Write};
+        use std::net::TcpStream;
+
+        let iron = Iron::new(|req: &mut Request| {
+            let info = req.connection_info();
+            let body = format!("{:?} {:?} {}", info.local_addr, info.remote_addr, info.tls);
+            Ok(Response::with((StatusCode::OK, body)))
+        });
+        let listening = iron.http("127.0.0.1:0").unwrap();
+
+        let mut stream = TcpStream::connect(listening.socket).unwrap();
+        stream
+            .write_all(b"GET / HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n")
+            .unwrap();
+        let mut received = String::new();
+        stream.read_to_string(&mut received).unwrap();
+
+        let expected = format!(
+            "\r\n\r\nSome({}) Some({}) false",
+            listening.socket,
+            stream.local_addr().unwrap()
+        );
+        assert!(received.ends_with(&expected), "{}", received);
+
+        listening.close();
+    }
+
+    static LOGGED: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+    struct TestLogger;
+
+    impl Log for TestLogger {
+        fn enabled(&self, metadata: &Metadata) -> bool {
+            metadata.level() <= log::Level::Debug
+        }
+
+        fn log(&self, record: &Record) {
+            if self.enabled(record.metadata()) {
+                LOGGED.lock().unwrap().push(record.args().to_string());
+            }
+        }
+
+        fn flush(&self) {}
+    }
+
+    // Whether a message containing `needle` was logged since `capture_logs`.
+    pub(crate) fn logged(needle: &str) -> bool {
+        LOGGED.lock().unwrap().iter().any(|w| w.contains(needle))
+    }
+
+    // Keep the messages logged at `debug` level and above for `logged`.
+    pub(crate) fn capture_logs() {
+        static INIT: Once = Once::new();
+        INIT.call_once(|| {
+            log::set_logger(&TestLogger).unwrap();
+            log::set_max_level(log::LevelFilter::Debug);
+        });
+    }
+
+    #[test]
+    fn test_slow_request_warning() {
+        capture_logs();
+
+        let mut iron = Iron::new(|req: &mut Request| {
+            if req.url.path() == ["slow"] {
+                thread::sleep(Duration::from_millis(100));
+            }
+            Ok(Response::with(StatusCode::OK))
+        });
+        iron.slow_request_threshold = Some(Duration::from_millis(50));
+
+        serve(&iron, request(::Method::GET, "/fast", ""));
+        assert!(!logged("/fast"));
+
+        serve(&iron, request(::Method::GET, "/slow", ""));
+        assert!(logged("Slow request: GET /slow took"));
+    }
+
+    #[cfg(all(
+        unix,
+        not(any(target_os = "solaris", target_os = "illumos", target_os = "cygwin"))
+    ))]
+    #[test]
+    fn test_reuse_port() {
+        use std::collections::HashSet;
+
+        fn server(name: &'static str, addr: SocketAddr) -> io::Result<Listening> {
+            let mut iron =
+                Iron::new(move |_: &mut Request| Ok(Response::with((StatusCode::OK, name))));
+            iron.reuse_port = true;
+            iron.http(addr)
+        }
+
+        let first = server("first", "127.0.0.1:0".parse().unwrap()).unwrap();
+        let addr = first.socket;
+        let second = server("second", addr).unwrap();
+        assert_eq!(second.socket, addr);
+
+        let get = || {
+            let received = fetch(
+                addr,
+                b"GET / HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n",
+            );
+            received.rsplit("\r\n\r\n").next().unwrap().to_owned()
+        };
+
+        // The kernel balances connections between the servers by their
+        // client port, so both get some of them.
+        let mut served = HashSet::new();
+        for _ in 0..200 {
+            served.insert(get());
+            if served.len() == 2 {
+                break;
+            }
+        }
+        let expected: HashSet<_> = ["first", "second"].iter().map(|s| s.to_string()).collect();
+        assert_eq!(served, expected);
+
+        // Once one server is gone, the other takes every connection.
+        first.close();
+        for _ in 0..10 {
+            assert_eq!(get(), "second");
+        }
+        second.close();
+    }
+
+    // Serve `req` on a runtime, which the handler timeout's timer needs.
+    fn serve_on_runtime<H: Handler>(iron: &Iron<H>, req: HttpRequest<Body>) -> StatusCode {
+        let (tx, rx) = mpsc::channel();
+        let mut service = iron.new_service().wait().unwrap();
+        hyper::rt::run(service.call(req).then(move |res| {
+            tx.send(res.unwrap().status()).unwrap();
+            Ok(())
+        }));
+        rx.recv().unwrap()
+    }
+
+    fn deadline_in(duration: Duration) -> String {
+        let deadline = SystemTime::now() + duration;
+        let millis = deadline.duration_since(UNIX_EPOCH).unwrap().as_millis();
+        millis.to_string()
+    }
+
+    #[test]
+    fn test_request_deadline() {
+        let mut iron = Iron::new(|req: &mut Request| {
+            let remaining = req.deadline().unwrap() - Instant::now();
+            if remaining > Duration::from_millis(200) {
+                return Ok(Response::with(StatusCode::OK));
+            }
+            thread::sleep(Duration::from_millis(500));
+            Ok(Response::with(StatusCode::OK))
+        });
+        iron.timeouts.handler = Some(Duration::from_secs(2));
+        iron.deadline_header = Some(HeaderName::from_static(REQUEST_DEADLINE));
+
+        let status = serve_on_runtime(&iron, request(::Method::GET, "/", ""));
+        assert_eq!(status, StatusCode::OK);
+
+        let mut req = request(::Method::GET, "/", "");
+        req.headers_mut().insert(
+            REQUEST_DEADLINE,
+            deadline_in(Duration::from_millis(100)).parse().unwrap(),
+        );
+        let start = Instant::now();
+        let status = serve_on_runtime(&iron, req);
+        assert_eq!(status, StatusCode::GATEWAY_TIMEOUT);
+        assert!(start.elapsed() < Duration::from_millis(450));
+    }
+
+    #[test]
+    fn test_event_stream_heartbeat() {
+        let iron = Iron::new(|_: &mut Request| {
+            let (events, receiver) = mpsc::channel::<Event>();
+            // Keep the stream open for a while without sending any events.
+            thread::spawn(move || {
+                thread::sleep(Duration::from_secs(1));
+                drop(events);
+            });
+            let stream = EventStream::new(receiver).heartbeat(Duration::from_millis(100));
+            Ok(Response::with((StatusCode::OK, stream)))
+        });
+        let mut service = iron.new_service().wait().unwrap();
+
+        let start = Instant::now();
+        let res = service
+            .call(request(::Method::GET, "/events", ""))
+            .wait()
+            .unwrap();
+        assert_eq!(res.headers()[headers::CONTENT_TYPE], "text/event-stream");
+
+        let (chunk, _) = res.into_body().into_future().wait().ok().unwrap();
+        assert!(start.elapsed() < Duration::from_millis(250));
+        assert_eq!(&chunk.unwrap()[..], b": ping\n\n");
+    }
+
+    #[test]
+    fn test_event_streams_leave_pool_free() {
+        let senders = Arc::new(Mutex::new(Vec::new()));
+        let open = senders.clone();
+        let mut iron = Iron::new(move |req: &mut Request| {
+            if req.url.path() == ["events"] {
+                let (events, receiver) = mpsc::channel::<Event>();
+                open.lock().unwrap().push(events);
+                Ok(Response::with((StatusCode::OK, EventStream::new(receiver))))
+            } else {
+                Ok(Response::with((StatusCode::OK, "done")))
+            }
+        });
+        iron.pool = CpuPool::new(2);
+        let mut service = iron.new_service().wait().unwrap();
+
+        // Open as many event streams as the pool has threads.
+        let streams: Vec<_> = (0..2)
+            .map(|_| {
+                service
+                    .call(request(::Method::GET, "/events", ""))
+                    .wait()
+                    .unwrap()
+            })
+            .collect();
+
+        let (done, finished) = mpsc::channel();
+        thread::spawn(move || {
+            let res = service.call(request(::Method::GET, "/", "")).wait();
+            let _ = done.send(res.map(|res| res.status()));
+        });
+        let status = finished.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert_eq!(status.unwrap(), StatusCode::OK);
+
+        drop(streams);
+        senders.lock().unwrap().clear();
+    }
+
+    #[test]
+    fn test_metrics() {
+        let iron = Iron::new(|req: &mut Request| {
+            if req.url.path() == ["missing"] {
+                Ok(Response::with((StatusCode::NOT_FOUND, "missing")))
+            } else if req.url.path() == ["stream"] {
+                let body = response::from_iter(vec!["ab", "cd"]);
+                Ok(Response::with((StatusCode::OK, body)))
+            } else {
+                echo(req)
+            }
+        });
+
+        let mut req = post("/", "hello");
+        req.headers_mut()
+            .insert(headers::CONTENT_LENGTH, "5".parse().unwrap());
+        assert_eq!(serve(&iron, req).0, StatusCode::OK);
+        // Bodies without a `Content-Length` count as well.
+        assert_eq!(serve(&iron, post("/", "chunked")).0, StatusCode::OK);
+        let stream = request(::Method::GET, "/stream", "");
+        assert_eq!(serve(&iron, stream), (StatusCode::OK, b"abcd".to_vec()));
+        let missing = request(::Method::GET, "/missing", "");
+        assert_eq!(serve(&iron, missing).0, StatusCode::NOT_FOUND);
+
+        assert_eq!(
+            iron.metrics.snapshot(),
+            ServerMetricsSnapshot {
+                requests: 4,
+                success: 3,
+                client_error: 1,
+                bytes_in: 12,
+                bytes_out: 23,
+                ..ServerMetricsSnapshot::default()
+            }
+        );
+    }
+
+    #[test]
+    fn test_listening_metrics() {
+        let listening = Iron::new(echo).http("127.0.0.1:0").unwrap();
+
+        let received = fetch(
+            listening.socket,
+            b"POST / HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\
+              Transfer-Encoding: chunked\r\n\r\n3\r\nabc\r\n2\r\nde\r\n0\r\n\r\n",
+        );
+        assert!(received.ends_with("\r\n\r\nabcde"));
+
+        let metrics = listening.metrics();
+        assert_eq!((metrics.requests, metrics.success), (1, 1));
+        assert_eq!((metrics.bytes_in, metrics.bytes_out), (5, 5));
+        listening.close();
+    }
+
+    fn keep_alive_headers<H: Handler>(
+        service: &mut IronHandler<H>,
+    ) -> (Option<HeaderValue>, Option<HeaderValue>) {
+        let req = request(::Method::GET, "/", "");
+        let res = service.call(req).wait().unwrap();
+        (
+            res.headers().get(KEEP_ALIVE).cloned(),
+            res.headers().get(headers::CONNECTION).cloned(),
+        )
+    }
+
+    #[test]
+    fn test_keep_alive_header() {
+        let mut iron = Iron::new(|_: &mut Request| Ok(Response::with(StatusCode::OK)));
+        iron.keep_alive_header = true;
+        iron.max_requests_per_connection = Some(3);
+
+        let mut service = iron.new_service().wait().unwrap();
+        let (keep_alive, connection) = keep_alive_headers(&mut service);
+        assert_eq!(keep_alive.unwrap(), "timeout=5, max=3");
+        assert_eq!(connection, None);
+        let (keep_alive, _) = keep_alive_headers(&mut service);
+        assert_eq!(keep_alive.unwrap(), "timeout=5, max=2");
+        let (keep_alive, connection) = keep_alive_headers(&mut service);
+        assert_eq!(keep_alive, None);
+        assert_eq!(connection.unwrap(), "close");
+
+        iron.max_requests_per_connection = None;
+        let mut service = iron.new_service().wait().unwrap();
+        let (keep_alive, _) = keep_alive_headers(&mut service);
+        assert_eq!(keep_alive.unwrap(), "timeout=5");
+    }
+
+    #[test]
+    fn test_keep_alive_header_disabled() {
+        let mut iron = Iron::new(|_: &mut Request| Ok(Response::with(StatusCode::OK)));
+        iron.keep_alive_header = true;
+        iron.timeouts.keep_alive = None;
+
+        let mut service = iron.new_service().wait().unwrap();
+        assert_eq!(keep_alive_headers(&mut service), (None, None));
+
+        iron.timeouts.keep_alive = Some(Duration::from_secs(5));
+        iron.keep_alive_header = false;
+        let mut service = iron.new_service().wait().unwrap();
+        assert_eq!(keep_alive_headers(&mut service), (None, None));
+    }
+
+    #[test]
+    fn test_draining_closes_connections() {
+        let mut iron = Iron::new(|_: &mut Request| Ok(Response::with(StatusCode::OK)));
+        iron.keep_alive_header = true;
+        let shutdown = iron.shutdown.clone();
+
+        let mut service = iron.new_service().wait().unwrap();
+        let (keep_alive, connection) = keep_alive_headers(&mut service);
+        assert_eq!(keep_alive.unwrap(), "timeout=5");
+        assert_eq!(connection, None);
+
+        shutdown.initiate();
+        let (keep_alive, connection) = keep_alive_headers(&mut service);
+        assert_eq!(keep_alive, None);
+        assert_eq!(connection.unwrap(), "close");
+    }
+
+    #[test]
+    fn test_foreign_absolute_uri() {
+        let mut iron = Iron::new(|_: &mut Request| Ok(Response::with((StatusCode::OK, "ok"))));
+        iron.server_names = vec!["localhost".to_owned(), "example.com:8080".to_owned()];
+        let status = |iron: &Iron<_>, uri: &str, host: &str| {
+            let mut req = request(::Method::GET, uri, "");
+            req.headers_mut()
+                .insert(headers::HOST, host.parse().unwrap());
+            serve(iron, req).0
+        };
+
+        let cases = [
+            ("/", "other.example", 200),
+            ("http://LocalHost/", "localhost", 200),
+            ("http://localhost:80/", "localhost", 200),
+            ("http://example.com:8080/", "a", 200),
+            ("http://localhost:8080/", "localhost", 400),
+            ("http://example.com/", "example.com", 400),
+            // A `Host` header naming the same foreign host does not let it pass.
+            ("http://other.example/", "other.example", 400),
+        ];
+        for &(uri, host, expected) in &cases {
+            assert_eq!(status(&iron, uri, host).as_u16(), expected, "{}", uri);
+        }
+
+        iron.reject_foreign_absolute_uris = false;
+        let status = status(&iron, "http://other.example/", "localhost");
+        assert_eq!(status, StatusCode::OK);
+    }
+
+    #[test]
+    fn test_foreign_absolute_uri_bound_address() {
+        let iron = Iron::new(|_: &mut Request| Ok(Response::with((StatusCode::OK, "ok"))));
+        let listening = iron.http("127.0.0.1:0").unwrap();
+        let port = listening.socket.port();
+
+        let get = |uri: String| {
+            let head = "HTTP/1.1\r\nHost: a\r\nConnection: close\r\n\r\n";
+            let request = format!("GET {} {}", uri, head);
+            fetch(listening.socket, request.as_bytes())
+        };
+        assert!(get(format!("http://127.0.0.1:{}/", port)).starts_with("HTTP/1.1 200 OK"));
+        assert!(get(format!("http://localhost:{}/", port)).starts_with("HTTP/1.1 200 OK"));
+        assert!(get("http://127.0.0.1/".to_owned()).starts_with("HTTP/1.1 400 Bad Request"));
+
+        listening.close();
+    }
+}
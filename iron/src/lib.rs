@@ -31,7 +31,7 @@
 //! fn main() {
 //!     Iron::new(|_: &mut Request| {
 //!         Ok(Response::with((StatusCode::OK, "Hello World!")))
-//!     }).http("localhost:3000");
+//!     }).http("localhost:3000").unwrap();
 //! }
 //! ```
 //!
@@ -68,13 +68,48 @@
 extern crate log;
 
 // Third party packages
+extern crate base64;
+pub extern crate bytes;
+#[cfg(feature = "cookie")]
+extern crate cookie;
+#[cfg(feature = "csv")]
+extern crate csv;
+extern crate encoding_rs;
 extern crate futures;
 extern crate futures_cpupool;
+#[cfg(feature = "handlebars")]
+extern crate handlebars;
 extern crate http;
+extern crate httpdate;
 extern crate hyper;
+#[cfg(feature = "jsonschema")]
+extern crate jsonschema;
+#[cfg(feature = "jwt")]
+extern crate jsonwebtoken;
+#[cfg(unix)]
+extern crate libc;
 pub extern crate mime;
 extern crate mime_guess;
 extern crate plugin;
+#[cfg(all(test, feature = "rustls"))]
+extern crate rcgen;
+#[cfg(feature = "rustls")]
+pub extern crate rustls;
+#[cfg(feature = "serde")]
+extern crate serde;
+#[cfg(feature = "serde_json")]
+extern crate serde_json;
+extern crate sha2;
+#[cfg(unix)]
+extern crate signal_hook;
+extern crate socket2;
+#[cfg(feature = "multipart")]
+extern crate tempfile;
+extern crate tokio;
+extern crate tokio_io;
+extern crate tokio_reactor;
+extern crate tokio_tcp;
+extern crate tokio_timer;
 extern crate typemap as tmap;
 extern crate url as url_ext;
 
@@ -87,6 +122,8 @@ pub use middleware::{AfterMiddleware, AroundMiddleware, BeforeMiddleware, Chain,
 
 // Server
 pub use iron::*;
+pub use metrics::{ServerMetrics, ServerMetricsSnapshot};
+pub use shutdown::Shutdown;
 
 // Extensions
 pub use typemap::TypeMap;
@@ -164,4 +201,10 @@ pub mod modifiers;
 // Helper macros for error handling
 mod macros;
 
+mod accept;
+mod header_timeout;
 mod iron;
+mod metrics;
+mod shutdown;
+#[cfg(feature = "rustls")]
+mod tls;
@@ -0,0 +1,106 @@
+//! Counters describing the traffic a server has handled.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use futures::Stream;
+use hyper::body::Payload;
+use hyper::Body;
+
+use StatusCode;
+
+/// Counters for the requests a server has answered, shared through
+/// `Iron::metrics`.
+///
+/// Every response counts once, including `504 Gateway Timeout` responses
+/// produced by the server itself. Bytes are counted as they pass through,
+/// so chunked and streamed bodies count in full: request bodies as they are
+/// read, and response bodies as they are handed to the connection.
+#[derive(Debug, Default)]
+pub struct ServerMetrics {
+    requests: AtomicU64,
+    status_classes: [AtomicU64; 5],
+    bytes_in: AtomicU64,
+    bytes_out: AtomicU64,
+}
+
+impl ServerMetrics {
+    /// Counters starting at zero.
+    pub fn new() -> ServerMetrics {
+        ServerMetrics::default()
+    }
+
+    /// The current value of every counter.
+    pub fn snapshot(&self) -> ServerMetricsSnapshot {
+        let class = |i: usize| self.status_classes[i].load(Ordering::Relaxed);
+        ServerMetricsSnapshot {
+            requests: self.requests.load(Ordering::Relaxed),
+            informational: class(0),
+            success: class(1),
+            redirection: class(2),
+            client_error: class(3),
+            server_error: class(4),
+            bytes_in: self.bytes_in.load(Ordering::Relaxed),
+            bytes_out: self.bytes_out.load(Ordering::Relaxed),
+        }
+    }
+
+    pub(crate) fn record(&self, status: StatusCode) {
+        self.requests.fetch_add(1, Ordering::Relaxed);
+        if let Some(class) = self.status_classes.get(status.as_u16() as usize / 100 - 1) {
+            class.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
+
+// Count the bytes of a request body as it is read.
+pub(crate) fn count_in(body: Body, metrics: Arc<ServerMetrics>) -> Body {
+    Body::wrap_stream(body.map(move |chunk| {
+        let len = chunk.len() as u64;
+        metrics.bytes_in.fetch_add(len, Ordering::Relaxed);
+        chunk
+    }))
+}
+
+// Count the bytes of a response body as it is sent.
+pub(crate) fn count_out(body: Body, metrics: Arc<ServerMetrics>) -> Body {
+    // A body of known length is already complete, and wrapping it would lose
+    // the length hyper uses for `Content-Length`.
+    if let Some(len) = body.content_length() {
+        metrics.bytes_out.fetch_add(len, Ordering::Relaxed);
+        return body;
+    }
+    Body::wrap_stream(body.map(move |chunk| {
+        let len = chunk.len() as u64;
+        metrics.bytes_out.fetch_add(len, Ordering::Relaxed);
+        chunk
+    }))
+}
+
+/// The value of the `ServerMetrics` counters at one point in time.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ServerMetricsSnapshot {
+    /// The number of requests answered.
+    pub requests: u64,
+
+    /// The number of `1xx` responses.
+    pub informational: u64,
+
+    /// The number of `2xx` responses.
+    pub success: u64,
+
+    /// The number of `3xx` responses.
+    pub redirection: u64,
+
+    /// The number of `4xx` responses.
+    pub client_error: u64,
+
+    /// The number of `5xx` responses.
+    pub server_error: u64,
+
+    /// The number of request body bytes received.
+    pub bytes_in: u64,
+
+    /// The number of response body bytes sent.
+    pub bytes_out: u64,
+}
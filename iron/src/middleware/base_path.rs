@@ -0,0 +1,129 @@
+use std::error::Error;
+use std::fmt;
+
+use typemap::Key;
+
+use {Handler, IronError, IronResult, Request, Response, StatusCode};
+
+/// A `Handler` serving another handler under a static base path.
+///
+/// This is a lightweight alternative to the `mount` crate for applications
+/// deployed under a single prefix. For a `BasePath` on `/myapp`, a request
+/// for `/myapp/users` reaches the inner handler as `/users`. Requests outside
+/// the base path are rejected with `404 Not Found`.
+///
+/// The prefix is available to the inner handler through `Request::base_path`,
+/// so that generated URLs can include it again.
+pub struct BasePath<H> {
+    prefix: String,
+    segments: Vec<String>,
+    handler: H,
+}
+
+impl<H: Handler> BasePath<H> {
+    /// Serve `handler` under `prefix`, e.g. `"/myapp"`.
+    pub fn new(prefix: &str, handler: H) -> BasePath<H> {
+        let segments: Vec<String> = prefix
+            .split('/')
+            .filter(|segment| !segment.is_empty())
+            .map(String::from)
+            .collect();
+        let prefix = segments.iter().map(|segment| format!("/{}", segment)).collect();
+
+        BasePath {
+            prefix,
+            segments,
+            handler,
+        }
+    }
+}
+
+impl<H: Handler> Handler for BasePath<H> {
+    fn handle(&self, req: &mut Request) -> IronResult<Response> {
+        let path = {
+            let path = req.url.path();
+            if path.len() < self.segments.len()
+                || path.iter().zip(&self.segments).any(|(a, b)| a != b)
+            {
+                return Err(IronError::new(OutsideBasePath, StatusCode::NOT_FOUND));
+            }
+            path[self.segments.len()..].join("/")
+        };
+
+        let original_url = req.url.clone();
+        let outer_prefix = req.extensions.remove::<BasePathKey>();
+
+        req.url.as_mut().set_path(&path);
+        req.extensions.insert::<BasePathKey>(format!(
+            "{}{}",
+            outer_prefix.as_ref().map_or("", |prefix| &**prefix),
+            self.prefix
+        ));
+
+        let res = self.handler.handle(req);
+
+        // Restore the request for any middleware running after this handler.
+        req.url = original_url;
+        match outer_prefix {
+            Some(prefix) => req.extensions.insert::<BasePathKey>(prefix),
+            None => req.extensions.remove::<BasePathKey>(),
+        };
+
+        res
+    }
+}
+
+pub(crate) struct BasePathKey;
+
+impl Key for BasePathKey {
+    type Value = String;
+}
+
+/// The error returned by `BasePath` for requests outside its base path.
+#[derive(Debug)]
+pub struct OutsideBasePath;
+
+impl fmt::Display for OutsideBasePath {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("The request path is outside the base path")
+    }
+}
+
+impl Error for OutsideBasePath {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use request::Url;
+
+    fn request(path: &str) -> Request {
+        let mut req = Request::stub();
+        req.url = Url::parse(&format!("http://localhost{}", path)).unwrap();
+        req
+    }
+
+    fn routes(req: &mut Request) -> IronResult<Response> {
+        assert_eq!(req.base_path(), Some("/myapp"));
+        if req.url.path() == ["users"] {
+            Ok(Response::with((StatusCode::OK, "users")))
+        } else {
+            Ok(Response::with(StatusCode::NOT_FOUND))
+        }
+    }
+
+    #[test]
+    fn test_base_path_routing() {
+        let app = BasePath::new("/myapp", routes);
+
+        let mut req = request("/myapp/users");
+        let res = app.handle(&mut req).unwrap();
+        assert_eq!(res.status, Some(StatusCode::OK));
+        assert_eq!(req.url.path(), ["myapp", "users"]);
+        assert_eq!(req.base_path(), None);
+
+        let mut req = request("/users");
+        let err = app.handle(&mut req).unwrap_err();
+        assert_eq!(err.response.status, Some(StatusCode::NOT_FOUND));
+    }
+}
@@ -0,0 +1,167 @@
+use std::error::Error;
+use std::fmt;
+
+use typemap::Key;
+use url_ext::form_urlencoded;
+
+use {headers, mime, BeforeMiddleware, IronError, IronResult, Method, Request, StatusCode};
+
+/// The form field a CSRF token is read from.
+pub const CSRF_FORM_FIELD: &str = "csrf_token";
+
+/// The header a CSRF token is read from.
+pub const CSRF_HEADER: &str = "x-csrf-token";
+
+/// `BeforeMiddleware` protecting against cross-site request forgery.
+///
+/// The token belonging to the client's session is looked up with the
+/// function passed to `Csrf::new`, which is where a session store plugs in.
+/// It is exposed to handlers through `Request::csrf_token` so it can be
+/// embedded in forms.
+///
+/// Requests with an unsafe method must send the same token in the
+/// `X-CSRF-Token` header or the `csrf_token` field of a
+/// `application/x-www-form-urlencoded` body, otherwise they are rejected with
+/// `403 Forbidden`. `GET`, `HEAD`, `OPTIONS` and `TRACE` requests are exempt.
+pub struct Csrf<F> {
+    session_token: F,
+}
+
+impl<F> Csrf<F>
+where
+    F: Fn(&Request) -> Option<String> + Send + Sync + 'static,
+{
+    /// Create a CSRF middleware reading the session's token with `session_token`.
+    pub fn new(session_token: F) -> Csrf<F> {
+        Csrf { session_token }
+    }
+}
+
+impl<F> BeforeMiddleware for Csrf<F>
+where
+    F: Fn(&Request) -> Option<String> + Send + Sync + 'static,
+{
+    fn before(&self, req: &mut Request) -> IronResult<()> {
+        let expected = (self.session_token)(req);
+        if let Some(ref token) = expected {
+            req.extensions.insert::<CsrfToken>(token.clone());
+        }
+
+        if is_safe(&req.method) {
+            return Ok(());
+        }
+
+        let submitted = submitted_token(req);
+        match (expected, submitted) {
+            (Some(ref expected), Some(ref submitted))
+                if constant_time_eq(expected.as_bytes(), submitted.as_bytes()) =>
+            {
+                Ok(())
+            }
+            _ => Err(IronError::new(CsrfError, StatusCode::FORBIDDEN)),
+        }
+    }
+}
+
+pub(crate) struct CsrfToken;
+
+impl Key for CsrfToken {
+    type Value = String;
+}
+
+fn is_safe(method: &Method) -> bool {
+    matches!(
+        *method,
+        Method::GET | Method::HEAD | Method::OPTIONS | Method::TRACE
+    )
+}
+
+fn submitted_token(req: &mut Request) -> Option<String> {
+    if let Some(token) = req.headers.get(CSRF_HEADER) {
+        return token.to_str().ok().map(String::from);
+    }
+
+    let is_form = req
+        .headers
+        .get(headers::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse::<mime::Mime>().ok())
+        .is_some_and(|mime| mime.essence_str() == "application/x-www-form-urlencoded");
+    if !is_form {
+        return None;
+    }
+
+    let body = req.get_body_contents().ok()?;
+    form_urlencoded::parse(body)
+        .find(|(name, _)| name == CSRF_FORM_FIELD)
+        .map(|(_, value)| value.into_owned())
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+/// The error produced when a request's CSRF token is missing or wrong.
+#[derive(Debug)]
+pub struct CsrfError;
+
+impl fmt::Display for CsrfError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("Missing or invalid CSRF token")
+    }
+}
+
+impl Error for CsrfError {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use hyper::Body;
+
+    fn csrf() -> Csrf<fn(&Request) -> Option<String>> {
+        fn session_token(_: &Request) -> Option<String> {
+            Some("s3cr3t".into())
+        }
+        Csrf::new(session_token)
+    }
+
+    fn post(body: &'static str) -> Request {
+        let mut req = Request::stub();
+        req.method = Method::POST;
+        req.headers.insert(
+            headers::CONTENT_TYPE,
+            "application/x-www-form-urlencoded".parse().unwrap(),
+        );
+        req.body = Some(Body::from(body));
+        req
+    }
+
+    #[test]
+    fn test_valid_token() {
+        let mut req = post("name=iron&csrf_token=s3cr3t");
+        assert!(csrf().before(&mut req).is_ok());
+
+        let mut req = post("");
+        req.headers.insert(CSRF_HEADER, "s3cr3t".parse().unwrap());
+        assert!(csrf().before(&mut req).is_ok());
+    }
+
+    #[test]
+    fn test_missing_token() {
+        let mut req = post("name=iron");
+        let err = csrf().before(&mut req).unwrap_err();
+        assert_eq!(err.response.status, Some(StatusCode::FORBIDDEN));
+
+        let mut req = post("csrf_token=wrong");
+        let err = csrf().before(&mut req).unwrap_err();
+        assert_eq!(err.response.status, Some(StatusCode::FORBIDDEN));
+    }
+
+    #[test]
+    fn test_safe_methods_exempt() {
+        let mut req = Request::stub();
+        assert!(csrf().before(&mut req).is_ok());
+        assert_eq!(req.csrf_token(), Some("s3cr3t"));
+    }
+}
@@ -0,0 +1,136 @@
+use std::error::Error;
+use std::fmt;
+use std::sync::Arc;
+
+use handlebars::{Handlebars, RenderError};
+
+use modifiers::Template;
+use {mime, AfterMiddleware, IronError, IronResult, Request, Response, Set, StatusCode};
+
+/// `AfterMiddleware` rendering `Template` responses with Handlebars.
+///
+/// Responses carrying a `Template` get the rendered HTML as their body and
+/// `Content-Type: text/html; charset=utf-8`. If rendering fails the request
+/// fails with `500 Internal Server Error`.
+///
+/// ```ignore
+/// let mut registry = Handlebars::new();
+/// registry.register_template_string("hello", "<p>Hello, {{name}}!</p>").unwrap();
+///
+/// let mut chain = Chain::new(|_: &mut Request| {
+///     Ok(Response::with((StatusCode::OK, Template::new("hello", &json!({"name": "iron"})))))
+/// });
+/// chain.link_after(HandlebarsEngine::new(registry));
+/// ```
+pub struct HandlebarsEngine {
+    registry: Arc<Handlebars<'static>>,
+}
+
+impl HandlebarsEngine {
+    /// Render templates with the templates registered in `registry`.
+    pub fn new(registry: Handlebars<'static>) -> HandlebarsEngine {
+        HandlebarsEngine {
+            registry: Arc::new(registry),
+        }
+    }
+
+    /// The registry templates are rendered with.
+    pub fn registry(&self) -> &Arc<Handlebars<'static>> {
+        &self.registry
+    }
+}
+
+impl AfterMiddleware for HandlebarsEngine {
+    fn after(&self, _: &mut Request, mut res: Response) -> IronResult<Response> {
+        let Template { name, context } = match res.extensions.remove::<Template>() {
+            Some(template) => template,
+            None => return Ok(res),
+        };
+
+        let rendered = context
+            .map_err(TemplateError::Context)
+            .and_then(|context| {
+                self.registry
+                    .render(&name, &context)
+                    .map_err(TemplateError::Render)
+            });
+
+        match rendered {
+            Ok(body) => Ok(res.set(mime::TEXT_HTML_UTF_8).set(body)),
+            Err(e) => Err(IronError::new(e, StatusCode::INTERNAL_SERVER_ERROR)),
+        }
+    }
+}
+
+/// The error produced when a `Template` cannot be rendered.
+#[derive(Debug)]
+pub enum TemplateError {
+    /// The context could not be serialized.
+    Context(::serde_json::Error),
+
+    /// Handlebars failed to render the template.
+    Render(RenderError),
+}
+
+impl fmt::Display for TemplateError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            TemplateError::Context(ref e) => write!(f, "Invalid template context: {}", e),
+            TemplateError::Render(ref e) => write!(f, "Template rendering failed: {}", e),
+        }
+    }
+}
+
+impl Error for TemplateError {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use serde::Serialize;
+
+    use {headers, Chain, Handler};
+
+    #[derive(Serialize)]
+    struct Greeting {
+        name: &'static str,
+    }
+
+    fn engine() -> HandlebarsEngine {
+        let mut registry = Handlebars::new();
+        registry
+            .register_template_string("hello", "<p>Hello, {{name}}!</p>")
+            .unwrap();
+        HandlebarsEngine::new(registry)
+    }
+
+    #[test]
+    fn test_render_template() {
+        let mut chain = Chain::new(|_: &mut Request| {
+            let greeting = Greeting { name: "<iron>" };
+            Ok(Response::with((StatusCode::OK, Template::new("hello", &greeting))))
+        });
+        chain.link_after(engine());
+
+        let mut res = chain.handle(&mut Request::stub()).unwrap();
+        assert_eq!(
+            res.headers[headers::CONTENT_TYPE],
+            "text/html; charset=utf-8"
+        );
+
+        let mut body = vec![];
+        res.body.as_mut().unwrap().write_body(&mut body).unwrap();
+        assert_eq!(body, b"<p>Hello, &lt;iron&gt;!</p>");
+    }
+
+    #[test]
+    fn test_missing_template() {
+        let mut chain = Chain::new(|_: &mut Request| {
+            Ok(Response::with((StatusCode::OK, Template::new("missing", &()))))
+        });
+        chain.link_after(engine());
+
+        let err = chain.handle(&mut Request::stub()).unwrap_err();
+        assert_eq!(err.response.status, Some(StatusCode::INTERNAL_SERVER_ERROR));
+    }
+}
@@ -0,0 +1,167 @@
+use std::fmt::Display;
+
+use futures::future::{self, Future, IntoFuture};
+use mime;
+
+use {Handler, IronResult, Request, Response, StatusCode};
+
+type Check = dyn Fn() -> Box<dyn Future<Item = (), Error = String> + Send> + Send + Sync;
+
+/// A `Handler` answering liveness and readiness probes.
+///
+/// Requests whose path ends in `healthz` are liveness probes, answered with
+/// `200 OK` as long as the server runs. Requests whose path ends in `readyz`
+/// are readiness probes, which run all checks concurrently: the answer is
+/// `200 OK` if every check succeeds, and `503 Service Unavailable` with the
+/// failing checks otherwise. Both answer with a JSON body. Other paths are
+/// not found.
+///
+/// ```ignore
+/// let mut health = HealthCheck::new();
+/// health.check("database", move || pool.ping());
+/// router.any("/:probe", health, "health");
+/// ```
+#[derive(Default)]
+pub struct HealthCheck {
+    checks: Vec<(String, Box<Check>)>,
+}
+
+impl HealthCheck {
+    /// A `HealthCheck` without any checks, so the server is always ready.
+    pub fn new() -> HealthCheck {
+        HealthCheck::default()
+    }
+
+    /// Add the readiness check `name`, which fails if the future returned by
+    /// `check` fails.
+    ///
+    /// The future is waited on by the thread handling the probe.
+    pub fn check<F, R>(&mut self, name: &str, check: F) -> &mut HealthCheck
+    where
+        F: Fn() -> R + Send + Sync + 'static,
+        R: IntoFuture<Item = ()>,
+        R::Future: Send + 'static,
+        R::Error: Display,
+    {
+        let check = move || -> Box<dyn Future<Item = (), Error = String> + Send> {
+            Box::new(check().into_future().map_err(|e| e.to_string()))
+        };
+        self.checks.push((name.to_owned(), Box::new(check)));
+        self
+    }
+
+    // The checks which failed, with their errors.
+    fn failing(&self) -> Vec<(&str, String)> {
+        let results = self
+            .checks
+            .iter()
+            .map(|(_, check)| check().then(Ok::<_, ()>));
+        // Errors are turned into results above, so this cannot fail.
+        let results = future::join_all(results).wait().unwrap_or_default();
+
+        self.checks
+            .iter()
+            .zip(results)
+            .filter_map(|((name, _), result)| result.err().map(|e| (&**name, e)))
+            .collect()
+    }
+}
+
+impl Handler for HealthCheck {
+    fn handle(&self, req: &mut Request) -> IronResult<Response> {
+        let probe = req.url.path().last().cloned().unwrap_or_default();
+        let (status, body) = match probe {
+            "healthz" => (StatusCode::OK, "{\"status\":\"ok\"}".to_owned()),
+            "readyz" => {
+                let failing = self.failing();
+                if failing.is_empty() {
+                    (StatusCode::OK, "{\"status\":\"ok\"}".to_owned())
+                } else {
+                    let failing = failing
+                        .iter()
+                        .map(|(name, e)| {
+                            format!(
+                                "{{\"name\":{},\"error\":{}}}",
+                                json_string(name),
+                                json_string(e)
+                            )
+                        })
+                        .collect::<Vec<_>>();
+                    let body = format!(
+                        "{{\"status\":\"unavailable\",\"failing\":[{}]}}",
+                        failing.join(",")
+                    );
+                    (StatusCode::SERVICE_UNAVAILABLE, body)
+                }
+            }
+            _ => return Ok(Response::with(StatusCode::NOT_FOUND)),
+        };
+        Ok(Response::with((status, mime::APPLICATION_JSON, body)))
+    }
+}
+
+// `s` as a JSON string literal.
+fn json_string(s: &str) -> String {
+    let mut json = String::with_capacity(s.len() + 2);
+    json.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            c if c < ' ' => json.push_str(&format!("\\u{:04x}", c as u32)),
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+    json
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use request::Url;
+
+    fn probe(health: &HealthCheck, path: &str) -> (StatusCode, String) {
+        let mut req = Request::stub();
+        req.url = Url::parse(&format!("http://localhost{}", path)).unwrap();
+        let mut res = health.handle(&mut req).unwrap();
+
+        let mut body = vec![];
+        if let Some(ref mut response_body) = res.body {
+            response_body.write_body(&mut body).unwrap();
+        }
+        (res.status.unwrap(), String::from_utf8(body).unwrap())
+    }
+
+    #[test]
+    fn test_all_checks_pass() {
+        let mut health = HealthCheck::new();
+        health
+            .check("database", || Ok::<(), String>(()))
+            .check("cache", || future::ok::<(), String>(()));
+
+        let (status, body) = probe(&health, "/readyz");
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body, "{\"status\":\"ok\"}");
+    }
+
+    #[test]
+    fn test_failing_check() {
+        let mut health = HealthCheck::new();
+        health
+            .check("database", || Ok::<(), String>(()))
+            .check("cache", || Err::<(), _>("connection \"refused\""));
+
+        let (status, body) = probe(&health, "/readyz");
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(
+            body,
+            "{\"status\":\"unavailable\",\"failing\":[{\"name\":\"cache\",\"error\":\"connection \\\"refused\\\"\"}]}"
+        );
+
+        // Liveness does not depend on the checks.
+        assert_eq!(probe(&health, "/healthz").0, StatusCode::OK);
+        assert_eq!(probe(&health, "/metrics").0, StatusCode::NOT_FOUND);
+    }
+}
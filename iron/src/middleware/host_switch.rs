@@ -0,0 +1,74 @@
+use std::collections::HashMap;
+
+use {Handler, IronResult, Request, Response};
+
+/// A `Handler` dispatching requests to other handlers by their host.
+///
+/// Hosts are matched exactly, ignoring case and port. Requests for any
+/// other host go to the fallback handler.
+///
+/// ```ignore
+/// let mut switch = HostSwitch::new(not_found);
+/// switch.host("api.example.com", api).host("www.example.com", site);
+/// ```
+pub struct HostSwitch {
+    hosts: HashMap<String, Box<dyn Handler>>,
+    fallback: Box<dyn Handler>,
+}
+
+impl HostSwitch {
+    /// Create a `HostSwitch` sending requests for unknown hosts to `fallback`.
+    pub fn new<H: Handler>(fallback: H) -> HostSwitch {
+        HostSwitch {
+            hosts: HashMap::new(),
+            fallback: Box::new(fallback),
+        }
+    }
+
+    /// Handle requests for `host` with `handler`.
+    ///
+    /// A handler added for the same host before is replaced.
+    pub fn host<H: Handler>(&mut self, host: &str, handler: H) -> &mut HostSwitch {
+        self.hosts.insert(host.to_ascii_lowercase(), Box::new(handler));
+        self
+    }
+}
+
+impl Handler for HostSwitch {
+    fn handle(&self, req: &mut Request) -> IronResult<Response> {
+        let host = req.url.host().to_string().to_ascii_lowercase();
+        self.hosts
+            .get(&host)
+            .unwrap_or(&self.fallback)
+            .handle(req)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use request::Url;
+
+    fn handle(switch: &HostSwitch, url: &str) -> String {
+        let mut req = Request::stub();
+        req.url = Url::parse(url).unwrap();
+        let mut res = switch.handle(&mut req).unwrap();
+
+        let mut body = vec![];
+        res.body.as_mut().unwrap().write_body(&mut body).unwrap();
+        String::from_utf8(body).unwrap()
+    }
+
+    #[test]
+    fn test_host_switch() {
+        let mut switch = HostSwitch::new(|_: &mut Request| Ok(Response::with("fallback")));
+        switch
+            .host("a.example.com", |_: &mut Request| Ok(Response::with("a")))
+            .host("B.example.com", |_: &mut Request| Ok(Response::with("b")));
+
+        assert_eq!(handle(&switch, "http://a.example.com/"), "a");
+        assert_eq!(handle(&switch, "http://b.example.com:8080/path"), "b");
+        assert_eq!(handle(&switch, "http://c.example.com/"), "fallback");
+    }
+}
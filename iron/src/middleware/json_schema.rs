@@ -0,0 +1,159 @@
+use std::error::Error;
+use std::fmt;
+
+use jsonschema::{self, Validator};
+use serde_json::{self, Map, Value};
+
+use {mime, BeforeMiddleware, IronError, IronResult, Request, StatusCode};
+
+/// `BeforeMiddleware` validating JSON request bodies against a JSON Schema.
+///
+/// Bodies which are not valid JSON are rejected with `400 Bad Request`, and
+/// bodies which cannot be read with the status of the `BodyError`, e.g.
+/// `413 Payload Too Large` past the request's body limit. Bodies which do
+/// not satisfy the schema are rejected with `422 Unprocessable Entity`
+/// and a JSON body listing each validation error:
+///
+/// ```plain
+/// {"errors": [{"path": "/name", "message": "\"name\" is a required property"}]}
+/// ```
+///
+/// The body stays available to the handler through `Request::get_body_contents`.
+pub struct JsonSchema {
+    validator: Validator,
+}
+
+impl JsonSchema {
+    /// Compile `schema` into a validating middleware.
+    ///
+    /// Fails if `schema` is not itself a valid JSON Schema.
+    pub fn new(schema: &Value) -> Result<JsonSchema, SchemaError> {
+        let validator = jsonschema::validator_for(schema).map_err(|e| SchemaError {
+            errors: vec![e.to_string()],
+        })?;
+        Ok(JsonSchema { validator })
+    }
+}
+
+impl BeforeMiddleware for JsonSchema {
+    fn before(&self, req: &mut Request) -> IronResult<()> {
+        let body = req.get_body_contents()?;
+        let instance: Value = serde_json::from_slice(body)
+            .map_err(|e| IronError::new(e, StatusCode::BAD_REQUEST))?;
+
+        let violations: Vec<(String, String)> = self
+            .validator
+            .iter_errors(&instance)
+            .map(|e| (e.instance_path.to_string(), e.to_string()))
+            .collect();
+
+        if violations.is_empty() {
+            return Ok(());
+        }
+
+        let errors = violations
+            .iter()
+            .map(|(path, message)| {
+                let mut error = Map::new();
+                error.insert("path".into(), path.clone().into());
+                error.insert("message".into(), message.clone().into());
+                Value::Object(error)
+            }).collect();
+        let mut body = Map::new();
+        body.insert("errors".into(), Value::Array(errors));
+
+        let err = SchemaError {
+            errors: violations.into_iter().map(|(_, message)| message).collect(),
+        };
+        Err(IronError::new(
+            err,
+            (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                mime::APPLICATION_JSON,
+                Value::Object(body).to_string(),
+            ),
+        ))
+    }
+}
+
+/// The error produced when a schema or a request body fails validation.
+#[derive(Debug)]
+pub struct SchemaError {
+    /// A description of each validation failure.
+    pub errors: Vec<String>,
+}
+
+impl fmt::Display for SchemaError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "JSON Schema validation failed: {}", self.errors.join("; "))
+    }
+}
+
+impl Error for SchemaError {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use hyper::Body;
+
+    use Method;
+
+    fn schema() -> JsonSchema {
+        JsonSchema::new(
+            &serde_json::from_str(
+                r#"{
+                    "type": "object",
+                    "properties": {"name": {"type": "string"}},
+                    "required": ["name"]
+                }"#,
+            ).unwrap(),
+        ).unwrap()
+    }
+
+    fn request(body: &'static str) -> Request {
+        let mut req = Request::stub();
+        req.method = Method::POST;
+        req.body = Some(Body::from(body));
+        req
+    }
+
+    #[test]
+    fn test_valid_payload() {
+        let mut req = request(r#"{"name": "iron"}"#);
+        assert!(schema().before(&mut req).is_ok());
+        assert_eq!(req.get_body_contents().unwrap(), br#"{"name": "iron"}"#);
+    }
+
+    #[test]
+    fn test_invalid_payload() {
+        let mut req = request(r#"{"name": 3}"#);
+        let mut err = schema().before(&mut req).unwrap_err();
+        assert_eq!(err.response.status, Some(StatusCode::UNPROCESSABLE_ENTITY));
+
+        let mut body = vec![];
+        err.response
+            .body
+            .as_mut()
+            .unwrap()
+            .write_body(&mut body)
+            .unwrap();
+        let body: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["errors"][0]["path"], "/name");
+    }
+
+    #[test]
+    fn test_payload_too_large() {
+        let mut req = request(r#"{"name": "iron"}"#);
+        req.body_limit = Some(4);
+        let err = schema().before(&mut req).unwrap_err();
+        assert_eq!(err.response.status, Some(StatusCode::PAYLOAD_TOO_LARGE));
+    }
+
+    #[test]
+    fn test_malformed_payload() {
+        let mut req = request("{");
+        let err = schema().before(&mut req).unwrap_err();
+        assert_eq!(err.response.status, Some(StatusCode::BAD_REQUEST));
+    }
+}
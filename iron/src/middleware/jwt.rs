@@ -0,0 +1,180 @@
+use std::error::Error;
+use std::fmt;
+
+use jsonwebtoken::{self, DecodingKey, Validation};
+use serde::de::DeserializeOwned;
+use serde_json::{self, Value};
+use typemap::Key;
+
+use modifiers::{Challenge, WwwAuthenticate};
+use {headers, BeforeMiddleware, IronError, IronResult, Request, StatusCode};
+
+/// `BeforeMiddleware` verifying a JSON Web Token sent as a bearer token.
+///
+/// The token is read from an `Authorization: Bearer <token>` header and
+/// verified with the configured key and `Validation`, which checks the
+/// algorithm and the `exp` claim by default. Requests without a valid token
+/// fail with `401 Unauthorized` and a `WWW-Authenticate: Bearer` challenge.
+/// The claims of a valid token are available through `Request::claims`.
+///
+/// ```ignore
+/// let jwt = Jwt::new(DecodingKey::from_secret(b"secret"), Validation::new(Algorithm::HS256));
+/// chain.link_before(jwt);
+/// ```
+pub struct Jwt {
+    key: DecodingKey,
+    validation: Validation,
+}
+
+impl Jwt {
+    /// Verify tokens with `key` according to `validation`.
+    pub fn new(key: DecodingKey, validation: Validation) -> Jwt {
+        Jwt { key, validation }
+    }
+}
+
+impl BeforeMiddleware for Jwt {
+    fn before(&self, req: &mut Request) -> IronResult<()> {
+        let token = req
+            .headers
+            .get(headers::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(bearer_token)
+            .ok_or(JwtError::Missing)
+            .map_err(unauthorized)?;
+
+        let claims = jsonwebtoken::decode::<Value>(token, &self.key, &self.validation)
+            .map_err(|e| unauthorized(JwtError::Invalid(e)))?
+            .claims;
+        req.extensions.insert::<Claims>(claims);
+        Ok(())
+    }
+}
+
+// The token of a `Bearer` authorization, whose scheme is case-insensitive.
+fn bearer_token(authorization: &str) -> Option<&str> {
+    let mut parts = authorization.trim().splitn(2, ' ');
+    match (parts.next(), parts.next()) {
+        (Some(scheme), Some(token)) if scheme.eq_ignore_ascii_case("bearer") => Some(token.trim()),
+        _ => None,
+    }
+}
+
+fn unauthorized(e: JwtError) -> IronError {
+    let challenge = match e {
+        JwtError::Invalid(_) => Challenge::new("Bearer").param("error", "invalid_token"),
+        _ => Challenge::new("Bearer"),
+    };
+    IronError::new(
+        e,
+        (StatusCode::UNAUTHORIZED, WwwAuthenticate(vec![challenge])),
+    )
+}
+
+/// The key of `Request::extensions` holding the claims of a verified token.
+pub(crate) struct Claims;
+
+impl Key for Claims {
+    type Value = Value;
+}
+
+pub(crate) fn claims<T: DeserializeOwned>(req: &Request) -> Result<T, JwtError> {
+    let claims = req.extensions.get::<Claims>().ok_or(JwtError::Missing)?;
+    T::deserialize(claims).map_err(JwtError::Claims)
+}
+
+/// The error produced when a request carries no valid JSON Web Token.
+#[derive(Debug)]
+pub enum JwtError {
+    /// The request has no bearer token, or it was not verified by `Jwt`.
+    Missing,
+
+    /// The token is malformed, expired or has an invalid signature.
+    Invalid(jsonwebtoken::errors::Error),
+
+    /// The claims do not have the requested shape.
+    Claims(serde_json::Error),
+}
+
+impl fmt::Display for JwtError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            JwtError::Missing => f.write_str("Missing bearer token"),
+            JwtError::Invalid(ref e) => write!(f, "Invalid bearer token: {}", e),
+            JwtError::Claims(ref e) => write!(f, "Unexpected token claims: {}", e),
+        }
+    }
+}
+
+impl Error for JwtError {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    use jsonwebtoken::{Algorithm, EncodingKey, Header};
+    use serde::{Deserialize, Serialize};
+
+    const SECRET: &[u8] = b"secret";
+
+    #[derive(Debug, Deserialize, PartialEq, Serialize)]
+    struct User {
+        sub: String,
+        exp: u64,
+    }
+
+    fn user(expires_in: i64) -> User {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
+        User {
+            sub: "alice".to_owned(),
+            exp: (now.as_secs() as i64 + expires_in) as u64,
+        }
+    }
+
+    fn authenticate(user: &User) -> IronResult<Request> {
+        let key = EncodingKey::from_secret(SECRET);
+        let token = jsonwebtoken::encode(&Header::default(), user, &key).unwrap();
+        let mut req = Request::stub();
+        req.headers.insert(
+            headers::AUTHORIZATION,
+            format!("Bearer {}", token).parse().unwrap(),
+        );
+
+        let jwt = Jwt::new(
+            DecodingKey::from_secret(SECRET),
+            Validation::new(Algorithm::HS256),
+        );
+        jwt.before(&mut req).map(|()| req)
+    }
+
+    #[test]
+    fn test_valid_token() {
+        let user = user(3600);
+        let req = authenticate(&user).unwrap();
+        assert_eq!(req.claims::<User>().unwrap(), user);
+    }
+
+    #[test]
+    fn test_expired_token() {
+        let err = authenticate(&user(-3600)).unwrap_err();
+        assert_eq!(err.response.status, Some(StatusCode::UNAUTHORIZED));
+        assert_eq!(
+            err.response.headers[headers::WWW_AUTHENTICATE],
+            "Bearer error=\"invalid_token\""
+        );
+    }
+
+    #[test]
+    fn test_missing_token() {
+        let jwt = Jwt::new(
+            DecodingKey::from_secret(SECRET),
+            Validation::new(Algorithm::HS256),
+        );
+        let mut req = Request::stub();
+        let err = jwt.before(&mut req).unwrap_err();
+        assert_eq!(err.response.status, Some(StatusCode::UNAUTHORIZED));
+        assert!(req.claims::<User>().is_err());
+    }
+}
@@ -131,10 +131,44 @@
 use std::sync::Arc;
 use {IronError, IronResult, Request, Response};
 
+pub use self::base_path::{BasePath, OutsideBasePath};
+pub use self::csrf::{Csrf, CsrfError, CSRF_FORM_FIELD, CSRF_HEADER};
+#[cfg(feature = "handlebars")]
+pub use self::handlebars::{HandlebarsEngine, TemplateError};
+pub use self::health::HealthCheck;
+pub use self::host_switch::HostSwitch;
+pub use self::or_not_found::OrNotFound;
+pub use self::retry::Retry;
+pub use self::secure::{RequireSecure, SecureOr};
+pub use self::server_timing::{ServerTiming, SERVER_TIMING};
+#[cfg(feature = "cookie")]
+pub use self::signed_cookies::{MissingSigningKey, SignedCookies, SigningKey};
+#[cfg(feature = "jsonschema")]
+pub use self::json_schema::{JsonSchema, SchemaError};
+#[cfg(feature = "jwt")]
+pub use self::jwt::{Jwt, JwtError};
+
+pub(crate) mod base_path;
+pub(crate) mod csrf;
+#[cfg(feature = "handlebars")]
+mod handlebars;
+mod health;
+mod host_switch;
+mod or_not_found;
+mod retry;
+mod secure;
+pub(crate) mod server_timing;
+#[cfg(feature = "cookie")]
+pub(crate) mod signed_cookies;
+#[cfg(feature = "jsonschema")]
+mod json_schema;
+#[cfg(feature = "jwt")]
+pub(crate) mod jwt;
+
 /// `Handler`s are responsible for handling requests by creating Responses from Requests.
 pub trait Handler: Send + Sync + 'static {
     /// Produce a `Response` from a Request, with the possibility of error.
-    fn handle(&self, &mut Request) -> IronResult<Response>;
+    fn handle(&self, _: &mut Request) -> IronResult<Response>;
 }
 
 /// `BeforeMiddleware` are fired before a `Handler` is called inside of a Chain.
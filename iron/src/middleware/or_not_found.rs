@@ -0,0 +1,60 @@
+use {Handler, IronResult, Request, Response, StatusCode};
+
+/// A `Handler` for lookups, answering `404 Not Found` when the wrapped
+/// function finds nothing.
+///
+/// The function returns `Ok(Some(response))` with the response to send and
+/// `Ok(None)` if there is no such resource. Errors are returned as they are.
+///
+/// ```ignore
+/// router.get("/users/:id", OrNotFound(|req: &mut Request| {
+///     let id = req.extensions.get::<Router>().unwrap().find("id").unwrap();
+///     Ok(users.get(id).map(|user| Response::with((StatusCode::OK, user.name.clone()))))
+/// }), "user");
+/// ```
+pub struct OrNotFound<F>(pub F);
+
+impl<F> Handler for OrNotFound<F>
+where
+    F: Send + Sync + 'static + Fn(&mut Request) -> IronResult<Option<Response>>,
+{
+    fn handle(&self, req: &mut Request) -> IronResult<Response> {
+        Ok((self.0)(req)?.unwrap_or_else(|| Response::with(StatusCode::NOT_FOUND)))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    // Finds the resource at `/found` only.
+    fn lookup(req: &mut Request) -> IronResult<Option<Response>> {
+        if req.url.path() == ["found"] {
+            Ok(Some(Response::with((StatusCode::OK, "found"))))
+        } else {
+            Ok(None)
+        }
+    }
+
+    #[test]
+    fn test_some() {
+        let mut req = Request::stub();
+        req.url = "http://localhost/found".parse().unwrap();
+
+        let mut res = OrNotFound(lookup).handle(&mut req).unwrap();
+        assert_eq!(res.status, Some(StatusCode::OK));
+        let mut body = vec![];
+        res.body.as_mut().unwrap().write_body(&mut body).unwrap();
+        assert_eq!(body, b"found");
+    }
+
+    #[test]
+    fn test_none() {
+        let mut req = Request::stub();
+        req.url = "http://localhost/missing".parse().unwrap();
+
+        let res = OrNotFound(lookup).handle(&mut req).unwrap();
+        assert_eq!(res.status, Some(StatusCode::NOT_FOUND));
+        assert!(res.body.is_none());
+    }
+}
@@ -0,0 +1,160 @@
+use std::thread;
+use std::time::{Duration, Instant};
+
+use {Handler, IronError, IronResult, Request, Response, StatusCode};
+
+/// A `Handler` retrying another handler when its upstream fails transiently.
+///
+/// Meant for proxy and gateway handlers: an attempt failing with
+/// `502 Bad Gateway`, `503 Service Unavailable` or `504 Gateway Timeout` is
+/// retried, as long as `Request::is_idempotent` holds. The delay before each
+/// retry doubles, starting at the configured backoff. No retry is started
+/// that would end after `Request::deadline`.
+///
+/// If every attempt fails, the error of the last attempt is returned.
+///
+/// ```ignore
+/// let handler = Retry::new(proxy).retries(3).backoff(Duration::from_millis(100));
+/// ```
+pub struct Retry<H> {
+    handler: H,
+    retries: u32,
+    backoff: Duration,
+}
+
+impl<H: Handler> Retry<H> {
+    /// Retry `handler` up to twice, backing off 50 milliseconds at first.
+    pub fn new(handler: H) -> Retry<H> {
+        Retry {
+            handler,
+            retries: 2,
+            backoff: Duration::from_millis(50),
+        }
+    }
+
+    /// Set the maximum number of retries after the first attempt.
+    pub fn retries(mut self, retries: u32) -> Retry<H> {
+        self.retries = retries;
+        self
+    }
+
+    /// Set the delay before the first retry.
+    pub fn backoff(mut self, backoff: Duration) -> Retry<H> {
+        self.backoff = backoff;
+        self
+    }
+}
+
+impl<H: Handler> Handler for Retry<H> {
+    fn handle(&self, req: &mut Request) -> IronResult<Response> {
+        let mut backoff = self.backoff;
+        let mut retries = if req.is_idempotent() { self.retries } else { 0 };
+
+        loop {
+            let err = match self.handler.handle(req) {
+                Err(err) => {
+                    if retries == 0 || !is_transient(&err) {
+                        return Err(err);
+                    }
+                    err
+                }
+                res => return res,
+            };
+
+            if req
+                .deadline()
+                .is_some_and(|deadline| Instant::now() + backoff >= deadline)
+            {
+                return Err(err);
+            }
+
+            warn!(
+                "Retrying {} {} in {:?} after: {}",
+                req.method,
+                req.url.as_ref().path(),
+                backoff,
+                err
+            );
+            thread::sleep(backoff);
+            backoff *= 2;
+            retries -= 1;
+        }
+    }
+}
+
+fn is_transient(err: &IronError) -> bool {
+    matches!(
+        err.response.status,
+        Some(StatusCode::BAD_GATEWAY)
+            | Some(StatusCode::SERVICE_UNAVAILABLE)
+            | Some(StatusCode::GATEWAY_TIMEOUT)
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::error::Error;
+    use std::fmt;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    use Method;
+
+    #[derive(Debug)]
+    struct UpstreamError;
+
+    impl fmt::Display for UpstreamError {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.write_str("upstream unavailable")
+        }
+    }
+
+    impl Error for UpstreamError {}
+
+    // An upstream stub failing `failures` times before succeeding.
+    fn flaky(failures: usize) -> (Arc<AtomicUsize>, impl Handler) {
+        let attempts = Arc::new(AtomicUsize::new(0));
+        let counter = attempts.clone();
+        let handler = move |_: &mut Request| {
+            if counter.fetch_add(1, Ordering::SeqCst) < failures {
+                Err(IronError::new(UpstreamError, StatusCode::BAD_GATEWAY))
+            } else {
+                Ok(Response::with(StatusCode::OK))
+            }
+        };
+        (attempts, handler)
+    }
+
+    #[test]
+    fn test_retries_idempotent_request() {
+        let (attempts, upstream) = flaky(2);
+        let retry = Retry::new(upstream).backoff(Duration::from_millis(1));
+
+        let res = retry.handle(&mut Request::stub()).unwrap();
+        assert_eq!(res.status, Some(StatusCode::OK));
+        assert_eq!(attempts.load(Ordering::SeqCst), 3);
+    }
+
+    #[test]
+    fn test_surfaces_last_error() {
+        let (attempts, upstream) = flaky(5);
+        let retry = Retry::new(upstream).backoff(Duration::from_millis(1));
+
+        let err = retry.handle(&mut Request::stub()).unwrap_err();
+        assert_eq!(err.response.status, Some(StatusCode::BAD_GATEWAY));
+        assert_eq!(attempts.load(Ordering::SeqCst), 3);
+    }
+
+    #[test]
+    fn test_does_not_retry_non_idempotent_request() {
+        let (attempts, upstream) = flaky(2);
+        let retry = Retry::new(upstream).backoff(Duration::from_millis(1));
+
+        let mut req = Request::stub();
+        req.method = Method::POST;
+        assert!(retry.handle(&mut req).is_err());
+        assert_eq!(attempts.load(Ordering::SeqCst), 1);
+    }
+}
@@ -0,0 +1,124 @@
+use modifiers::Redirect;
+use {Handler, IronResult, Request, Response, StatusCode};
+
+/// A `Handler` dispatching requests by whether they arrived over HTTPS.
+///
+/// Requests for which `Request::secure` holds go to the secure handler, all
+/// others to the insecure one.
+///
+/// ```ignore
+/// let handler = SecureOr::new(login_form, login_disabled_notice);
+/// ```
+pub struct SecureOr<S, I> {
+    secure: S,
+    insecure: I,
+}
+
+impl<S: Handler, I: Handler> SecureOr<S, I> {
+    /// Handle HTTPS requests with `secure` and plain HTTP requests with `insecure`.
+    pub fn new(secure: S, insecure: I) -> SecureOr<S, I> {
+        SecureOr { secure, insecure }
+    }
+}
+
+impl<S: Handler, I: Handler> Handler for SecureOr<S, I> {
+    fn handle(&self, req: &mut Request) -> IronResult<Response> {
+        if req.secure() {
+            self.secure.handle(req)
+        } else {
+            self.insecure.handle(req)
+        }
+    }
+}
+
+/// A `Handler` only serving requests which arrived over HTTPS.
+///
+/// Plain HTTP requests are redirected to the same URL with the `https`
+/// scheme and the default port, with `301 Moved Permanently`.
+///
+/// ```ignore
+/// router.get("/account", RequireSecure::new(account_page), "account");
+/// ```
+pub struct RequireSecure<H> {
+    handler: H,
+}
+
+impl<H: Handler> RequireSecure<H> {
+    /// Serve HTTPS requests with `handler`.
+    pub fn new(handler: H) -> RequireSecure<H> {
+        RequireSecure { handler }
+    }
+}
+
+impl<H: Handler> Handler for RequireSecure<H> {
+    fn handle(&self, req: &mut Request) -> IronResult<Response> {
+        if req.secure() {
+            return self.handler.handle(req);
+        }
+
+        let mut url = req.url.clone();
+        // Switching between special schemes and clearing the port can't fail.
+        url.as_mut().set_scheme("https").unwrap();
+        url.as_mut().set_port(None).unwrap();
+        Ok(Response::with((
+            StatusCode::MOVED_PERMANENTLY,
+            Redirect(url),
+        )))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use headers;
+    use request::Url;
+
+    fn request(url: &str) -> Request {
+        let mut req = Request::stub();
+        req.url = Url::parse(url).unwrap();
+        req
+    }
+
+    fn secure_handler() -> RequireSecure<fn(&mut Request) -> IronResult<Response>> {
+        fn account(_: &mut Request) -> IronResult<Response> {
+            Ok(Response::with((StatusCode::OK, "account")))
+        }
+        RequireSecure::new(account)
+    }
+
+    #[test]
+    fn test_secure_request() {
+        let res = secure_handler()
+            .handle(&mut request("https://example.com/account"))
+            .unwrap();
+        assert_eq!(res.status, Some(StatusCode::OK));
+    }
+
+    #[test]
+    fn test_insecure_request_redirected() {
+        let res = secure_handler()
+            .handle(&mut request("http://example.com:8080/account?tab=keys"))
+            .unwrap();
+        assert_eq!(res.status, Some(StatusCode::MOVED_PERMANENTLY));
+        assert_eq!(
+            res.headers[headers::LOCATION],
+            "https://example.com/account?tab=keys"
+        );
+    }
+
+    #[test]
+    fn test_secure_or() {
+        let handler = SecureOr::new(
+            |_: &mut Request| Ok(Response::with(StatusCode::OK)),
+            |_: &mut Request| Ok(Response::with(StatusCode::FORBIDDEN)),
+        );
+
+        let res = handler
+            .handle(&mut request("https://example.com/"))
+            .unwrap();
+        assert_eq!(res.status, Some(StatusCode::OK));
+        let res = handler.handle(&mut request("http://example.com/")).unwrap();
+        assert_eq!(res.status, Some(StatusCode::FORBIDDEN));
+    }
+}
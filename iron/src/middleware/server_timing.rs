@@ -0,0 +1,80 @@
+use std::time::Duration;
+
+use typemap::Key;
+
+use {AfterMiddleware, IronError, IronResult, Request, Response};
+
+/// The name of the `Server-Timing` header.
+pub const SERVER_TIMING: &str = "server-timing";
+
+/// `AfterMiddleware` adding a `Server-Timing` header to responses.
+///
+/// The header lists the metrics handlers added with
+/// `Request::add_server_timing`, followed by a `total` metric measuring the
+/// time since `Request::received_at`. Durations are given in milliseconds:
+///
+/// ```plain
+/// Server-Timing: db;dur=12.5, total;dur=20.1
+/// ```
+///
+/// Link it last so `total` covers the other `AfterMiddleware` as well.
+pub struct ServerTiming;
+
+impl ServerTiming {
+    fn write_header(req: &mut Request, res: &mut Response) {
+        let mut metrics: Vec<String> = req
+            .extensions
+            .remove::<ServerTimingEntries>()
+            .unwrap_or_default()
+            .iter()
+            .map(|(name, duration)| metric(name, *duration))
+            .collect();
+        metrics.push(metric("total", req.received_at.elapsed()));
+
+        if let Ok(value) = metrics.join(", ").parse() {
+            res.headers.append(SERVER_TIMING, value);
+        }
+    }
+}
+
+impl AfterMiddleware for ServerTiming {
+    fn after(&self, req: &mut Request, mut res: Response) -> IronResult<Response> {
+        ServerTiming::write_header(req, &mut res);
+        Ok(res)
+    }
+
+    fn catch(&self, req: &mut Request, mut err: IronError) -> IronResult<Response> {
+        ServerTiming::write_header(req, &mut err.response);
+        Err(err)
+    }
+}
+
+pub(crate) struct ServerTimingEntries;
+
+impl Key for ServerTimingEntries {
+    type Value = Vec<(String, Duration)>;
+}
+
+fn metric(name: &str, duration: Duration) -> String {
+    format!("{};dur={:.1}", name, duration.as_secs_f64() * 1000.0)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use {Chain, Handler, StatusCode};
+
+    #[test]
+    fn test_server_timing_header() {
+        let mut chain = Chain::new(|req: &mut Request| {
+            req.add_server_timing("db", Duration::from_millis(12));
+            Ok(Response::with(StatusCode::OK))
+        });
+        chain.link_after(ServerTiming);
+
+        let res = chain.handle(&mut Request::stub()).unwrap();
+        let value = res.headers[SERVER_TIMING].to_str().unwrap();
+        assert!(value.starts_with("db;dur=12.0, total;dur="), "{}", value);
+    }
+}
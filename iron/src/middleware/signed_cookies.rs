@@ -0,0 +1,116 @@
+use std::error::Error;
+use std::fmt;
+
+use cookie::{Cookie, CookieJar, Key as CookieKey};
+use typemap::Key;
+
+use {headers, BeforeMiddleware, IronResult, Request};
+
+/// `BeforeMiddleware` storing the key used to verify signed cookies.
+///
+/// Once it has run, `Request::signed_cookie` returns the values of cookies
+/// signed with this key.
+pub struct SignedCookies {
+    key: CookieKey,
+}
+
+impl SignedCookies {
+    /// Verify signed cookies with `key`.
+    pub fn new(key: CookieKey) -> SignedCookies {
+        SignedCookies { key }
+    }
+}
+
+impl BeforeMiddleware for SignedCookies {
+    fn before(&self, req: &mut Request) -> IronResult<()> {
+        req.extensions.insert::<SigningKey>(self.key.clone());
+        Ok(())
+    }
+}
+
+/// The key of `Request::extensions` holding the cookie signing key.
+pub struct SigningKey;
+
+impl Key for SigningKey {
+    type Value = CookieKey;
+}
+
+pub(crate) fn signed_cookie(req: &Request, name: &str) -> Result<Option<String>, MissingSigningKey> {
+    let key = req.extensions.get::<SigningKey>().ok_or(MissingSigningKey)?;
+
+    let mut jar = CookieJar::new();
+    let cookies = req
+        .headers
+        .get_all(headers::COOKIE)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| Cookie::split_parse(value.to_owned()))
+        .filter_map(Result::ok);
+    for cookie in cookies {
+        jar.add_original(cookie);
+    }
+
+    Ok(jar
+        .signed(key)
+        .get(name)
+        .map(|cookie| cookie.value().to_owned()))
+}
+
+/// The error returned by `Request::signed_cookie` if no signing key was stored.
+///
+/// This usually means the `SignedCookies` middleware is not linked into the
+/// chain.
+#[derive(Debug)]
+pub struct MissingSigningKey;
+
+impl fmt::Display for MissingSigningKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("No cookie signing key; is the SignedCookies middleware linked?")
+    }
+}
+
+impl Error for MissingSigningKey {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn key() -> CookieKey {
+        CookieKey::from(&[7; 64])
+    }
+
+    fn signed(name: &'static str, value: &'static str) -> String {
+        let mut jar = CookieJar::new();
+        jar.signed_mut(&key()).add(Cookie::new(name, value));
+        jar.get(name).unwrap().to_string()
+    }
+
+    fn request(cookie: &str) -> Request {
+        let mut req = Request::stub();
+        req.headers.insert(headers::COOKIE, cookie.parse().unwrap());
+        SignedCookies::new(key()).before(&mut req).unwrap();
+        req
+    }
+
+    #[test]
+    fn test_valid_signed_cookie() {
+        let req = request(&format!("theme=dark; {}", signed("session", "alice")));
+        assert_eq!(req.signed_cookie("session").unwrap(), Some("alice".into()));
+        assert_eq!(req.signed_cookie("theme").unwrap(), None);
+    }
+
+    #[test]
+    fn test_tampered_signed_cookie() {
+        let cookie = signed("session", "alice").replace("alice", "admin");
+        let req = request(&cookie);
+        assert_eq!(req.signed_cookie("session").unwrap(), None);
+    }
+
+    #[test]
+    fn test_missing_key() {
+        let mut req = Request::stub();
+        req.headers
+            .insert(headers::COOKIE, signed("session", "alice").parse().unwrap());
+        assert!(req.signed_cookie("session").is_err());
+    }
+}
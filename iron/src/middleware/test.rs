@@ -1,11 +1,13 @@
 use std::sync::atomic::AtomicBool;
 use std::sync::atomic::Ordering::Relaxed;
 use std::sync::Arc;
+use std::time::Instant;
 
 use self::Kind::{Fine, Prob};
 
 use prelude::*;
-use {AfterMiddleware, BeforeMiddleware, Handler};
+use typemap::Key;
+use {AfterMiddleware, AroundMiddleware, BeforeMiddleware, Handler, StatusCode};
 
 #[test]
 fn test_chain_normal() {
@@ -69,6 +71,82 @@ fn test_chain_handler_error_then_handle() {
     );
 }
 
+struct UserId;
+
+impl Key for UserId {
+    type Value = u32;
+}
+
+#[test]
+fn test_before_sets_extension() {
+    let mut chain = Chain::new(|req: &mut Request| {
+        let user = req.extensions.get::<UserId>().cloned();
+        Ok(Response::with(format!("{:?}", user)))
+    });
+    chain.link_before(|req: &mut Request| {
+        req.extensions.insert::<UserId>(42);
+        Ok(())
+    });
+
+    let mut res = chain.handle(&mut request()).unwrap();
+    let mut body = vec![];
+    res.body.as_mut().unwrap().write_body(&mut body).unwrap();
+    assert_eq!(body, b"Some(42)");
+}
+
+fn greeting(_: &mut Request) -> IronResult<Response> {
+    let mut res = Response::with((StatusCode::CREATED, "hello"));
+    res.headers.insert("x-greeting", "1".parse().unwrap());
+    Ok(res)
+}
+
+#[test]
+fn test_noop_around() {
+    let mut chain = Chain::new(greeting);
+    chain.link_around(|handler: Box<dyn Handler>| handler);
+
+    let mut res = chain.handle(&mut request()).unwrap();
+    assert_eq!(res.status, Some(StatusCode::CREATED));
+    assert_eq!(res.headers, greeting(&mut request()).unwrap().headers);
+    let mut body = vec![];
+    res.body.as_mut().unwrap().write_body(&mut body).unwrap();
+    assert_eq!(body, b"hello");
+}
+
+struct Timing;
+
+struct TimingHandler(Box<dyn Handler>);
+
+impl AroundMiddleware for Timing {
+    fn around(self, handler: Box<dyn Handler>) -> Box<dyn Handler> {
+        Box::new(TimingHandler(handler))
+    }
+}
+
+impl Handler for TimingHandler {
+    fn handle(&self, req: &mut Request) -> IronResult<Response> {
+        let start = Instant::now();
+        let mut res = self.0.handle(req)?;
+        let elapsed = format!("{}us", start.elapsed().as_micros());
+        res.headers
+            .insert("x-response-time", elapsed.parse().unwrap());
+        Ok(res)
+    }
+}
+
+#[test]
+fn test_timing_around() {
+    let mut chain = Chain::new(greeting);
+    chain.link_around(Timing);
+
+    let res = chain.handle(&mut request()).unwrap();
+    assert_eq!(res.status, Some(StatusCode::CREATED));
+    assert!(res.headers["x-response-time"]
+        .to_str()
+        .unwrap()
+        .ends_with("us"));
+}
+
 // Used to indicate the action taken by a middleware or handler.
 #[derive(Debug, PartialEq)]
 enum Kind {
@@ -218,9 +296,9 @@ fn to_chain(counters: &ChainLike<Twice<Arc<AtomicBool>>>, chain: ChainLike<Kind>
         .collect::<Vec<_>>();
 
     Chain {
-        befores: befores,
+        befores,
         handler: Some(Box::new(handler) as Box<dyn Handler>),
-        afters: afters,
+        afters,
     }
 }
 
@@ -231,7 +309,7 @@ fn into_middleware(input: (Kind, &Twice<Arc<AtomicBool>>)) -> Middleware {
     Middleware {
         normal: normal.clone(),
         error: error.clone(),
-        mode: mode,
+        mode,
     }
 }
 
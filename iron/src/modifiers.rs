@@ -47,18 +47,28 @@
 //! For more information about the modifier system, see
 //! [rust-modifier](https://github.com/reem/rust-modifier).
 
+use std::collections::hash_map::RandomState;
+use std::fmt;
 use std::fs::File;
-use std::io;
+use std::hash::{BuildHasher, Hasher};
+use std::io::{self, Read, Seek, SeekFrom};
 use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 use modifier::Modifier;
 
 use mime::{self, Mime};
 
-use {headers, Request, Response, Set, StatusCode, Url};
+use {headers, Error, IronError, Method, Request, Response, Set, StatusCode, Url};
 
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use http::{self, HttpTryFrom};
+use httpdate;
 use mime_guess;
-use response::{BodyReader, WriteBody};
+use request::{ByteRanges, IfRange, PREFERENCE_APPLIED};
+use response::{BodyReader, EventStream, Streaming, WriteBody};
+use sha2::{Digest, Sha256};
 
 impl Modifier<Response> for Mime {
     #[inline]
@@ -83,6 +93,26 @@ impl<R: io::Read + Send + 'static> Modifier<Response> for BodyReader<R> {
     }
 }
 
+impl<F> Modifier<Response> for Streaming<F>
+where
+    F: FnOnce(&mut dyn io::Write) -> io::Result<()> + Send + 'static,
+{
+    #[inline]
+    fn modify(self, res: &mut Response) {
+        res.body = Some(Box::new(self));
+    }
+}
+
+impl Modifier<Response> for EventStream {
+    /// Stream server-sent events as a `text/event-stream` body.
+    fn modify(self, res: &mut Response) {
+        res.headers
+            .insert(headers::CACHE_CONTROL, headers::HeaderValue::from_static("no-cache"));
+        res.set_mut(mime::TEXT_EVENT_STREAM);
+        res.body = Some(Box::new(self));
+    }
+}
+
 impl Modifier<Response> for String {
     #[inline]
     fn modify(self, res: &mut Response) {
@@ -99,14 +129,14 @@ impl Modifier<Response> for Vec<u8> {
     }
 }
 
-impl<'a> Modifier<Response> for &'a str {
+impl Modifier<Response> for &str {
     #[inline]
     fn modify(self, res: &mut Response) {
         self.to_owned().modify(res);
     }
 }
 
-impl<'a> Modifier<Response> for &'a [u8] {
+impl Modifier<Response> for &[u8] {
     #[inline]
     fn modify(self, res: &mut Response) {
         self.to_vec().modify(res);
@@ -125,7 +155,7 @@ impl Modifier<Response> for File {
     }
 }
 
-impl<'a> Modifier<Response> for &'a Path {
+impl Modifier<Response> for &Path {
     /// Set the body to the contents of the File at this path.
     ///
     /// ## Panics
@@ -153,31 +183,347 @@ impl Modifier<Response> for PathBuf {
     }
 }
 
+/// A modifier serving the file at a path like `&Path` does, but only the
+/// byte ranges asked for in the request's `Range` header.
+///
+/// A single range is answered with `206 Partial Content` and the bytes in
+/// that range. Several ranges are answered with a `multipart/byteranges`
+/// body holding one part per range, after overlapping and adjacent ranges
+/// have been coalesced. If no range lies within the file, the answer is
+/// `416 Range Not Satisfiable`. The whole file is sent without changing the
+/// status if the request asks for no ranges, or if its `If-Range` date is
+/// not the modification time of the file.
+///
+/// ```ignore
+/// fn download(req: &mut Request) -> IronResult<Response> {
+///     Ok(Response::with((StatusCode::OK, FileRanges::new("videos/intro.mp4", req))))
+/// }
+/// ```
+///
+/// ## Panics
+///
+/// Panics if there is no file at the path.
+pub struct FileRanges {
+    path: PathBuf,
+    ranges: Option<ByteRanges>,
+    if_range: Option<IfRange>,
+}
+
+impl FileRanges {
+    /// Serve the ranges `req` asks for of the file at `path`.
+    pub fn new<P: Into<PathBuf>>(path: P, req: &Request) -> FileRanges {
+        FileRanges {
+            path: path.into(),
+            ranges: req.range(),
+            if_range: req.if_range(),
+        }
+    }
+}
+
+impl Modifier<Response> for FileRanges {
+    fn modify(self, res: &mut Response) {
+        let file = File::open(&self.path)
+            .unwrap_or_else(|_| panic!("No such file: {}", self.path.display()));
+        let metadata = file.metadata().ok();
+        // HTTP dates have a resolution of one second.
+        let modified = metadata
+            .as_ref()
+            .and_then(|metadata| metadata.modified().ok())
+            .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+            .map(|modified| UNIX_EPOCH + Duration::from_secs(modified.as_secs()));
+        let if_range_matches = self
+            .if_range
+            .as_ref()
+            .is_none_or(|if_range| if_range.matches(None, modified));
+        let mime = mime_for_path(&self.path);
+
+        res.headers.insert(
+            headers::ACCEPT_RANGES,
+            headers::HeaderValue::from_static("bytes"),
+        );
+        let (ranges, len) = match (self.ranges, metadata) {
+            (Some(ranges), Some(metadata)) if if_range_matches => {
+                (ranges.resolve(metadata.len()), metadata.len())
+            }
+            _ => {
+                file.modify(res);
+                res.set_mut(mime);
+                return;
+            }
+        };
+
+        write_ranges(res, file, &ranges, len, &mime);
+    }
+}
+
+/// A modifier serving byte ranges like `FileRanges`, but read from any
+/// seekable source of a known length, such as an object fetched from a
+/// store.
+///
+/// The source is read when the response is written, like other response
+/// bodies. The parts of a `multipart/byteranges` answer carry the
+/// `Content-Type` the response has when this modifier is applied, so set it
+/// first:
+///
+/// ```ignore
+/// fn download(req: &mut Request) -> IronResult<Response> {
+///     let (object, len) = store.open("videos/intro.mp4")?;
+///     let ranges = ReaderRanges::new(object, len, req);
+///     Ok(Response::with((StatusCode::OK, mime::APPLICATION_OCTET_STREAM, ranges)))
+/// }
+/// ```
+///
+/// Without validators to compare, a request with an `If-Range` header is
+/// always sent the whole source.
+pub struct ReaderRanges<R> {
+    source: R,
+    len: u64,
+    ranges: Option<ByteRanges>,
+}
+
+impl<R: Read + Seek + Send + 'static> ReaderRanges<R> {
+    /// Serve the ranges `req` asks for of `source`, which is `len` bytes long.
+    pub fn new(source: R, len: u64, req: &Request) -> ReaderRanges<R> {
+        let ranges = match req.if_range() {
+            Some(_) => None,
+            None => req.range(),
+        };
+        ReaderRanges {
+            source,
+            len,
+            ranges,
+        }
+    }
+}
+
+impl<R: Read + Seek + Send + 'static> Modifier<Response> for ReaderRanges<R> {
+    fn modify(self, res: &mut Response) {
+        res.headers.insert(
+            headers::ACCEPT_RANGES,
+            headers::HeaderValue::from_static("bytes"),
+        );
+        let ranges = match self.ranges {
+            Some(ranges) => ranges.resolve(self.len),
+            None => {
+                // The whole source is a single part, read from its start.
+                let parts = if self.len > 0 {
+                    vec![(String::new(), 0, self.len - 1)]
+                } else {
+                    vec![]
+                };
+                let body = RangesBody {
+                    source: self.source,
+                    parts,
+                    tail: String::new(),
+                };
+                res.headers
+                    .insert(headers::CONTENT_LENGTH, body.len().into());
+                res.body = Some(Box::new(body));
+                return;
+            }
+        };
+        let mime = res
+            .headers
+            .get(headers::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.parse().ok())
+            .unwrap_or(mime::APPLICATION_OCTET_STREAM);
+        write_ranges(res, self.source, &ranges, self.len, &mime);
+    }
+}
+
+// Answer with the resolved `ranges` of `source`, which is `len` bytes long
+// and has the type `mime`.
+fn write_ranges<R: Read + Seek + Send + 'static>(
+    res: &mut Response,
+    source: R,
+    ranges: &[(u64, u64)],
+    len: u64,
+    mime: &Mime,
+) {
+    let parts = match ranges.len() {
+        0 => {
+            res.status = Some(StatusCode::RANGE_NOT_SATISFIABLE);
+            // Only digits, so unwrap is safe here.
+            let content_range = format!("bytes */{}", len);
+            res.headers
+                .insert(headers::CONTENT_RANGE, content_range.parse().unwrap());
+            return;
+        }
+        1 => {
+            let (first, last) = ranges[0];
+            let content_range = format!("bytes {}-{}/{}", first, last, len);
+            res.headers
+                .insert(headers::CONTENT_RANGE, content_range.parse().unwrap());
+            res.set_mut(mime.clone());
+            RangesBody {
+                source,
+                parts: vec![(String::new(), first, last)],
+                tail: String::new(),
+            }
+        }
+        _ => {
+            let boundary = format!("{:016x}", RandomState::new().build_hasher().finish());
+            let parts = ranges
+                .iter()
+                .enumerate()
+                .map(|(i, &(first, last))| {
+                    let head = format!(
+                        "{}--{}\r\nContent-Type: {}\r\nContent-Range: bytes {}-{}/{}\r\n\r\n",
+                        if i == 0 { "" } else { "\r\n" },
+                        boundary,
+                        mime,
+                        first,
+                        last,
+                        len
+                    );
+                    (head, first, last)
+                })
+                .collect();
+            let content_type = format!("multipart/byteranges; boundary={}", boundary);
+            res.headers
+                .insert(headers::CONTENT_TYPE, content_type.parse().unwrap());
+            RangesBody {
+                source,
+                parts,
+                tail: format!("\r\n--{}--\r\n", boundary),
+            }
+        }
+    };
+
+    res.status = Some(StatusCode::PARTIAL_CONTENT);
+    res.headers
+        .insert(headers::CONTENT_LENGTH, parts.len().into());
+    res.body = Some(Box::new(parts));
+}
+
+// The ranges of a source, each following its part head, and a tail.
+struct RangesBody<R> {
+    source: R,
+    parts: Vec<(String, u64, u64)>,
+    tail: String,
+}
+
+impl<R> RangesBody<R> {
+    fn len(&self) -> u64 {
+        let parts: u64 = self
+            .parts
+            .iter()
+            .map(|(head, first, last)| head.len() as u64 + last - first + 1)
+            .sum();
+        parts + self.tail.len() as u64
+    }
+}
+
+impl<R: Read + Seek + Send> WriteBody for RangesBody<R> {
+    fn write_body(&mut self, res: &mut dyn io::Write) -> io::Result<()> {
+        for (head, first, last) in &self.parts {
+            res.write_all(head.as_bytes())?;
+            self.source.seek(SeekFrom::Start(*first))?;
+            let copied = io::copy(&mut (&mut self.source).take(last - first + 1), res)?;
+            if copied < last - first + 1 {
+                return Err(io::ErrorKind::UnexpectedEof.into());
+            }
+        }
+        res.write_all(self.tail.as_bytes())
+    }
+}
+
 impl Modifier<Response> for StatusCode {
     fn modify(self, res: &mut Response) {
         res.status = Some(self);
     }
 }
 
+/// A modifier setting the status from a numeric code, e.g. one received from
+/// an upstream server.
+///
+/// ```
+/// # use iron::prelude::*;
+/// # use iron::modifiers::Status;
+/// fn relay(code: u16) -> IronResult<Response> {
+///     Ok(Response::with(Status::from_u16(code)?))
+/// }
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Status(StatusCode);
+
+impl Status {
+    /// The status with `code`, which has to be between 100 and 599.
+    ///
+    /// Other codes fail with `500 Internal Server Error`.
+    pub fn from_u16(code: u16) -> Result<Status, IronError> {
+        match StatusCode::from_u16(code) {
+            Ok(status) if code <= 599 => Ok(Status(status)),
+            _ => Err(IronError::new(
+                InvalidStatus(code),
+                StatusCode::INTERNAL_SERVER_ERROR,
+            )),
+        }
+    }
+}
+
+impl Modifier<Response> for Status {
+    fn modify(self, res: &mut Response) {
+        res.status = Some(self.0);
+    }
+}
+
+/// The error for a numeric status code outside of 100 to 599.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct InvalidStatus(pub u16);
+
+impl fmt::Display for InvalidStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Invalid status code: {}", self.0)
+    }
+}
+
+impl Error for InvalidStatus {}
+
 /// A modifier for changing headers on requests and responses.
+///
+/// The value may be a `HeaderValue` or anything `HeaderValue` converts from,
+/// like a `&str`, so `Header(CONTENT_TYPE, "application/json")` works. Only
+/// the named header is replaced; an invalid value is logged and ignored.
 #[derive(Clone)]
-pub struct Header<H>(pub H, pub headers::HeaderValue);
+pub struct Header<H, V = headers::HeaderValue>(pub H, pub V);
 
-impl<H> Modifier<Response> for Header<H>
+impl<H, V> Header<H, V>
 where
     H: headers::IntoHeaderName,
+    headers::HeaderValue: HttpTryFrom<V>,
+{
+    fn insert_into(self, headers: &mut headers::HeaderMap) {
+        match headers::HeaderValue::try_from(self.1) {
+            Ok(value) => {
+                headers.insert(self.0, value);
+            }
+            Err(e) => {
+                let e: http::Error = e.into();
+                error!("Invalid header value: {}", e);
+            }
+        }
+    }
+}
+
+impl<H, V> Modifier<Response> for Header<H, V>
+where
+    H: headers::IntoHeaderName,
+    headers::HeaderValue: HttpTryFrom<V>,
 {
     fn modify(self, res: &mut Response) {
-        res.headers.insert(self.0, self.1);
+        self.insert_into(&mut res.headers);
     }
 }
 
-impl<H> Modifier<Request> for Header<H>
+impl<H, V> Modifier<Request> for Header<H, V>
 where
     H: headers::IntoHeaderName,
+    headers::HeaderValue: HttpTryFrom<V>,
 {
-    fn modify(self, res: &mut Request) {
-        res.headers.insert(self.0, self.1);
+    fn modify(self, req: &mut Request) {
+        self.insert_into(&mut req.headers);
     }
 }
 
@@ -203,6 +549,690 @@ impl Modifier<Response> for RedirectRaw {
     }
 }
 
+/// A modifier setting the `ETag` header, the entity tag of the representation
+/// in the response.
+///
+/// A strong tag promises the representation is byte-for-byte identical
+/// whenever the tag is, and may be used for range requests through
+/// `If-Range`. A weak tag, sent as `W/"tag"`, only promises semantic
+/// equivalence and is only good for revalidating caches with
+/// `If-None-Match`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ETag {
+    tag: String,
+    weak: bool,
+}
+
+impl ETag {
+    /// A strong entity tag, given without quotes.
+    pub fn strong(tag: &str) -> ETag {
+        ETag {
+            tag: tag.to_owned(),
+            weak: false,
+        }
+    }
+
+    /// A weak entity tag, given without quotes or `W/` prefix.
+    pub fn weak(tag: &str) -> ETag {
+        ETag {
+            tag: tag.to_owned(),
+            weak: true,
+        }
+    }
+
+    /// Whether this is a weak entity tag.
+    pub fn is_weak(&self) -> bool {
+        self.weak
+    }
+}
+
+impl fmt::Display for ETag {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.weak {
+            f.write_str("W/")?;
+        }
+        write!(f, "\"{}\"", self.tag)
+    }
+}
+
+impl Modifier<Response> for ETag {
+    fn modify(self, res: &mut Response) {
+        match self.to_string().parse() {
+            Ok(value) => {
+                res.headers.insert(headers::ETAG, value);
+            }
+            Err(_) => error!("Invalid ETag: {:?}", self.tag),
+        }
+    }
+}
+
+/// A modifier preventing the response from being stored by any cache.
+///
+/// Sets `Cache-Control: no-store, no-cache, must-revalidate`, along with
+/// `Pragma: no-cache` and `Expires: 0` for HTTP/1.0 caches. Meant for
+/// responses with sensitive content.
+#[derive(Clone, Copy, Debug)]
+pub struct NoCache;
+
+impl Modifier<Response> for NoCache {
+    fn modify(self, res: &mut Response) {
+        res.headers.insert(
+            headers::CACHE_CONTROL,
+            headers::HeaderValue::from_static("no-store, no-cache, must-revalidate"),
+        );
+        res.headers.insert(
+            headers::PRAGMA,
+            headers::HeaderValue::from_static("no-cache"),
+        );
+        res.headers
+            .insert(headers::EXPIRES, headers::HeaderValue::from_static("0"));
+    }
+}
+
+/// A modifier setting the `Content-Location` header, the canonical URL of
+/// the representation in the response.
+///
+/// Relative references like `/users/1` are set from a string.
+pub struct ContentLocation(pub String);
+
+impl From<Url> for ContentLocation {
+    fn from(url: Url) -> ContentLocation {
+        ContentLocation(url.to_string())
+    }
+}
+
+impl<'a> From<&'a str> for ContentLocation {
+    fn from(location: &'a str) -> ContentLocation {
+        ContentLocation(location.to_owned())
+    }
+}
+
+impl Modifier<Response> for ContentLocation {
+    fn modify(self, res: &mut Response) {
+        match self.0.parse() {
+            Ok(value) => {
+                res.headers.insert(headers::CONTENT_LOCATION, value);
+            }
+            Err(_) => error!("Invalid Content-Location: {:?}", self.0),
+        }
+    }
+}
+
+/// A modifier for answering with the methods a resource allows.
+///
+/// Sets the `Allow` header and, unless a status was already set, the status
+/// to `405 Method Not Allowed`.
+pub struct Allow<'a>(pub &'a [Method]);
+
+impl<'a> Modifier<Response> for Allow<'a> {
+    fn modify(self, res: &mut Response) {
+        let methods: Vec<&str> = self.0.iter().map(Method::as_str).collect();
+        // Method names are tokens, so unwrap is safe here.
+        res.headers
+            .insert(headers::ALLOW, methods.join(", ").parse().unwrap());
+        res.status.get_or_insert(StatusCode::METHOD_NOT_ALLOWED);
+    }
+}
+
+/// A modifier setting the `Retry-After` header, typically on `429` and `503`
+/// responses.
+///
+/// A `Duration` is sent as a number of seconds, a `SystemTime` as an HTTP-date.
+/// A date before 1970 or after 9999 cannot be sent, so the header is left
+/// out and an error is logged.
+pub enum RetryAfter {
+    /// Retry after this delay, sent in whole seconds.
+    Delay(Duration),
+
+    /// Retry at this point in time.
+    Date(SystemTime),
+}
+
+impl From<Duration> for RetryAfter {
+    fn from(delay: Duration) -> RetryAfter {
+        RetryAfter::Delay(delay)
+    }
+}
+
+impl From<SystemTime> for RetryAfter {
+    fn from(date: SystemTime) -> RetryAfter {
+        RetryAfter::Date(date)
+    }
+}
+
+impl Modifier<Response> for RetryAfter {
+    fn modify(self, res: &mut Response) {
+        let value = match self {
+            RetryAfter::Delay(delay) => delay.as_secs().to_string(),
+            RetryAfter::Date(date) if is_http_date(date) => httpdate::fmt_http_date(date),
+            RetryAfter::Date(date) => {
+                error!("Retry-After date out of range: {:?}", date);
+                return;
+            }
+        };
+        // Both forms only contain visible ASCII, so unwrap is safe here.
+        res.headers.insert(headers::RETRY_AFTER, value.parse().unwrap());
+    }
+}
+
+// Whether `date` can be sent as an HTTP-date, which covers the years 1970
+// to 9999.
+fn is_http_date(date: SystemTime) -> bool {
+    const MAX_SECS: u64 = 253_402_300_799; // 9999-12-31T23:59:59Z
+    date.duration_since(UNIX_EPOCH)
+        .is_ok_and(|since| since.as_secs() <= MAX_SECS)
+}
+
+/// A modifier setting the `Age` header, how long a response served from a
+/// cache has been stored there.
+///
+/// The age is sent in whole seconds.
+pub struct Age(pub Duration);
+
+impl Modifier<Response> for Age {
+    fn modify(self, res: &mut Response) {
+        res.headers.insert(headers::AGE, self.0.as_secs().into());
+    }
+}
+
+/// A modifier setting a `Content-Security-Policy` header.
+///
+/// Directives are added with the builder methods and serialized in the order
+/// they were first added. Adding sources to a directive that is already
+/// present extends it. A report-only policy is sent as
+/// `Content-Security-Policy-Report-Only` instead.
+///
+/// ```
+/// # use iron::prelude::*;
+/// # use iron::modifiers::Csp;
+/// let csp = Csp::new()
+///     .directive("default-src", &["'self'"])
+///     .nonce("script-src", "r4nd0m");
+/// Response::with(csp);
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct Csp {
+    directives: Vec<(String, Vec<String>)>,
+    report_only: bool,
+}
+
+impl Csp {
+    /// An empty policy.
+    pub fn new() -> Csp {
+        Csp::default()
+    }
+
+    /// Add `sources` to the directive `name`, e.g. `script-src`.
+    pub fn directive(mut self, name: &str, sources: &[&str]) -> Csp {
+        let sources = sources.iter().map(|source| source.to_string());
+        match self.directives.iter_mut().find(|(n, _)| n == name) {
+            Some((_, existing)) => existing.extend(sources),
+            None => self.directives.push((name.to_owned(), sources.collect())),
+        }
+        self
+    }
+
+    /// Allow resources carrying `nonce` for the directive `name`.
+    pub fn nonce(self, name: &str, nonce: &str) -> Csp {
+        self.directive(name, &[&format!("'nonce-{}'", nonce)])
+    }
+
+    /// Only report violations of the policy instead of enforcing it.
+    pub fn report_only(mut self) -> Csp {
+        self.report_only = true;
+        self
+    }
+
+    /// The name of the header the policy is sent in.
+    pub fn header_name(&self) -> headers::HeaderName {
+        if self.report_only {
+            headers::CONTENT_SECURITY_POLICY_REPORT_ONLY
+        } else {
+            headers::CONTENT_SECURITY_POLICY
+        }
+    }
+}
+
+impl fmt::Display for Csp {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, (name, sources)) in self.directives.iter().enumerate() {
+            if i > 0 {
+                f.write_str("; ")?;
+            }
+            f.write_str(name)?;
+            for source in sources {
+                write!(f, " {}", source)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Modifier<Response> for Csp {
+    fn modify(self, res: &mut Response) {
+        let policy = self.to_string();
+        match policy.parse() {
+            Ok(value) => {
+                res.headers.insert(self.header_name(), value);
+            }
+            Err(_) => error!("Invalid Content-Security-Policy: {:?}", policy),
+        }
+    }
+}
+
+/// An authentication challenge for the `WwwAuthenticate` modifier.
+///
+/// Parameter values are sent as quoted strings, e.g. `Basic realm="api"`.
+#[derive(Clone, Debug)]
+pub struct Challenge {
+    scheme: String,
+    params: Vec<(String, String)>,
+}
+
+impl Challenge {
+    /// A challenge for the authentication `scheme`, e.g. `Basic` or `Bearer`.
+    pub fn new(scheme: &str) -> Challenge {
+        Challenge {
+            scheme: scheme.to_owned(),
+            params: vec![],
+        }
+    }
+
+    /// Set the protection space of the challenge.
+    pub fn realm(self, realm: &str) -> Challenge {
+        self.param("realm", realm)
+    }
+
+    /// Add an auth parameter, e.g. `error="invalid_token"`.
+    pub fn param(mut self, name: &str, value: &str) -> Challenge {
+        self.params.push((name.to_owned(), value.to_owned()));
+        self
+    }
+}
+
+impl fmt::Display for Challenge {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.scheme)?;
+        for (i, (name, value)) in self.params.iter().enumerate() {
+            let separator = if i == 0 { " " } else { ", " };
+            let value = value.replace('\\', "\\\\").replace('"', "\\\"");
+            write!(f, "{}{}=\"{}\"", separator, name, value)?;
+        }
+        Ok(())
+    }
+}
+
+/// A modifier for asking the client to authenticate.
+///
+/// Sets one `WWW-Authenticate` header per challenge, in order of preference,
+/// and, unless a status was already set, the status to `401 Unauthorized`.
+pub struct WwwAuthenticate(pub Vec<Challenge>);
+
+impl Modifier<Response> for WwwAuthenticate {
+    fn modify(self, res: &mut Response) {
+        for challenge in self.0 {
+            let challenge = challenge.to_string();
+            match challenge.parse() {
+                Ok(value) => {
+                    res.headers.append(headers::WWW_AUTHENTICATE, value);
+                }
+                Err(_) => error!("Invalid WWW-Authenticate challenge: {:?}", challenge),
+            }
+        }
+        res.status.get_or_insert(StatusCode::UNAUTHORIZED);
+    }
+}
+
+/// A modifier listing the request preferences a response honored.
+///
+/// Sets the `Preference-Applied` header, e.g. to
+/// `PreferenceApplied(&[Return::Minimal.as_str()])` after leaving out the
+/// resource as asked for by `Request::prefer`.
+pub struct PreferenceApplied<'a>(pub &'a [&'a str]);
+
+impl<'a> Modifier<Response> for PreferenceApplied<'a> {
+    fn modify(self, res: &mut Response) {
+        let applied = self.0.join(", ");
+        match applied.parse() {
+            Ok(value) => {
+                res.headers.insert(PREFERENCE_APPLIED, value);
+            }
+            Err(_) => error!("Invalid Preference-Applied: {:?}", applied),
+        }
+    }
+}
+
+/// The `SameSite` attribute of a cookie set with `SetCookie`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SameSite {
+    /// Only send the cookie with requests from the same site.
+    Strict,
+
+    /// Also send the cookie when navigating to the site from elsewhere.
+    Lax,
+
+    /// Send the cookie with all requests. Browsers require `Secure` for this.
+    None,
+}
+
+/// A modifier setting a cookie on the client.
+///
+/// Appends a `Set-Cookie` header, so several cookies can be set on one
+/// response. Bytes of the value which may not appear in a cookie, and `%`,
+/// are percent-encoded, matching the decoding of `Request::cookies`.
+///
+/// ```
+/// # use iron::prelude::*;
+/// # use iron::modifiers::{SameSite, SetCookie};
+/// # use iron::StatusCode;
+/// # use std::time::Duration;
+/// let session = SetCookie::new("session", "abc")
+///     .http_only(true)
+///     .secure(true)
+///     .same_site(SameSite::Lax)
+///     .max_age(Duration::from_secs(3600));
+/// Response::with((StatusCode::OK, session, SetCookie::new("theme", "dark")));
+/// ```
+#[derive(Clone, Debug)]
+pub struct SetCookie {
+    name: String,
+    value: String,
+    path: Option<String>,
+    domain: Option<String>,
+    max_age: Option<Duration>,
+    http_only: bool,
+    secure: bool,
+    same_site: Option<SameSite>,
+}
+
+impl SetCookie {
+    /// Set the cookie `name` to `value`, for the current browser session.
+    pub fn new(name: &str, value: &str) -> SetCookie {
+        SetCookie {
+            name: name.to_owned(),
+            value: value.to_owned(),
+            path: None,
+            domain: None,
+            max_age: None,
+            http_only: false,
+            secure: false,
+            same_site: None,
+        }
+    }
+
+    /// Only send the cookie with requests below `path`.
+    pub fn path(mut self, path: &str) -> SetCookie {
+        self.path = Some(path.to_owned());
+        self
+    }
+
+    /// Send the cookie to `domain` and its subdomains.
+    pub fn domain(mut self, domain: &str) -> SetCookie {
+        self.domain = Some(domain.to_owned());
+        self
+    }
+
+    /// Keep the cookie for `max_age`, in whole seconds, rather than until the
+    /// browser session ends.
+    pub fn max_age(mut self, max_age: Duration) -> SetCookie {
+        self.max_age = Some(max_age);
+        self
+    }
+
+    /// Hide the cookie from scripts.
+    pub fn http_only(mut self, http_only: bool) -> SetCookie {
+        self.http_only = http_only;
+        self
+    }
+
+    /// Only send the cookie over HTTPS.
+    pub fn secure(mut self, secure: bool) -> SetCookie {
+        self.secure = secure;
+        self
+    }
+
+    /// Restrict sending the cookie with cross-site requests.
+    pub fn same_site(mut self, same_site: SameSite) -> SetCookie {
+        self.same_site = Some(same_site);
+        self
+    }
+}
+
+impl Modifier<Response> for SetCookie {
+    fn modify(self, res: &mut Response) {
+        let mut cookie = format!("{}={}", self.name, encode_cookie_value(&self.value));
+        if let Some(path) = self.path {
+            cookie.push_str(&format!("; Path={}", path));
+        }
+        if let Some(domain) = self.domain {
+            cookie.push_str(&format!("; Domain={}", domain));
+        }
+        if let Some(max_age) = self.max_age {
+            cookie.push_str(&format!("; Max-Age={}", max_age.as_secs()));
+        }
+        if self.http_only {
+            cookie.push_str("; HttpOnly");
+        }
+        if self.secure {
+            cookie.push_str("; Secure");
+        }
+        match self.same_site {
+            Some(SameSite::Strict) => cookie.push_str("; SameSite=Strict"),
+            Some(SameSite::Lax) => cookie.push_str("; SameSite=Lax"),
+            Some(SameSite::None) => cookie.push_str("; SameSite=None"),
+            None => (),
+        }
+
+        match cookie.parse() {
+            Ok(value) => {
+                res.headers.append(headers::SET_COOKIE, value);
+            }
+            Err(_) => error!("Invalid cookie to set: {:?}", cookie),
+        }
+    }
+}
+
+// Percent-encode the bytes of `value` that are not cookie octets (RFC 6265,
+// section 4.1.1), and `%` itself.
+fn encode_cookie_value(value: &str) -> String {
+    let mut encoded = String::with_capacity(value.len());
+    for &byte in value.as_bytes() {
+        match byte {
+            b'!' | b'#'..=b'$' | b'&'..=b'+' | b'-'..=b':' | b'<'..=b'[' | b']'..=b'~' => {
+                encoded.push(byte as char)
+            }
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+/// A modifier telling the client to delete a cookie.
+///
+/// Appends a `Set-Cookie` header with an empty value which expired at the
+/// Unix epoch, so several cookies can be cleared on one response. A cookie
+/// set with a `Path` or `Domain` is only cleared if the same ones are given.
+///
+/// ```
+/// # use iron::prelude::*;
+/// # use iron::modifiers::ClearCookie;
+/// Response::with((ClearCookie::new("session").path("/"), ClearCookie::new("theme")));
+/// ```
+#[derive(Clone, Debug)]
+pub struct ClearCookie {
+    name: String,
+    path: Option<String>,
+    domain: Option<String>,
+}
+
+impl ClearCookie {
+    /// Clear the cookie called `name`.
+    pub fn new(name: &str) -> ClearCookie {
+        ClearCookie {
+            name: name.to_owned(),
+            path: None,
+            domain: None,
+        }
+    }
+
+    /// Match the `Path` the cookie was set with.
+    pub fn path(mut self, path: &str) -> ClearCookie {
+        self.path = Some(path.to_owned());
+        self
+    }
+
+    /// Match the `Domain` the cookie was set with.
+    pub fn domain(mut self, domain: &str) -> ClearCookie {
+        self.domain = Some(domain.to_owned());
+        self
+    }
+}
+
+impl Modifier<Response> for ClearCookie {
+    fn modify(self, res: &mut Response) {
+        let mut cookie = format!(
+            "{}=; Expires=Thu, 01 Jan 1970 00:00:00 GMT; Max-Age=0",
+            self.name
+        );
+        if let Some(path) = self.path {
+            cookie.push_str(&format!("; Path={}", path));
+        }
+        if let Some(domain) = self.domain {
+            cookie.push_str(&format!("; Domain={}", domain));
+        }
+
+        match cookie.parse() {
+            Ok(value) => {
+                res.headers.append(headers::SET_COOKIE, value);
+            }
+            Err(_) => error!("Invalid cookie to clear: {:?}", cookie),
+        }
+    }
+}
+
+/// The name of the `Content-Digest` header.
+pub const CONTENT_DIGEST: &str = "content-digest";
+
+/// A modifier setting a `Content-Digest` header with the SHA-256 hash of the
+/// body, e.g. `Content-Digest: sha-256=:uU0nuZNNPgilLlLX2n2r+sSE7+N6U4DukIj3rOLvzek=:`.
+///
+/// It must be applied after the body is set. Streamed bodies are left
+/// without a digest. If the body cannot be read the status is set to
+/// `StatusCode::INTERNAL_SERVER_ERROR` and no body is set.
+pub struct ContentDigest;
+
+impl Modifier<Response> for ContentDigest {
+    fn modify(self, res: &mut Response) {
+        if res.body.as_ref().is_some_and(|body| body.is_streaming()) {
+            return;
+        }
+        let mut body = match res.body.take() {
+            Some(body) => body,
+            None => return,
+        };
+
+        let mut contents = vec![];
+        if let Err(e) = body.write_body(&mut contents) {
+            error!("Error reading body for Content-Digest: {}", e);
+            res.status = Some(StatusCode::INTERNAL_SERVER_ERROR);
+            return;
+        }
+
+        let digest = format!("sha-256=:{}:", BASE64.encode(Sha256::digest(&contents)));
+        // Base64 only contains visible ASCII, so unwrap is safe here.
+        res.headers.insert(CONTENT_DIGEST, digest.parse().unwrap());
+        res.body = Some(Box::new(contents));
+    }
+}
+
+/// A modifier for sending a slice of records as a CSV download.
+///
+/// The header row is generated from the field names of the records. This
+/// sets `Content-Type: text/csv` and an attachment `Content-Disposition`
+/// with the given filename.
+///
+/// If a record cannot be serialized the status is set to
+/// `StatusCode::INTERNAL_SERVER_ERROR` and no body is set.
+#[cfg(feature = "csv")]
+pub struct Csv<'a, T: 'a> {
+    records: &'a [T],
+    filename: String,
+}
+
+#[cfg(feature = "csv")]
+impl<'a, T: ::serde::Serialize> Csv<'a, T> {
+    /// Create a CSV modifier serializing `records` as a download named `filename`.
+    pub fn new<S: Into<String>>(records: &'a [T], filename: S) -> Csv<'a, T> {
+        Csv {
+            records,
+            filename: filename.into(),
+        }
+    }
+}
+
+#[cfg(feature = "csv")]
+impl<'a, T: ::serde::Serialize> Modifier<Response> for Csv<'a, T> {
+    fn modify(self, res: &mut Response) {
+        let mut writer = ::csv::Writer::from_writer(vec![]);
+        let written = self
+            .records
+            .iter()
+            .try_for_each(|record| writer.serialize(record))
+            .map_err(|e| e.to_string())
+            .and_then(|()| writer.into_inner().map_err(|e| e.to_string()));
+
+        match written {
+            Ok(body) => {
+                let filename = self.filename.replace('\\', "\\\\").replace('"', "\\\"");
+                // Quotes and backslashes are escaped, so the header value is always valid.
+                let disposition = format!("attachment; filename=\"{}\"", filename);
+                res.headers.insert(
+                    headers::CONTENT_DISPOSITION,
+                    disposition.parse().unwrap(),
+                );
+                res.set_mut(mime::TEXT_CSV).set_mut(body);
+            }
+            Err(e) => {
+                error!("Error serializing CSV response: {}", e);
+                res.status = Some(StatusCode::INTERNAL_SERVER_ERROR);
+            }
+        }
+    }
+}
+
+/// A modifier rendering a Handlebars template as the response body.
+///
+/// The template is rendered by the `HandlebarsEngine` middleware, which must
+/// be linked after the handler; without it the response has no body.
+#[cfg(feature = "handlebars")]
+pub struct Template {
+    pub(crate) name: String,
+    pub(crate) context: Result<::serde_json::Value, ::serde_json::Error>,
+}
+
+#[cfg(feature = "handlebars")]
+impl Template {
+    /// Render the template registered as `name` with `context`.
+    pub fn new<T: ::serde::Serialize>(name: &str, context: &T) -> Template {
+        Template {
+            name: name.to_owned(),
+            context: ::serde_json::to_value(context),
+        }
+    }
+}
+
+#[cfg(feature = "handlebars")]
+impl ::typemap::Key for Template {
+    type Value = Template;
+}
+
+#[cfg(feature = "handlebars")]
+impl Modifier<Response> for Template {
+    fn modify(self, res: &mut Response) {
+        res.extensions.insert::<Template>(self);
+    }
+}
+
 fn mime_for_path(path: &Path) -> Mime {
     mime_guess::from_path(path).first().unwrap_or(mime::TEXT_PLAIN)
 }
@@ -211,6 +1241,180 @@ fn mime_for_path(path: &Path) -> Mime {
 mod test {
     use super::*;
 
+    #[cfg(feature = "csv")]
+    use serde::Serialize;
+
+    const RANGES_FILE: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/Cargo.toml");
+
+    // Serve `RANGES_FILE` for a request with the `Range` header `range`.
+    fn serve_ranges(range: &str) -> (Response, Vec<u8>) {
+        let mut req = Request::stub();
+        req.headers.insert(headers::RANGE, range.parse().unwrap());
+        let mut res = Response::with((StatusCode::OK, FileRanges::new(RANGES_FILE, &req)));
+
+        let mut body = vec![];
+        if let Some(ref mut response_body) = res.body {
+            response_body.write_body(&mut body).unwrap();
+        }
+        (res, body)
+    }
+
+    #[test]
+    fn test_file_ranges_multipart() {
+        let contents = ::std::fs::read(RANGES_FILE).unwrap();
+        let len = contents.len();
+        let (res, body) = serve_ranges("bytes=20-29,0-9");
+
+        assert_eq!(res.status, Some(StatusCode::PARTIAL_CONTENT));
+        let content_type = res.headers[headers::CONTENT_TYPE].to_str().unwrap();
+        assert!(content_type.starts_with("multipart/byteranges; boundary="));
+        let boundary = &content_type["multipart/byteranges; boundary=".len()..];
+
+        let mime = mime_for_path(Path::new(RANGES_FILE));
+        let mut expected = vec![];
+        for (i, &(first, last)) in [(0, 9), (20, 29)].iter().enumerate() {
+            if i > 0 {
+                expected.extend_from_slice(b"\r\n");
+            }
+            let head = format!(
+                "--{}\r\nContent-Type: {}\r\nContent-Range: bytes {}-{}/{}\r\n\r\n",
+                boundary, mime, first, last, len
+            );
+            expected.extend_from_slice(head.as_bytes());
+            expected.extend_from_slice(&contents[first..=last]);
+        }
+        expected.extend_from_slice(format!("\r\n--{}--\r\n", boundary).as_bytes());
+
+        assert_eq!(body, expected);
+        let length = expected.len().to_string();
+        assert_eq!(res.headers[headers::CONTENT_LENGTH], length.as_str());
+    }
+
+    #[test]
+    fn test_file_ranges_coalesced() {
+        let contents = ::std::fs::read(RANGES_FILE).unwrap();
+        let (res, body) = serve_ranges("bytes=0-9,5-14,15-19");
+
+        assert_eq!(res.status, Some(StatusCode::PARTIAL_CONTENT));
+        let content_range = format!("bytes 0-19/{}", contents.len());
+        assert_eq!(res.headers[headers::CONTENT_RANGE], content_range.as_str());
+        assert_eq!(res.headers[headers::CONTENT_LENGTH], "20");
+        assert_eq!(body, &contents[..20]);
+    }
+
+    #[test]
+    fn test_file_ranges_not_satisfiable() {
+        let len = ::std::fs::read(RANGES_FILE).unwrap().len();
+        let (res, body) = serve_ranges(&format!("bytes={}-", len));
+
+        assert_eq!(res.status, Some(StatusCode::RANGE_NOT_SATISFIABLE));
+        let content_range = format!("bytes */{}", len);
+        assert_eq!(res.headers[headers::CONTENT_RANGE], content_range.as_str());
+        assert!(body.is_empty());
+    }
+
+    #[test]
+    fn test_file_ranges_whole_file() {
+        let contents = ::std::fs::read(RANGES_FILE).unwrap();
+        let (res, body) = serve_ranges("items=0-9");
+
+        assert_eq!(res.status, Some(StatusCode::OK));
+        assert_eq!(res.headers[headers::ACCEPT_RANGES], "bytes");
+        assert_eq!(body, contents);
+    }
+
+    // Serve `ALPHABET` from memory for a request with the `Range` header
+    // `range`, if any.
+    fn serve_reader_ranges(range: Option<&str>) -> (Response, Vec<u8>) {
+        let mut req = Request::stub();
+        if let Some(range) = range {
+            req.headers.insert(headers::RANGE, range.parse().unwrap());
+        }
+        let source = io::Cursor::new(ALPHABET.to_vec());
+        let ranges = ReaderRanges::new(source, ALPHABET.len() as u64, &req);
+        let mut res = Response::with((StatusCode::OK, mime::TEXT_PLAIN, ranges));
+
+        let mut body = vec![];
+        if let Some(ref mut response_body) = res.body {
+            response_body.write_body(&mut body).unwrap();
+        }
+        (res, body)
+    }
+
+    const ALPHABET: &[u8] = b"abcdefghijklmnopqrstuvwxyz";
+
+    #[test]
+    fn test_reader_ranges() {
+        let (res, body) = serve_reader_ranges(Some("bytes=2-4"));
+        assert_eq!(res.status, Some(StatusCode::PARTIAL_CONTENT));
+        assert_eq!(res.headers[headers::CONTENT_RANGE], "bytes 2-4/26");
+        assert_eq!(res.headers[headers::CONTENT_LENGTH], "3");
+        assert_eq!(body, b"cde");
+
+        let (res, body) = serve_reader_ranges(Some("bytes=0-0,-1"));
+        let content_type = res.headers[headers::CONTENT_TYPE].to_str().unwrap();
+        let boundary = &content_type["multipart/byteranges; boundary=".len()..];
+        let expected = format!(
+            "--{0}\r\nContent-Type: text/plain\r\nContent-Range: bytes 0-0/26\r\n\r\na\r\n\
+             --{0}\r\nContent-Type: text/plain\r\nContent-Range: bytes 25-25/26\r\n\r\nz\r\n\
+             --{0}--\r\n",
+            boundary
+        );
+        assert_eq!(body, expected.as_bytes());
+    }
+
+    #[test]
+    fn test_reader_ranges_not_satisfiable() {
+        let (res, body) = serve_reader_ranges(Some("bytes=26-"));
+        assert_eq!(res.status, Some(StatusCode::RANGE_NOT_SATISFIABLE));
+        assert_eq!(res.headers[headers::CONTENT_RANGE], "bytes */26");
+        assert!(body.is_empty());
+    }
+
+    #[test]
+    fn test_reader_ranges_whole() {
+        let (res, body) = serve_reader_ranges(None);
+        assert_eq!(res.status, Some(StatusCode::OK));
+        assert_eq!(res.headers[headers::ACCEPT_RANGES], "bytes");
+        assert_eq!(res.headers[headers::CONTENT_LENGTH], "26");
+        assert_eq!(body, ALPHABET);
+    }
+
+    #[test]
+    fn test_no_cache() {
+        let res = Response::with((StatusCode::OK, "secret", NoCache));
+        assert_eq!(
+            res.headers[headers::CACHE_CONTROL],
+            "no-store, no-cache, must-revalidate"
+        );
+        assert_eq!(res.headers[headers::PRAGMA], "no-cache");
+        assert_eq!(res.headers[headers::EXPIRES], "0");
+    }
+
+    #[test]
+    fn test_age() {
+        let res = Response::with((StatusCode::OK, Age(Duration::from_millis(90_500))));
+        assert_eq!(res.headers[headers::AGE], "90");
+    }
+
+    #[test]
+    fn test_header() {
+        let res = Response::with((
+            StatusCode::OK,
+            Header(headers::CONTENT_TYPE, "application/json"),
+            Header("x-request-id", headers::HeaderValue::from_static("42")),
+            "{}",
+        ));
+        assert_eq!(res.headers[headers::CONTENT_TYPE], "application/json");
+        assert_eq!(res.headers["x-request-id"], "42");
+    }
+
+    #[test]
+    fn test_header_invalid_value() {
+        let res = Response::with((Header("x-request-id", "42"), Header("x-request-id", "4\n2")));
+        assert_eq!(res.headers["x-request-id"], "42");
+    }
+
     #[test]
     fn test_mime_for_path() {
         assert_eq!(mime_for_path(Path::new("foo.txt")), mime::TEXT_PLAIN);
@@ -221,4 +1425,261 @@ mod test {
         );
         assert_eq!(mime_for_path(Path::new("foo")), mime::TEXT_PLAIN);
     }
+
+    #[test]
+    fn test_status_from_u16() {
+        let res = Response::with(Status::from_u16(204).unwrap());
+        assert_eq!(res.status, Some(StatusCode::NO_CONTENT));
+
+        let res = Response::with(Status::from_u16(599).unwrap());
+        assert_eq!(res.status.map(|status| status.as_u16()), Some(599));
+    }
+
+    #[test]
+    fn test_status_from_u16_invalid() {
+        for &code in &[0, 99, 600, 700] {
+            let err = Status::from_u16(code).unwrap_err();
+            assert_eq!(err.response.status, Some(StatusCode::INTERNAL_SERVER_ERROR));
+            assert_eq!(err.to_string(), format!("Invalid status code: {}", code));
+        }
+    }
+
+    #[test]
+    fn test_set_chaining() {
+        let mut res = Response::new()
+            .set(StatusCode::CREATED)
+            .set(mime::APPLICATION_JSON)
+            .set("{}");
+
+        assert_eq!(res.status, Some(StatusCode::CREATED));
+        assert_eq!(res.headers[headers::CONTENT_TYPE], "application/json");
+
+        let mut body = vec![];
+        res.body.as_mut().unwrap().write_body(&mut body).unwrap();
+        assert_eq!(body, b"{}");
+    }
+
+    #[test]
+    fn test_etag() {
+        let res = Response::with(ETag::strong("v1"));
+        assert_eq!(res.headers[headers::ETAG], "\"v1\"");
+
+        let res = Response::with(ETag::weak("v1"));
+        assert_eq!(res.headers[headers::ETAG], "W/\"v1\"");
+        assert!(ETag::weak("v1").is_weak());
+    }
+
+    #[test]
+    fn test_content_location() {
+        let url = Url::parse("https://example.com/users/1?fields=name").unwrap();
+        let res = Response::with((StatusCode::CREATED, ContentLocation::from(url)));
+        assert_eq!(
+            res.headers[headers::CONTENT_LOCATION],
+            "https://example.com/users/1?fields=name"
+        );
+
+        let res = Response::with(ContentLocation::from("/users/1"));
+        assert_eq!(res.headers[headers::CONTENT_LOCATION], "/users/1");
+    }
+
+    #[test]
+    fn test_allow() {
+        let res = Response::with(Allow(&[Method::GET, Method::POST]));
+        assert_eq!(res.status, Some(StatusCode::METHOD_NOT_ALLOWED));
+        assert_eq!(res.headers[headers::ALLOW], "GET, POST");
+
+        let res = Response::with((StatusCode::OK, Allow(&[Method::GET])));
+        assert_eq!(res.status, Some(StatusCode::OK));
+    }
+
+    #[test]
+    fn test_retry_after_delay() {
+        let res = Response::with((
+            StatusCode::TOO_MANY_REQUESTS,
+            RetryAfter::from(Duration::from_millis(120_500)),
+        ));
+        assert_eq!(res.headers[headers::RETRY_AFTER], "120");
+    }
+
+    #[test]
+    fn test_retry_after_date() {
+        let date = SystemTime::UNIX_EPOCH + Duration::from_secs(784_111_777);
+        let res = Response::with((StatusCode::SERVICE_UNAVAILABLE, RetryAfter::from(date)));
+        assert_eq!(
+            res.headers[headers::RETRY_AFTER],
+            "Sun, 06 Nov 1994 08:49:37 GMT"
+        );
+    }
+
+    #[test]
+    fn test_retry_after_date_out_of_range() {
+        let before_epoch = SystemTime::UNIX_EPOCH - Duration::from_secs(1);
+        let after_9999 = SystemTime::UNIX_EPOCH + Duration::from_secs(253_402_300_800);
+        for &date in &[before_epoch, after_9999] {
+            let res = Response::with(RetryAfter::from(date));
+            assert!(!res.headers.contains_key(headers::RETRY_AFTER));
+        }
+    }
+
+    #[test]
+    fn test_csp() {
+        let csp = Csp::new()
+            .directive("default-src", &["'self'"])
+            .directive("script-src", &["'self'", "cdn.example.com"])
+            .nonce("script-src", "r4nd0m");
+        assert_eq!(csp.header_name(), headers::CONTENT_SECURITY_POLICY);
+
+        let res = Response::with(csp);
+        assert_eq!(
+            res.headers[headers::CONTENT_SECURITY_POLICY],
+            "default-src 'self'; script-src 'self' cdn.example.com 'nonce-r4nd0m'"
+        );
+    }
+
+    #[test]
+    fn test_csp_report_only() {
+        let csp = Csp::new()
+            .directive("default-src", &["'none'"])
+            .directive("report-uri", &["/csp-reports"])
+            .report_only();
+        assert_eq!(csp.header_name(), "content-security-policy-report-only");
+
+        let res = Response::with(csp);
+        assert!(!res.headers.contains_key(headers::CONTENT_SECURITY_POLICY));
+        assert_eq!(
+            res.headers[headers::CONTENT_SECURITY_POLICY_REPORT_ONLY],
+            "default-src 'none'; report-uri /csp-reports"
+        );
+    }
+
+    #[test]
+    fn test_www_authenticate() {
+        let res = Response::with(WwwAuthenticate(vec![
+            Challenge::new("Basic")
+                .realm("api")
+                .param("charset", "UTF-8"),
+            Challenge::new("Bearer")
+                .realm("api")
+                .param("error_description", "say \"please\""),
+        ]));
+        assert_eq!(res.status, Some(StatusCode::UNAUTHORIZED));
+
+        let challenges: Vec<_> = res
+            .headers
+            .get_all(headers::WWW_AUTHENTICATE)
+            .iter()
+            .collect();
+        assert_eq!(
+            challenges,
+            [
+                "Basic realm=\"api\", charset=\"UTF-8\"",
+                "Bearer realm=\"api\", error_description=\"say \\\"please\\\"\"",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_set_cookie() {
+        let res = Response::with((
+            StatusCode::OK,
+            SetCookie::new("session", "abc")
+                .path("/")
+                .domain("example.com")
+                .max_age(Duration::from_secs(3600))
+                .http_only(true)
+                .secure(true)
+                .same_site(SameSite::Strict),
+            SetCookie::new("greeting", "hello, world; 100%"),
+        ));
+
+        let cookies: Vec<_> = res.headers.get_all(headers::SET_COOKIE).iter().collect();
+        assert_eq!(
+            cookies,
+            [
+                "session=abc; Path=/; Domain=example.com; Max-Age=3600; HttpOnly; Secure; \
+                 SameSite=Strict",
+                "greeting=hello%2C%20world%3B%20100%25",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_clear_cookie() {
+        let res = Response::with((
+            ClearCookie::new("session")
+                .path("/app")
+                .domain("example.com"),
+            ClearCookie::new("theme"),
+        ));
+
+        let cookies: Vec<_> = res.headers.get_all(headers::SET_COOKIE).iter().collect();
+        assert_eq!(
+            cookies,
+            [
+                "session=; Expires=Thu, 01 Jan 1970 00:00:00 GMT; Max-Age=0; Path=/app; \
+                 Domain=example.com",
+                "theme=; Expires=Thu, 01 Jan 1970 00:00:00 GMT; Max-Age=0",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_content_digest() {
+        let mut res = Response::with((StatusCode::OK, "hello world", ContentDigest));
+        // `echo -n "hello world" | openssl dgst -sha256 -binary | base64`
+        assert_eq!(
+            res.headers[CONTENT_DIGEST],
+            "sha-256=:uU0nuZNNPgilLlLX2n2r+sSE7+N6U4DukIj3rOLvzek=:"
+        );
+
+        let mut body = vec![];
+        res.body.as_mut().unwrap().write_body(&mut body).unwrap();
+        assert_eq!(body, b"hello world");
+    }
+
+    #[test]
+    fn test_content_digest_streaming() {
+        let (_events, receiver) = ::std::sync::mpsc::channel();
+        let res = Response::with((EventStream::new(receiver), ContentDigest));
+        assert!(!res.headers.contains_key(CONTENT_DIGEST));
+        assert!(res.body.is_some());
+    }
+
+    #[test]
+    fn test_preference_applied() {
+        use request::{Handling, Return};
+
+        let applied = [Return::Minimal.as_str(), Handling::Lenient.as_str()];
+        let res = Response::with((StatusCode::NO_CONTENT, PreferenceApplied(&applied)));
+        assert_eq!(
+            res.headers[PREFERENCE_APPLIED],
+            "return=minimal, handling=lenient"
+        );
+    }
+
+    #[cfg(feature = "csv")]
+    #[test]
+    fn test_csv() {
+        #[derive(Serialize)]
+        struct Record {
+            name: &'static str,
+            count: u32,
+        }
+
+        let records = [
+            Record { name: "apples", count: 3 },
+            Record { name: "pears", count: 5 },
+        ];
+        let mut res = Response::with((StatusCode::OK, Csv::new(&records, "fruit.csv")));
+
+        assert_eq!(res.headers[headers::CONTENT_TYPE], "text/csv");
+        assert_eq!(
+            res.headers[headers::CONTENT_DISPOSITION],
+            "attachment; filename=\"fruit.csv\""
+        );
+
+        let mut body = vec![];
+        res.body.as_mut().unwrap().write_body(&mut body).unwrap();
+        assert_eq!(body, b"name,count\napples,3\npears,5\n");
+    }
 }
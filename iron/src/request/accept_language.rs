@@ -0,0 +1,115 @@
+//! Language negotiation using the `Accept-Language` header.
+
+use headers::{self, HeaderMap};
+
+/// Pick the language from `available` the client prefers most.
+///
+/// Tags are tried in order of their quality values, and tags with equal
+/// quality keep their order in the header. A tag matches an available
+/// language exactly, ignoring case, or by prefix: `en` matches `en-US` and
+/// `en-US` matches `en`. Tags with `q=0` are never picked, nor are tags
+/// whose quality is not a number from 0 to 1.
+pub fn preferred_language(headers: &HeaderMap, available: &[&str]) -> Option<String> {
+    let mut tags: Vec<(&str, f32)> = headers
+        .get_all(headers::ACCEPT_LANGUAGE)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .filter_map(parse_tag)
+        .filter(|&(_, quality)| quality > 0.0)
+        .collect();
+    tags.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap());
+
+    tags.iter()
+        .filter_map(|&(tag, _)| {
+            if tag == "*" {
+                return available.first();
+            }
+            available
+                .iter()
+                .find(|language| language.eq_ignore_ascii_case(tag))
+                .or_else(|| {
+                    available
+                        .iter()
+                        .find(|language| is_prefix(tag, language) || is_prefix(language, tag))
+                })
+        }).next()
+        .map(|language| language.to_string())
+}
+
+// The language range and quality of `tag`, or `None` if it is empty or its
+// quality is not a number from 0 to 1.
+fn parse_tag(tag: &str) -> Option<(&str, f32)> {
+    let mut parts = tag.split(';');
+    let name = parts.next()?.trim();
+    if name.is_empty() {
+        return None;
+    }
+
+    let quality = parts.find_map(|param| {
+        let mut param = param.splitn(2, '=');
+        match (param.next()?.trim(), param.next()) {
+            ("q", Some(value)) | ("Q", Some(value)) => Some(value.trim()),
+            _ => None,
+        }
+    });
+    let quality = match quality {
+        Some(value) => value.parse().ok().filter(|q| (0.0..=1.0).contains(q))?,
+        None => 1.0,
+    };
+
+    Some((name, quality))
+}
+
+// Whether `prefix` is the leading subtag range of `tag`, e.g. `en` of `en-US`.
+fn is_prefix(prefix: &str, tag: &str) -> bool {
+    tag.len() > prefix.len()
+        && tag.as_bytes()[prefix.len()] == b'-'
+        && tag[..prefix.len()].eq_ignore_ascii_case(prefix)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn preferred(value: &'static str, available: &[&str]) -> Option<String> {
+        let mut headers = HeaderMap::new();
+        headers.insert(headers::ACCEPT_LANGUAGE, value.parse().unwrap());
+        preferred_language(&headers, available)
+    }
+
+    #[test]
+    fn test_exact_match() {
+        assert_eq!(preferred("de-DE, en", &["en", "de-de"]), Some("de-de".into()));
+    }
+
+    #[test]
+    fn test_prefix_match() {
+        assert_eq!(preferred("en", &["fr", "en-US"]), Some("en-US".into()));
+        assert_eq!(preferred("en-GB", &["fr", "en"]), Some("en".into()));
+    }
+
+    #[test]
+    fn test_quality_ordering() {
+        assert_eq!(
+            preferred("fr;q=0.5, de;q=0.9, en;q=0", &["en", "fr", "de"]),
+            Some("de".into())
+        );
+        assert_eq!(preferred("*;q=0.1, fr", &["en", "fr"]), Some("fr".into()));
+    }
+
+    #[test]
+    fn test_invalid_quality() {
+        let en = Some("en".to_owned());
+        assert_eq!(preferred("fr;q=2, en;q=0.5", &["en", "fr"]), en);
+        assert_eq!(preferred("fr;q=-1, en;q=0.5", &["en", "fr"]), en);
+        assert_eq!(preferred("fr;q=NaN, en;q=0.5", &["en", "fr"]), en);
+        assert_eq!(preferred("fr;q=high", &["en", "fr"]), None);
+    }
+
+    #[test]
+    fn test_no_match() {
+        assert_eq!(preferred("ja, zh;q=0.8", &["en", "fr"]), None);
+        assert_eq!(preferred_language(&HeaderMap::new(), &["en"]), None);
+    }
+}
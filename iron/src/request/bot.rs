@@ -0,0 +1,95 @@
+//! Detection of bot and crawler user agents.
+
+use typemap::Key;
+
+use headers::{self, HeaderMap};
+
+/// The user agent substrings `Request::is_bot` matches by default.
+pub const DEFAULT_BOT_PATTERNS: &[&str] = &[
+    "googlebot",
+    "bingbot",
+    "slurp",
+    "duckduckbot",
+    "baiduspider",
+    "yandexbot",
+    "facebookexternalhit",
+    "twitterbot",
+    "linkedinbot",
+    "applebot",
+    "petalbot",
+    "semrushbot",
+    "ahrefsbot",
+    "crawler",
+    "spider",
+];
+
+/// Overrides the user agent substrings `Request::is_bot` matches.
+///
+/// Insert a list into `Request::extensions`, for example from a
+/// `BeforeMiddleware`, to replace `DEFAULT_BOT_PATTERNS` for that request.
+/// Patterns are matched ignoring ASCII case.
+pub struct BotPatterns;
+
+impl Key for BotPatterns {
+    type Value = Vec<String>;
+}
+
+pub(crate) fn is_bot<S: AsRef<str>>(headers: &HeaderMap, patterns: &[S]) -> bool {
+    let user_agent = match headers
+        .get(headers::USER_AGENT)
+        .and_then(|value| value.to_str().ok())
+    {
+        Some(user_agent) => user_agent.to_ascii_lowercase(),
+        None => return false,
+    };
+
+    patterns
+        .iter()
+        .any(|pattern| user_agent.contains(&*pattern.as_ref().to_ascii_lowercase()))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use Request;
+
+    fn request(user_agent: &'static str) -> Request {
+        let mut req = Request::stub();
+        req.headers
+            .insert(headers::USER_AGENT, user_agent.parse().unwrap());
+        req
+    }
+
+    #[test]
+    fn test_known_bot() {
+        let req = request(
+            "Mozilla/5.0 (compatible; Googlebot/2.1; +http://www.google.com/bot.html)",
+        );
+        assert!(req.is_bot());
+    }
+
+    #[test]
+    fn test_browser() {
+        let req = request(
+            "Mozilla/5.0 (X11; Linux x86_64; rv:128.0) Gecko/20100101 Firefox/128.0",
+        );
+        assert!(!req.is_bot());
+        assert!(!Request::stub().is_bot());
+    }
+
+    #[test]
+    fn test_custom_patterns() {
+        let mut req = request("InternalMonitor/1.0");
+        assert!(!req.is_bot());
+
+        req.extensions
+            .insert::<BotPatterns>(vec!["internalmonitor".into()]);
+        assert!(req.is_bot());
+
+        let mut req = request("Googlebot/2.1");
+        req.extensions
+            .insert::<BotPatterns>(vec!["internalmonitor".into()]);
+        assert!(!req.is_bot());
+    }
+}
@@ -0,0 +1,118 @@
+//! Parsing of request `Cache-Control` directives.
+
+use std::time::Duration;
+
+use headers::{self, HeaderMap};
+
+/// The `Cache-Control` directives sent by a client.
+///
+/// Unknown directives and directives with invalid values are ignored.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CacheControl {
+    /// `no-cache`: a cached response must be revalidated before it is used.
+    pub no_cache: bool,
+
+    /// `no-store`: nothing about the request or response may be stored.
+    pub no_store: bool,
+
+    /// `no-transform`: intermediaries must not transform the payload.
+    pub no_transform: bool,
+
+    /// `only-if-cached`: the client only wants a stored response.
+    pub only_if_cached: bool,
+
+    /// `max-age`: the maximum age of a response the client will accept.
+    pub max_age: Option<Duration>,
+
+    /// `max-stale`: how far past its expiry a response may be.
+    ///
+    /// A `max-stale` without a value accepts any staleness, which is
+    /// represented as `Duration::MAX`.
+    pub max_stale: Option<Duration>,
+
+    /// `min-fresh`: how long a response must still be fresh for.
+    pub min_fresh: Option<Duration>,
+}
+
+impl CacheControl {
+    /// Parse the directives from all `Cache-Control` headers in `headers`.
+    pub fn from_headers(headers: &HeaderMap) -> CacheControl {
+        let mut cache_control = CacheControl::default();
+
+        let directives = headers
+            .get_all(headers::CACHE_CONTROL)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','));
+
+        for directive in directives {
+            let mut parts = directive.splitn(2, '=');
+            let name = parts.next().unwrap_or_default().trim().to_ascii_lowercase();
+            let seconds = parts.next().and_then(parse_seconds);
+
+            match &*name {
+                "no-cache" => cache_control.no_cache = true,
+                "no-store" => cache_control.no_store = true,
+                "no-transform" => cache_control.no_transform = true,
+                "only-if-cached" => cache_control.only_if_cached = true,
+                "max-age" => cache_control.max_age = seconds.or(cache_control.max_age),
+                "max-stale" => {
+                    cache_control.max_stale = seconds.or(Some(Duration::MAX));
+                }
+                "min-fresh" => cache_control.min_fresh = seconds.or(cache_control.min_fresh),
+                _ => (),
+            }
+        }
+
+        cache_control
+    }
+}
+
+fn parse_seconds(value: &str) -> Option<Duration> {
+    value
+        .trim()
+        .trim_matches('"')
+        .parse()
+        .ok()
+        .map(Duration::from_secs)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn parse(value: &'static str) -> CacheControl {
+        let mut headers = HeaderMap::new();
+        headers.insert(headers::CACHE_CONTROL, value.parse().unwrap());
+        CacheControl::from_headers(&headers)
+    }
+
+    #[test]
+    fn test_no_cache() {
+        let cache_control = parse("no-cache");
+        assert!(cache_control.no_cache);
+        assert_eq!(cache_control.max_age, None);
+    }
+
+    #[test]
+    fn test_max_age_zero() {
+        let cache_control = parse("max-age=0, No-Transform");
+        assert_eq!(cache_control.max_age, Some(Duration::from_secs(0)));
+        assert!(cache_control.no_transform);
+        assert!(!cache_control.no_cache);
+    }
+
+    #[test]
+    fn test_max_stale_without_value() {
+        assert_eq!(parse("max-stale").max_stale, Some(Duration::MAX));
+        assert_eq!(parse("max-stale=30").max_stale, Some(Duration::from_secs(30)));
+    }
+
+    #[test]
+    fn test_absent_header() {
+        assert_eq!(
+            CacheControl::from_headers(&HeaderMap::new()),
+            CacheControl::default()
+        );
+    }
+}
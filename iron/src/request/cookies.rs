@@ -0,0 +1,98 @@
+//! Parsing of the `Cookie` request header.
+
+use std::collections::HashMap;
+
+use headers::{self, HeaderMap};
+use url_ext::percent_encoding::percent_decode;
+
+/// Parse the cookies from all `Cookie` headers in `headers`.
+///
+/// Pairs without a `=` or with an empty name are skipped. Values wrapped in
+/// double quotes are unquoted. If a name appears more than once, the first
+/// value wins.
+pub fn cookie_map(headers: &HeaderMap) -> HashMap<String, String> {
+    let mut cookies = HashMap::new();
+
+    let pairs = headers
+        .get_all(headers::COOKIE)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(';'));
+
+    for pair in pairs {
+        let mut parts = pair.splitn(2, '=');
+        let name = parts.next().unwrap_or_default().trim();
+        let value = match parts.next() {
+            Some(value) if !name.is_empty() => value.trim(),
+            _ => continue,
+        };
+        let value = if value.len() >= 2 && value.starts_with('"') && value.ends_with('"') {
+            &value[1..value.len() - 1]
+        } else {
+            value
+        };
+
+        cookies
+            .entry(name.to_owned())
+            .or_insert_with(|| value.to_owned());
+    }
+
+    cookies
+}
+
+/// Like `cookie_map`, with values percent-decoded.
+///
+/// Sequences that do not decode to UTF-8 are replaced with U+FFFD.
+pub fn decoded_cookie_map(headers: &HeaderMap) -> HashMap<String, String> {
+    cookie_map(headers)
+        .into_iter()
+        .map(|(name, value)| {
+            let value = percent_decode(value.as_bytes()).decode_utf8_lossy();
+            (name, value.into_owned())
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_cookie_map() {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            headers::COOKIE,
+            "session=abc123; theme=\"dark\"; malformed; =nameless"
+                .parse()
+                .unwrap(),
+        );
+        headers.append(headers::COOKIE, "lang=en; session=ignored".parse().unwrap());
+
+        let cookies = cookie_map(&headers);
+        assert_eq!(cookies.len(), 3);
+        assert_eq!(cookies["session"], "abc123");
+        assert_eq!(cookies["theme"], "dark");
+        assert_eq!(cookies["lang"], "en");
+    }
+
+    #[test]
+    fn test_decoded_cookie_map() {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            headers::COOKIE,
+            "greeting=hello%2C%20world; empty=; plus=a+b"
+                .parse()
+                .unwrap(),
+        );
+
+        let cookies = decoded_cookie_map(&headers);
+        assert_eq!(cookies["greeting"], "hello, world");
+        assert_eq!(cookies["empty"], "");
+        assert_eq!(cookies["plus"], "a+b");
+    }
+
+    #[test]
+    fn test_no_cookies() {
+        assert!(cookie_map(&HeaderMap::new()).is_empty());
+    }
+}
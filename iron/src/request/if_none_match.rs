@@ -0,0 +1,108 @@
+//! Parsing of the `If-None-Match` request header.
+
+use headers::{self, HeaderMap};
+
+/// The entity tags a client sent in `If-None-Match` to revalidate a cached
+/// representation.
+///
+/// If the current representation `matches`, a `GET` or `HEAD` request should
+/// be answered with `304 Not Modified`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum IfNoneMatch {
+    /// `*`, matching any current representation.
+    Any,
+
+    /// Entity tags, including their quotes and any `W/` prefix.
+    Tags(Vec<String>),
+}
+
+impl IfNoneMatch {
+    /// Parse all `If-None-Match` headers in `headers`.
+    ///
+    /// Returns `None` if the header is absent or holds no entity tags.
+    pub fn from_headers(headers: &HeaderMap) -> Option<IfNoneMatch> {
+        let mut tags = Vec::new();
+        let values = headers
+            .get_all(headers::IF_NONE_MATCH)
+            .iter()
+            .filter_map(|value| value.to_str().ok());
+
+        for tag in values.flat_map(|value| value.split(',')).map(str::trim) {
+            if tag == "*" {
+                return Some(IfNoneMatch::Any);
+            }
+            if tag.ends_with('"') && (tag.starts_with('"') || tag.starts_with("W/\"")) {
+                tags.push(tag.to_owned());
+            }
+        }
+
+        if tags.is_empty() {
+            None
+        } else {
+            Some(IfNoneMatch::Tags(tags))
+        }
+    }
+
+    /// Whether the representation with `etag` is one the client has cached.
+    ///
+    /// Entity tags are compared weakly, so `W/"v1"` and `"v1"` match.
+    pub fn matches(&self, etag: Option<&str>) -> bool {
+        match *self {
+            IfNoneMatch::Any => etag.is_some(),
+            IfNoneMatch::Tags(ref tags) => {
+                etag.is_some_and(|etag| tags.iter().any(|tag| opaque_tag(tag) == opaque_tag(etag)))
+            }
+        }
+    }
+}
+
+// The quoted part of an entity tag, without any `W/` prefix.
+fn opaque_tag(etag: &str) -> &str {
+    etag.trim_start_matches("W/")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use modifiers::ETag;
+    use request::IfRange;
+
+    fn headers(name: headers::HeaderName, value: &'static str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(name, value.parse().unwrap());
+        headers
+    }
+
+    #[test]
+    fn test_weak_comparison() {
+        let if_none_match =
+            IfNoneMatch::from_headers(&headers(headers::IF_NONE_MATCH, "\"v0\", W/\"v1\""))
+                .unwrap();
+        assert!(if_none_match.matches(Some(&ETag::weak("v1").to_string())));
+        assert!(if_none_match.matches(Some(&ETag::strong("v1").to_string())));
+        assert!(!if_none_match.matches(Some(&ETag::weak("v2").to_string())));
+        assert!(!if_none_match.matches(None));
+    }
+
+    #[test]
+    fn test_weak_etag_not_for_if_range() {
+        let etag = ETag::weak("v1").to_string();
+        let if_none_match =
+            IfNoneMatch::from_headers(&headers(headers::IF_NONE_MATCH, "W/\"v1\"")).unwrap();
+        let if_range = IfRange::from_headers(&headers(headers::IF_RANGE, "W/\"v1\"")).unwrap();
+
+        assert!(if_none_match.matches(Some(&etag)));
+        assert!(!if_range.matches(Some(&etag), None));
+    }
+
+    #[test]
+    fn test_any() {
+        let if_none_match =
+            IfNoneMatch::from_headers(&headers(headers::IF_NONE_MATCH, "*")).unwrap();
+        assert_eq!(if_none_match, IfNoneMatch::Any);
+        assert!(if_none_match.matches(Some("\"v1\"")));
+        assert!(!if_none_match.matches(None));
+        assert_eq!(IfNoneMatch::from_headers(&HeaderMap::new()), None);
+    }
+}
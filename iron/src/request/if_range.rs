@@ -0,0 +1,91 @@
+//! Parsing of the `If-Range` request header.
+
+use std::time::SystemTime;
+
+use headers::{self, HeaderMap};
+use httpdate;
+
+/// The validator a client sent in `If-Range` when resuming a download.
+///
+/// A range request should only be answered with `206 Partial Content` if
+/// the representation still `matches` the validator; otherwise the full
+/// representation is sent with `200 OK`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum IfRange {
+    /// An entity tag, including its quotes and any `W/` prefix.
+    ETag(String),
+
+    /// The `Last-Modified` date the client saw.
+    Date(SystemTime),
+}
+
+impl IfRange {
+    /// Parse the `If-Range` header in `headers`.
+    ///
+    /// Returns `None` if the header is absent or neither an entity tag nor an
+    /// HTTP-date.
+    pub fn from_headers(headers: &HeaderMap) -> Option<IfRange> {
+        let value = headers.get(headers::IF_RANGE)?.to_str().ok()?.trim();
+        if value.ends_with('"') && (value.starts_with('"') || value.starts_with("W/\"")) {
+            Some(IfRange::ETag(value.to_owned()))
+        } else {
+            httpdate::parse_http_date(value).ok().map(IfRange::Date)
+        }
+    }
+
+    /// Whether the representation with `etag` and `last_modified` is the one
+    /// the client saw, so a partial response may be sent.
+    ///
+    /// Entity tags are compared strongly, so weak tags never match. Dates
+    /// only match if they are exactly equal.
+    pub fn matches(&self, etag: Option<&str>, last_modified: Option<SystemTime>) -> bool {
+        match *self {
+            IfRange::ETag(ref expected) => {
+                !expected.starts_with("W/") && etag.is_some_and(|etag| etag == expected)
+            }
+            IfRange::Date(expected) => last_modified.is_some_and(|date| date == expected),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::time::{Duration, UNIX_EPOCH};
+
+    fn parse(value: &'static str) -> Option<IfRange> {
+        let mut headers = HeaderMap::new();
+        headers.insert(headers::IF_RANGE, value.parse().unwrap());
+        IfRange::from_headers(&headers)
+    }
+
+    #[test]
+    fn test_etag_match() {
+        let if_range = parse("\"v2\"").unwrap();
+        assert_eq!(if_range, IfRange::ETag("\"v2\"".to_owned()));
+        assert!(if_range.matches(Some("\"v2\""), None));
+    }
+
+    #[test]
+    fn test_etag_mismatch() {
+        assert!(!parse("\"v1\"").unwrap().matches(Some("\"v2\""), None));
+        assert!(!parse("W/\"v2\"").unwrap().matches(Some("W/\"v2\""), None));
+        assert!(!parse("\"v2\"").unwrap().matches(None, None));
+    }
+
+    #[test]
+    fn test_date() {
+        let date = UNIX_EPOCH + Duration::from_secs(784_111_777);
+        let if_range = parse("Sun, 06 Nov 1994 08:49:37 GMT").unwrap();
+        assert_eq!(if_range, IfRange::Date(date));
+        assert!(if_range.matches(None, Some(date)));
+        assert!(!if_range.matches(None, Some(date + Duration::from_secs(1))));
+    }
+
+    #[test]
+    fn test_invalid() {
+        assert_eq!(parse("yesterday"), None);
+        assert_eq!(IfRange::from_headers(&HeaderMap::new()), None);
+    }
+}
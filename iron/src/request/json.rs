@@ -0,0 +1,179 @@
+//! Deserializing `application/json` request bodies.
+
+use std::error::Error;
+use std::fmt;
+
+use mime::{self, Mime};
+use serde::de::DeserializeOwned;
+use serde_json;
+
+use headers::{self, HeaderMap};
+use {IronError, StatusCode};
+
+use super::{BodyError, Request};
+
+/// The error returned when a request body cannot be read as JSON.
+#[derive(Debug)]
+pub enum JsonError {
+    /// The request's `Content-Type` is missing or not JSON.
+    NotJson,
+
+    /// The body could not be read.
+    Body(BodyError),
+
+    /// The body is not valid JSON, or does not match the expected type.
+    Malformed(serde_json::Error),
+}
+
+impl JsonError {
+    /// The status code to respond with for this error.
+    ///
+    /// Errors reading the body have the status of the `BodyError`; all others
+    /// result in `400 Bad Request`.
+    pub fn status(&self) -> StatusCode {
+        match *self {
+            JsonError::Body(ref e) => e.status(),
+            _ => StatusCode::BAD_REQUEST,
+        }
+    }
+}
+
+impl From<JsonError> for IronError {
+    fn from(e: JsonError) -> IronError {
+        match e {
+            JsonError::Body(e) => IronError::from(e),
+            e => {
+                let status = e.status();
+                IronError::new(e, status)
+            }
+        }
+    }
+}
+
+impl fmt::Display for JsonError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            JsonError::NotJson => f.write_str("Request body is not application/json"),
+            JsonError::Body(ref e) => e.fmt(f),
+            JsonError::Malformed(ref e) => write!(f, "Malformed JSON body: {}", e),
+        }
+    }
+}
+
+impl Error for JsonError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match *self {
+            JsonError::NotJson => None,
+            JsonError::Body(ref e) => Some(e),
+            JsonError::Malformed(ref e) => Some(e),
+        }
+    }
+}
+
+pub fn parse<T: DeserializeOwned>(req: &mut Request) -> Result<T, JsonError> {
+    if !is_json(&req.headers) {
+        return Err(JsonError::NotJson);
+    }
+    let body = req.get_body_contents().map_err(JsonError::Body)?;
+    serde_json::from_slice(body).map_err(JsonError::Malformed)
+}
+
+// Whether the `Content-Type` is `application/json` or a `+json` type, with
+// any parameters.
+fn is_json(headers: &HeaderMap) -> bool {
+    let mime: Option<Mime> = headers
+        .get(headers::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse().ok());
+    match mime {
+        Some(mime) => {
+            mime.type_() == mime::APPLICATION
+                && (mime.subtype() == mime::JSON || mime.suffix() == Some(mime::JSON))
+        }
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use serde::Deserialize;
+
+    use hyper::Body;
+    use Method;
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct User {
+        name: String,
+        age: u32,
+    }
+
+    fn request(content_type: &str, body: &'static str) -> Request {
+        let mut req = Request::stub();
+        req.method = Method::POST;
+        req.headers
+            .insert(headers::CONTENT_TYPE, content_type.parse().unwrap());
+        req.body = Some(Body::from(body));
+        req
+    }
+
+    #[test]
+    fn test_valid() {
+        let mut req = request("application/json", r#"{"name": "Ada", "age": 36}"#);
+        let user: User = parse(&mut req).unwrap();
+        assert_eq!(
+            user,
+            User {
+                name: "Ada".to_owned(),
+                age: 36,
+            }
+        );
+    }
+
+    #[test]
+    fn test_content_type_parameters() {
+        let body = r#"{"name": "Ada", "age": 36}"#;
+        let mut req = request("application/json; charset=utf-8", body);
+        assert!(parse::<User>(&mut req).is_ok());
+        let mut req = request("application/merge-patch+json", body);
+        assert!(parse::<User>(&mut req).is_ok());
+    }
+
+    #[test]
+    fn test_malformed() {
+        let mut req = request("application/json", r#"{"name": "Ada", "age": "#);
+        let err = parse::<User>(&mut req).unwrap_err();
+        assert!(matches!(err, JsonError::Malformed(_)));
+
+        let err = IronError::from(err);
+        assert_eq!(err.response.status, Some(StatusCode::BAD_REQUEST));
+    }
+
+    #[test]
+    fn test_json_value() {
+        let mut req = request("application/json", r#"{"name": "Ada", "tags": [1, 2]}"#);
+        let value = req.json_value().unwrap();
+        assert_eq!(value["name"], "Ada");
+        assert_eq!(value["tags"][1], 2);
+
+        let mut req = request("application/json", r#"[true, null, "x"]"#);
+        let value = req.json_value().unwrap();
+        assert_eq!(value, serde_json::json!([true, null, "x"]));
+
+        let mut req = request("application/json", "[1, 2");
+        let err = req.json_value().unwrap_err();
+        assert_eq!(err.response.status, Some(StatusCode::BAD_REQUEST));
+    }
+
+    #[test]
+    fn test_not_json() {
+        let mut req = request("text/plain", r#"{"name": "Ada", "age": 36}"#);
+        let err = parse::<User>(&mut req).unwrap_err();
+        assert!(matches!(err, JsonError::NotJson));
+        assert_eq!(err.status(), StatusCode::BAD_REQUEST);
+
+        req.headers.remove(headers::CONTENT_TYPE);
+        assert!(matches!(parse::<User>(&mut req), Err(JsonError::NotJson)));
+    }
+}
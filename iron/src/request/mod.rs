@@ -1,8 +1,16 @@
 //! Iron's HTTP Request representation and associated methods.
+use std::collections::HashMap;
+use std::error::Error;
 use std::fmt::{self, Debug};
+use std::mem;
 use std::net::SocketAddr;
+use std::sync::Arc;
+use std::thread::{self, Thread};
+use std::time::{Duration, Instant};
 
-use futures::Stream;
+use encoding_rs::{Encoding, UTF_8};
+use futures::executor::{self, Notify};
+use futures::Async;
 
 use http;
 use http::version::Version as HttpVersion;
@@ -10,6 +18,7 @@ use http::version::Version as HttpVersion;
 use method::Method;
 use plugin::Extensible;
 use typemap::{Key, TypeMap};
+use url_ext::form_urlencoded;
 
 pub use hyper::Body;
 pub use hyper::Request as HttpRequest;
@@ -17,25 +26,66 @@ pub use hyper::Request as HttpRequest;
 #[cfg(test)]
 use std::net::ToSocketAddrs;
 
+pub use self::bot::{BotPatterns, DEFAULT_BOT_PATTERNS};
+pub use self::cache_control::CacheControl;
+pub use self::if_none_match::IfNoneMatch;
+pub use self::if_range::IfRange;
+#[cfg(feature = "json")]
+pub use self::json::JsonError;
+#[cfg(feature = "multipart")]
+pub use self::multipart::{Multipart, MultipartError, MultipartLimits, Part};
+pub use self::origin::Origin;
+pub use self::prefer::{Handling, Preferences, Return, PREFER, PREFERENCE_APPLIED};
+pub use self::range::{ByteRange, ByteRanges};
 pub use self::url::Url;
 
 use error::HttpError;
-use headers::{self, HeaderMap};
-use {Plugin, Protocol, Set};
+use middleware::base_path::BasePathKey;
+use middleware::csrf::CsrfToken;
+#[cfg(feature = "jwt")]
+use middleware::jwt::{self, JwtError};
+use middleware::server_timing::ServerTimingEntries;
+#[cfg(feature = "cookie")]
+use middleware::signed_cookies::{self, MissingSigningKey};
+#[cfg(feature = "json")]
+use serde::de::DeserializeOwned;
+use headers::{self, HeaderMap, HeaderValue};
+use {mime, IronError, IronResult, Plugin, Protocol, Set, StatusCode};
 
+mod accept_language;
+mod bot;
+mod cache_control;
+mod cookies;
+mod if_none_match;
+mod if_range;
+#[cfg(feature = "json")]
+mod json;
+#[cfg(feature = "multipart")]
+mod multipart;
+mod origin;
+mod prefer;
+mod range;
 mod url;
 
 /// The `Request` given to all `Middleware`.
 ///
 /// Stores all the properties of the client's request plus
 /// an `TypeMap` for data communication between middleware.
+#[allow(clippy::manual_non_exhaustive)]
 pub struct Request {
     /// The requested URL.
     pub url: Url,
 
-    /// The local address of the request.
+    /// The local address of the request, i.e. the address the server is
+    /// bound to.
     pub local_addr: Option<SocketAddr>,
 
+    /// The address of the client which sent the request.
+    ///
+    /// `None` if the request did not arrive on a TCP connection accepted by
+    /// `Iron::http`, e.g. when calling the service directly.
+    pub remote_addr: Option<SocketAddr>,
+
     /// The request headers.
     pub headers: HeaderMap,
 
@@ -51,18 +101,37 @@ pub struct Request {
     /// The version of the HTTP protocol used.
     pub version: HttpVersion,
 
+    /// The maximum number of bytes `get_body_contents` will read.
+    ///
+    /// This starts out as `Iron::max_body_size` and may be changed by
+    /// middleware to raise or lower the limit for a single request.
+    /// `None` means the body is read without a limit.
+    pub body_limit: Option<usize>,
+
+    /// The minimum rate at which `get_body_contents` expects the body to
+    /// arrive.
+    ///
+    /// This starts out as `Iron::min_body_rate` and may be changed by
+    /// middleware for a single request. `None` means the body may arrive
+    /// at any rate.
+    pub min_body_rate: Option<MinDataRate>,
+
+    /// When the request was received by the server.
+    pub received_at: Instant,
+
     _p: (),
 }
 
 impl Debug for Request {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        try!(writeln!(f, "Request {{"));
+        writeln!(f, "Request {{")?;
 
-        try!(writeln!(f, "    url: {:?}", self.url));
-        try!(writeln!(f, "    method: {:?}", self.method));
-        try!(writeln!(f, "    local_addr: {:?}", self.local_addr));
+        writeln!(f, "    url: {:?}", self.url)?;
+        writeln!(f, "    method: {:?}", self.method)?;
+        writeln!(f, "    local_addr: {:?}", self.local_addr)?;
+        writeln!(f, "    remote_addr: {:?}", self.remote_addr)?;
 
-        try!(write!(f, "}}"));
+        write!(f, "}}")?;
         Ok(())
     }
 }
@@ -74,6 +143,7 @@ impl Request {
     pub fn from_http(
         req: HttpRequest<Body>,
         local_addr: Option<SocketAddr>,
+        remote_addr: Option<SocketAddr>,
         protocol: &Protocol,
     ) -> Result<Request, String> {
         let (
@@ -137,11 +207,15 @@ impl Request {
         Ok(Request {
             url,
             local_addr,
+            remote_addr,
             headers,
             body: Some(body),
             method,
             extensions: TypeMap::new(),
             version,
+            body_limit: None,
+            min_body_rate: None,
+            received_at: Instant::now(),
             _p: (),
         })
     }
@@ -150,47 +224,697 @@ impl Request {
     ///
     /// This consumes the body future and turns it into Vec<u8>.  Note this should not be called
     /// from the main hyper thread, as it will potentially deadlock.
-    pub fn get_body_contents(&mut self) -> Result<&Vec<u8>, HttpError> {
+    ///
+    /// Reading stops with `BodyError::TooLarge` as soon as the body grows past
+    /// `body_limit`, and with `BodyError::TooSlow` as soon as it arrives slower
+    /// than `min_body_rate`. A body whose `Content-Length` is past the limit is
+    /// refused without reading any of it.
+    ///
+    /// HTTP/1 `GET`, `HEAD` and `DELETE` requests without a `Content-Length`
+    /// or `Transfer-Encoding` header carry no body, so for them the body is
+    /// empty without waiting on the connection. HTTP/2 requests need neither
+    /// header to carry a body, so theirs is always read.
+    pub fn get_body_contents(&mut self) -> Result<&Vec<u8>, BodyError> {
+        if self.body.is_some() && !self.may_have_body() {
+            self.body = None;
+            self.extensions.insert::<RequestBodyKey>(Vec::new());
+        }
+
+        if self.body.is_some() {
+            self.check_content_length()?;
+        }
         if let Some(reader) = self.body.take() {
-            let body = reader.wait().fold(Ok(Vec::new()), |r, input| {
-                if let Ok(mut v) = r {
-                    input.map(move |next_body_chunk| {
-                        v.extend_from_slice(&next_body_chunk);
-                        v
-                    })
-                } else {
-                    r
-                }
-            });
-            match body {
-                Ok(body) => self.extensions.insert::<RequestBodyKey>(body),
-                Err(e) => return Err(e),
-            };
+            let body = read_body(reader, self.body_limit, self.min_body_rate)?;
+            self.extensions.insert::<RequestBodyKey>(body);
         }
         Ok(self.extensions.get::<RequestBodyKey>().unwrap())
     }
 
+    /// Read the whole body, leaving an empty body in its place.
+    ///
+    /// Unlike `get_body_contents`, the contents are not kept in the request,
+    /// so a second call returns an empty vector. The same limits apply, and
+    /// the same notes on blocking the current thread.
+    pub fn get_body_bytes(&mut self) -> Result<Vec<u8>, BodyError> {
+        if !self.may_have_body() {
+            return Ok(Vec::new());
+        }
+        self.check_content_length()?;
+        match self.body.as_mut() {
+            Some(body) => {
+                let body = mem::replace(body, Body::empty());
+                read_body(body, self.body_limit, self.min_body_rate)
+            }
+            None => Ok(Vec::new()),
+        }
+    }
+
+    // Refuse a body declared to be larger than `body_limit` up front, rather
+    // than reading up to the limit first.
+    fn check_content_length(&self) -> Result<(), BodyError> {
+        let declared = self
+            .headers
+            .get(headers::CONTENT_LENGTH)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.trim().parse::<u64>().ok());
+        match (self.body_limit, declared) {
+            (Some(limit), Some(declared)) if declared > limit as u64 => {
+                Err(BodyError::TooLarge(limit))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn may_have_body(&self) -> bool {
+        if self.version >= HttpVersion::HTTP_2 {
+            return true;
+        }
+        match self.method {
+            Method::GET | Method::HEAD | Method::DELETE => {
+                self.headers.contains_key(headers::CONTENT_LENGTH)
+                    || self.headers.contains_key(headers::TRANSFER_ENCODING)
+            }
+            _ => true,
+        }
+    }
+
+    /// Read the request body as a string, decoded with the charset declared
+    /// in its `Content-Type`.
+    ///
+    /// Bodies without a charset, or with a charset that is not recognized, are
+    /// decoded as UTF-8. Bytes that are invalid in the charset result in
+    /// `BodyError::Undecodable`.
+    pub fn body_string_charset(&mut self) -> Result<String, BodyError> {
+        let encoding = self
+            .headers
+            .get(headers::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.parse::<mime::Mime>().ok())
+            .and_then(|mime| {
+                mime.get_param(mime::CHARSET)
+                    .and_then(|charset| Encoding::for_label(charset.as_str().as_bytes()))
+            }).unwrap_or(UTF_8);
+
+        let body = self.get_body_contents()?;
+        encoding
+            .decode_without_bom_handling_and_without_replacement(body)
+            .map(|body| body.into_owned())
+            .ok_or_else(|| BodyError::Undecodable(encoding.name()))
+    }
+
+    /// Deserialize the body as JSON.
+    ///
+    /// The `Content-Type` must be `application/json` or a `+json` type, with
+    /// any parameters. The body is read as with `get_body_contents`. Fails
+    /// with `400 Bad Request` on a wrong content type or malformed JSON.
+    #[cfg(feature = "json")]
+    pub fn get_json<T: DeserializeOwned>(&mut self) -> IronResult<T> {
+        json::parse(self).map_err(IronError::from)
+    }
+
+    /// Parse the body as an arbitrary JSON value, for handlers without a
+    /// type to deserialize into.
+    ///
+    /// Behaves like `get_json`, so the body is bounded by `body_limit`.
+    #[cfg(feature = "json")]
+    pub fn json_value(&mut self) -> IronResult<serde_json::Value> {
+        self.get_json()
+    }
+
+    /// Read a `multipart/form-data` body, streaming each part to a temporary file.
+    ///
+    /// Fails with `MultipartError::PartTooLarge` or `MultipartError::TooLarge`
+    /// as soon as a limit is exceeded; `MultipartError` converts into an
+    /// `IronError` with the matching status, `413 Payload Too Large` for those.
+    #[cfg(feature = "multipart")]
+    pub fn multipart(&mut self, limits: MultipartLimits) -> Result<Vec<Part>, MultipartError> {
+        multipart::parse(self, limits)
+    }
+
+    /// Read a `multipart/form-data` body into its parts, which can be read
+    /// from in turn.
+    ///
+    /// Like `multipart` with the default limits, so the size of the whole
+    /// body is bounded by `body_limit`. A body that is not multipart is
+    /// answered with `400 Bad Request`.
+    #[cfg(feature = "multipart")]
+    pub fn get_multipart(&mut self) -> IronResult<Multipart> {
+        multipart::parts(self).map_err(IronError::from)
+    }
+
+    /// All cookies sent with this request, by name.
+    ///
+    /// Malformed pairs are skipped; for repeated names the first value is used.
+    pub fn cookie_map(&self) -> HashMap<String, String> {
+        cookies::cookie_map(&self.headers)
+    }
+
+    /// All cookies sent with this request, by name, with values
+    /// percent-decoded.
+    ///
+    /// The cookies are parsed on the first call and kept in the request's
+    /// extensions. As with `cookie_map`, for repeated names the first value is
+    /// used.
+    pub fn cookies(&mut self) -> &HashMap<String, String> {
+        if !self.extensions.contains::<CookiesKey>() {
+            let cookies = cookies::decoded_cookie_map(&self.headers);
+            self.extensions.insert::<CookiesKey>(cookies);
+        }
+        self.extensions.get::<CookiesKey>().unwrap()
+    }
+
+    /// The request headers as a map from lowercase names to values.
+    ///
+    /// The values of a header sent more than once are joined with `", "`, in
+    /// the order they were received. Bytes that are not valid UTF-8 are
+    /// replaced with `U+FFFD`.
+    pub fn headers_lower(&self) -> HashMap<String, String> {
+        let mut headers = HashMap::new();
+        for name in self.headers.keys() {
+            let values: Vec<_> = self
+                .headers
+                .get_all(name)
+                .iter()
+                .map(|value| String::from_utf8_lossy(value.as_bytes()))
+                .collect();
+            // `HeaderName`s are always lowercase.
+            headers.insert(name.as_str().to_owned(), values.join(", "));
+        }
+        headers
+    }
+
+    /// The parameters in the query string, by name.
+    ///
+    /// Names and values are percent-decoded with `+` standing for a space.
+    /// The values of a parameter given more than once are kept in order, e.g.
+    /// `?a=1&a=2` yields `["1", "2"]` for `a`. A parameter without `=` has an
+    /// empty value.
+    pub fn query_pairs(&self) -> HashMap<String, Vec<String>> {
+        let query = self.url.query().unwrap_or_default();
+        form_pairs(query.as_bytes())
+    }
+
+    /// The fields of an `application/x-www-form-urlencoded` body, by name.
+    ///
+    /// Fields are decoded like `query_pairs`, with repeated names kept in
+    /// order. The body is read as with `get_body_contents`. Any other content
+    /// type fails with `415 Unsupported Media Type`.
+    pub fn get_form(&mut self) -> IronResult<HashMap<String, Vec<String>>> {
+        let is_form = self
+            .headers
+            .get(headers::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.parse::<mime::Mime>().ok())
+            .is_some_and(|mime| mime.essence_str() == "application/x-www-form-urlencoded");
+        if !is_form {
+            return Err(IronError::new(NotForm, StatusCode::UNSUPPORTED_MEDIA_TYPE));
+        }
+        Ok(form_pairs(self.get_body_contents()?))
+    }
+
+    /// Whether the request method is idempotent, so repeating the request has
+    /// the same effect as sending it once.
+    ///
+    /// This holds for `GET`, `HEAD`, `OPTIONS`, `TRACE`, `PUT` and `DELETE`.
+    pub fn is_idempotent(&self) -> bool {
+        matches!(
+            self.method,
+            Method::GET
+                | Method::HEAD
+                | Method::OPTIONS
+                | Method::TRACE
+                | Method::PUT
+                | Method::DELETE
+        )
+    }
+
+    /// Whether the request arrived over HTTPS, going by the scheme of `url`.
+    pub fn secure(&self) -> bool {
+        self.url.scheme() == "https"
+    }
+
+    /// Whether the request arrived over HTTP/2.
+    pub fn is_http2(&self) -> bool {
+        self.version == HttpVersion::HTTP_2
+    }
+
+    /// Whether the client accepts trailer fields after a chunked response
+    /// body, by sending `TE: trailers`.
+    ///
+    /// HTTP/2 clients always accept trailers, and clients before HTTP/1.1
+    /// never do, as they don't understand chunked bodies.
+    pub fn accepts_trailers(&self) -> bool {
+        if self.version >= HttpVersion::HTTP_2 {
+            return true;
+        }
+        self.version >= HttpVersion::HTTP_11
+            && self
+                .headers
+                .get_all(headers::TE)
+                .iter()
+                .filter_map(|value| value.to_str().ok())
+                .flat_map(|value| value.split(','))
+                .map(|coding| coding.split(';').next().unwrap_or_default().trim())
+                .any(|coding| coding.eq_ignore_ascii_case("trailers"))
+    }
+
+    /// The `Cache-Control` directives sent with this request.
+    pub fn cache_control(&self) -> CacheControl {
+        CacheControl::from_headers(&self.headers)
+    }
+
+    /// The entity tags sent in `If-None-Match` headers, if any.
+    ///
+    /// Handlers should answer `GET` and `HEAD` requests with `304 Not
+    /// Modified` if `IfNoneMatch::matches` the current representation.
+    pub fn if_none_match(&self) -> Option<IfNoneMatch> {
+        IfNoneMatch::from_headers(&self.headers)
+    }
+
+    /// The byte ranges asked for in the `Range` header, if any.
+    ///
+    /// Only `GET` requests ask for ranges; the header is ignored for other
+    /// methods. See `modifiers::FileRanges` for serving ranges of a file.
+    pub fn range(&self) -> Option<ByteRanges> {
+        if self.method != Method::GET {
+            return None;
+        }
+        ByteRanges::from_headers(&self.headers)
+    }
+
+    /// The validator sent in the `If-Range` header, if any.
+    ///
+    /// Handlers serving ranges should only answer with `206 Partial Content`
+    /// if `IfRange::matches` the current representation, and send all of it
+    /// with `200 OK` otherwise.
+    pub fn if_range(&self) -> Option<IfRange> {
+        IfRange::from_headers(&self.headers)
+    }
+
+    /// The origin sent in the `Origin` header, if any.
+    ///
+    /// A `null` origin is treated as absent. Use `Origin::is_allowed` to check
+    /// it against an allow-list.
+    pub fn origin(&self) -> Option<Origin> {
+        Origin::from_headers(&self.headers)
+    }
+
+    /// The preferences sent with this request in `Prefer` headers.
+    ///
+    /// Handlers honoring a preference can say so with the
+    /// `modifiers::PreferenceApplied` modifier.
+    pub fn prefer(&self) -> Preferences {
+        Preferences::from_headers(&self.headers)
+    }
+
+    /// The base path the application is served under, e.g. `"/myapp"`.
+    ///
+    /// Set by the `BasePath` handler, which strips this prefix from `url`.
+    pub fn base_path(&self) -> Option<&str> {
+        self.extensions.get::<BasePathKey>().map(|prefix| &**prefix)
+    }
+
+    /// The CSRF token of the client's session, for embedding in forms.
+    ///
+    /// Set by the `Csrf` middleware; `None` if it did not run or the session
+    /// has no token.
+    pub fn csrf_token(&self) -> Option<&str> {
+        self.extensions.get::<CsrfToken>().map(|token| &**token)
+    }
+
+    /// The language from `available` this request's `Accept-Language` header
+    /// prefers most, if any.
+    ///
+    /// A language tag also matches the more or less specific tags sharing its
+    /// prefix, so `en` picks `en-US` when only that is available.
+    pub fn preferred_language(&self, available: &[&str]) -> Option<String> {
+        accept_language::preferred_language(&self.headers, available)
+    }
+
+    /// Add a named metric to the `Server-Timing` header of the response.
+    ///
+    /// The header is only written if the `ServerTiming` middleware is linked
+    /// into the chain.
+    pub fn add_server_timing(&mut self, name: &str, duration: Duration) {
+        self.extensions
+            .entry::<ServerTimingEntries>()
+            .or_insert_with(Vec::new)
+            .push((name.to_owned(), duration));
+    }
+
+    /// Whether the `User-Agent` header identifies a bot or crawler.
+    ///
+    /// Matches the user agent against `DEFAULT_BOT_PATTERNS`, or against the
+    /// patterns stored under `BotPatterns` in `extensions` if present.
+    pub fn is_bot(&self) -> bool {
+        match self.extensions.get::<BotPatterns>() {
+            Some(patterns) => bot::is_bot(&self.headers, patterns),
+            None => bot::is_bot(&self.headers, DEFAULT_BOT_PATTERNS),
+        }
+    }
+
+    /// The value of the cookie `name` if its signature is valid.
+    ///
+    /// Returns `Ok(None)` if the cookie is missing or was tampered with, and
+    /// an error if no signing key was stored by the `SignedCookies` middleware.
+    #[cfg(feature = "cookie")]
+    pub fn signed_cookie(&self, name: &str) -> Result<Option<String>, MissingSigningKey> {
+        signed_cookies::signed_cookie(self, name)
+    }
+
+    /// The claims of the JSON Web Token verified by the `Jwt` middleware.
+    ///
+    /// Fails with `JwtError::Missing` if no token was verified, and with
+    /// `JwtError::Claims` if the claims cannot be deserialized into `T`.
+    #[cfg(feature = "jwt")]
+    pub fn claims<T: ::serde::de::DeserializeOwned>(&self) -> Result<T, JwtError> {
+        jwt::claims(self)
+    }
+
+    /// The point in time by which this request should be answered.
+    ///
+    /// This is the earlier of `Timeouts::handler` and the deadline the client
+    /// sent in `Iron::deadline_header`; `None` if neither is set. Requests not
+    /// handled in time are answered with `504 Gateway Timeout`.
+    pub fn deadline(&self) -> Option<Instant> {
+        self.extensions.get::<DeadlineKey>().cloned()
+    }
+
+    /// The TLS session details of the connection this request arrived on.
+    ///
+    /// `None` for requests served over plain HTTP.
+    pub fn tls_info(&self) -> Option<TlsInfo> {
+        self.extensions.get::<TlsInfo>().cloned()
+    }
+
+    /// Both ends of the connection the request arrived on.
+    pub fn connection_info(&self) -> ConnectionInfo {
+        ConnectionInfo {
+            local_addr: self.local_addr,
+            remote_addr: self.remote_addr,
+            tls: self.extensions.contains::<TlsInfo>(),
+        }
+    }
+
+    /// A request forwarding this one upstream, for proxy handlers.
+    ///
+    /// The outbound request has the same method, path and query, and takes
+    /// over the body, leaving `body` as `None`. All headers are copied except
+    /// the hop-by-hop ones, which only concern the connection the request
+    /// arrived on: `Connection` and the headers it names, `Keep-Alive`,
+    /// `Proxy-Authenticate`, `Proxy-Authorization`, `TE`, `Trailer`,
+    /// `Transfer-Encoding` and `Upgrade`.
+    ///
+    /// The URI is in origin form, e.g. `/search?q=iron`; proxies set the
+    /// scheme and authority of the upstream server before sending it.
+    pub fn outbound_request(&mut self) -> Result<HttpRequest<Body>, http::Error> {
+        let url: &::url::Url = self.url.as_ref();
+        let uri: http::Uri = match url.query() {
+            Some(query) => format!("{}?{}", url.path(), query).parse()?,
+            None => url.path().parse()?,
+        };
+
+        let mut headers = self.headers.clone();
+        let named: Vec<String> = self
+            .headers
+            .get_all(headers::CONNECTION)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .map(|name| name.trim().to_owned())
+            .collect();
+        for name in &named {
+            headers.remove(&**name);
+        }
+        for name in HOP_BY_HOP {
+            headers.remove(*name);
+        }
+
+        let mut outbound = HttpRequest::builder()
+            .method(self.method.clone())
+            .uri(uri)
+            .body(self.body.take().unwrap_or_else(Body::empty))?;
+        *outbound.headers_mut() = headers;
+        Ok(outbound)
+    }
+
+    /// Create a stub request for use in tests.
     #[cfg(test)]
     pub fn stub() -> Request {
         Request {
             url: Url::parse("http://www.rust-lang.org").unwrap(),
             local_addr: "localhost:3000".to_socket_addrs().unwrap().next(),
+            remote_addr: "127.0.0.1:50000".parse().ok(),
             headers: HeaderMap::new(),
             body: Some(Body::empty()),
             method: Method::GET,
             extensions: TypeMap::new(),
             version: HttpVersion::HTTP_11,
+            body_limit: None,
+            min_body_rate: None,
+            received_at: Instant::now(),
             _p: (),
         }
     }
 }
 
+// Headers which only apply to a single connection, so proxies drop them.
+const HOP_BY_HOP: &[&str] = &[
+    "connection",
+    "keep-alive",
+    "proxy-authenticate",
+    "proxy-authorization",
+    "te",
+    "trailer",
+    "transfer-encoding",
+    "upgrade",
+];
+
+// Decode the `application/x-www-form-urlencoded` pairs in `input`.
+fn form_pairs(input: &[u8]) -> HashMap<String, Vec<String>> {
+    let mut pairs: HashMap<String, Vec<String>> = HashMap::new();
+    for (name, value) in form_urlencoded::parse(input) {
+        pairs
+            .entry(name.into_owned())
+            .or_default()
+            .push(value.into_owned());
+    }
+    pairs
+}
+
+// Read all of `body`, blocking the current thread.
+fn read_body(
+    body: Body,
+    limit: Option<usize>,
+    min_rate: Option<MinDataRate>,
+) -> Result<Vec<u8>, BodyError> {
+    let notify = Arc::new(ThreadNotify(thread::current()));
+    let mut body = executor::spawn(body);
+    let mut contents = Vec::new();
+    // The end of the current rate window and the bytes received in it.
+    let mut window = min_rate.map(|rate| (Instant::now() + rate.window, 0));
+
+    loop {
+        let polled = body.poll_stream_notify(&notify, 0);
+        match polled.map_err(BodyError::Http)? {
+            Async::Ready(Some(chunk)) => {
+                match limit {
+                    Some(limit) if contents.len() + chunk.len() > limit => {
+                        return Err(BodyError::TooLarge(limit));
+                    }
+                    _ => contents.extend_from_slice(&chunk),
+                }
+                if let Some((_, ref mut received)) = window {
+                    *received += chunk.len() as u64;
+                }
+            }
+            Async::Ready(None) => return Ok(contents),
+            Async::NotReady => match window {
+                Some((end, _)) => {
+                    thread::park_timeout(end.saturating_duration_since(Instant::now()))
+                }
+                None => thread::park(),
+            },
+        }
+
+        if let (Some(rate), Some((ref mut end, ref mut received))) = (min_rate, window.as_mut()) {
+            let now = Instant::now();
+            if now >= *end {
+                if *received < rate.bytes_per_window() {
+                    return Err(BodyError::TooSlow(rate));
+                }
+                *end = now + rate.window;
+                *received = 0;
+            }
+        }
+    }
+}
+
+// Wakes a thread blocked on reading the body.
+struct ThreadNotify(Thread);
+
+impl Notify for ThreadNotify {
+    fn notify(&self, _id: usize) {
+        self.0.unpark();
+    }
+}
+
+pub(crate) struct DeadlineKey;
+
+impl Key for DeadlineKey {
+    type Value = Instant;
+}
+
+/// The negotiated parameters of a TLS connection.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TlsInfo {
+    /// The negotiated protocol version, e.g. `"TLSv1_3"`.
+    pub version: String,
+
+    /// The negotiated cipher suite, e.g. `"TLS13_AES_128_GCM_SHA256"`.
+    pub cipher_suite: String,
+}
+
+impl Key for TlsInfo {
+    type Value = TlsInfo;
+}
+
+/// The connection a request arrived on, as returned by
+/// `Request::connection_info`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ConnectionInfo {
+    /// The address the server is bound to.
+    pub local_addr: Option<SocketAddr>,
+
+    /// The address of the client.
+    pub remote_addr: Option<SocketAddr>,
+
+    /// Whether the connection is encrypted with TLS by `Iron::https`.
+    pub tls: bool,
+}
+
+/// A minimum rate at which clients have to send request bodies.
+///
+/// The rate is checked over consecutive windows, each of which must bring at
+/// least `bytes_per_second` times its length in bytes. Short pauses are
+/// therefore fine, as are large uploads taking a long time, but clients
+/// trickling a body to tie up the server are cut off.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MinDataRate {
+    /// The minimum average number of bytes per second.
+    pub bytes_per_second: u64,
+
+    /// The length of the windows the rate is averaged over.
+    pub window: Duration,
+}
+
+impl MinDataRate {
+    fn bytes_per_window(&self) -> u64 {
+        (self.bytes_per_second as f64 * self.window.as_secs_f64()) as u64
+    }
+}
+
+/// The error returned when the request body could not be read.
+#[derive(Debug)]
+pub enum BodyError {
+    /// Reading from the connection failed.
+    Http(HttpError),
+
+    /// The body was larger than the request's body limit.
+    TooLarge(usize),
+
+    /// The body is not valid in the named charset.
+    Undecodable(&'static str),
+
+    /// The body arrived slower than the request's minimum body rate.
+    TooSlow(MinDataRate),
+}
+
+impl BodyError {
+    /// The status code to respond with for this error.
+    ///
+    /// This is `413 Payload Too Large` for `TooLarge`, `408 Request Timeout`
+    /// for `TooSlow` and `400 Bad Request` otherwise.
+    pub fn status(&self) -> StatusCode {
+        match *self {
+            BodyError::TooLarge(_) => StatusCode::PAYLOAD_TOO_LARGE,
+            BodyError::TooSlow(_) => StatusCode::REQUEST_TIMEOUT,
+            _ => StatusCode::BAD_REQUEST,
+        }
+    }
+}
+
+impl From<BodyError> for IronError {
+    fn from(e: BodyError) -> IronError {
+        let status = e.status();
+        let close = match e {
+            // The rest of the body is not worth waiting for, and closing the
+            // connection spares draining it to reuse the connection.
+            BodyError::TooLarge(_) | BodyError::TooSlow(_) => {
+                Some(HeaderValue::from_static("close"))
+            }
+            _ => None,
+        };
+        let mut err = IronError::new(e, status);
+        if let Some(close) = close {
+            err.response.headers.insert(headers::CONNECTION, close);
+        }
+        err
+    }
+}
+
+impl fmt::Display for BodyError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            BodyError::Http(ref e) => write!(f, "Error reading request body: {}", e),
+            BodyError::TooLarge(limit) => {
+                write!(f, "Request body exceeds the limit of {} bytes", limit)
+            }
+            BodyError::Undecodable(charset) => {
+                write!(f, "Request body is not valid {}", charset)
+            }
+            BodyError::TooSlow(rate) => write!(
+                f,
+                "Request body arrived slower than {} bytes per second",
+                rate.bytes_per_second
+            ),
+        }
+    }
+}
+
+impl Error for BodyError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match *self {
+            BodyError::Http(ref e) => Some(e),
+            BodyError::TooLarge(_) | BodyError::Undecodable(_) | BodyError::TooSlow(_) => None,
+        }
+    }
+}
+
+/// The error returned by `Request::get_form` when the body is not a form.
+#[derive(Debug)]
+pub struct NotForm;
+
+impl fmt::Display for NotForm {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("Request body is not application/x-www-form-urlencoded")
+    }
+}
+
+impl Error for NotForm {}
+
 struct RequestBodyKey;
 
 impl Key for RequestBodyKey {
     type Value = Vec<u8>;
 }
 
+struct CookiesKey;
+
+impl Key for CookiesKey {
+    type Value = HashMap<String, String>;
+}
+
 // Allow plugins to attach to requests.
 impl Extensible for Request {
     fn extensions(&self) -> &TypeMap {
@@ -209,17 +933,232 @@ impl Set for Request {}
 mod test {
     use super::*;
 
+    use futures::{future, Future, Stream};
     use headers;
 
     use url_ext::Host::*;
 
+    #[test]
+    fn test_get_body_contents_within_limit() {
+        let mut req = Request::stub();
+        req.method = Method::POST;
+        req.body = Some(Body::from("hello"));
+        req.body_limit = Some(5);
+
+        assert_eq!(req.get_body_contents().unwrap(), b"hello");
+    }
+
+    #[test]
+    fn test_get_body_contents_over_limit() {
+        let mut req = Request::stub();
+        req.method = Method::POST;
+        req.body = Some(Body::from("hello world"));
+        req.body_limit = Some(5);
+
+        match req.get_body_contents() {
+            Err(BodyError::TooLarge(5)) => (),
+            other => panic!("Expected BodyError::TooLarge, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_get_body_bytes() {
+        let mut req = Request::stub();
+        req.method = Method::POST;
+        req.body = Some(Body::from("hello"));
+
+        assert_eq!(req.get_body_bytes().unwrap(), b"hello");
+        assert_eq!(req.get_body_bytes().unwrap(), b"");
+        assert!(req.body.is_some());
+    }
+
+    #[test]
+    fn test_get_body_bytes_over_limit() {
+        let mut req = Request::stub();
+        req.method = Method::POST;
+        req.body = Some(Body::from("hello world"));
+        req.body_limit = Some(5);
+
+        match req.get_body_bytes() {
+            Err(BodyError::TooLarge(5)) => (),
+            other => panic!("Expected BodyError::TooLarge, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_declared_length_over_limit() {
+        let mut req = Request::stub();
+        req.method = Method::POST;
+        req.headers
+            .insert(headers::CONTENT_LENGTH, "11".parse().unwrap());
+        // Never completes, so the body must not be read at all.
+        let (_sender, body) = Body::channel();
+        req.body = Some(body);
+        req.body_limit = Some(5);
+
+        match req.get_body_contents() {
+            Err(BodyError::TooLarge(5)) => (),
+            other => panic!("Expected BodyError::TooLarge, got {:?}", other),
+        }
+        match req.get_body_bytes() {
+            Err(BodyError::TooLarge(5)) => (),
+            other => panic!("Expected BodyError::TooLarge, got {:?}", other),
+        }
+
+        let err = IronError::from(BodyError::TooLarge(5));
+        assert_eq!(err.response.status, Some(StatusCode::PAYLOAD_TOO_LARGE));
+        assert_eq!(err.response.headers[headers::CONNECTION], "close");
+    }
+
+    // A request whose body is sent in `chunks` of `size` bytes, `interval`
+    // apart, by another thread.
+    fn streamed_request(chunks: usize, size: usize, interval: Duration) -> Request {
+        let (mut sender, body) = Body::channel();
+        thread::spawn(move || {
+            for _ in 0..chunks {
+                thread::sleep(interval);
+                if future::poll_fn(|| sender.poll_ready()).wait().is_err()
+                    || sender.send_data(vec![b'x'; size].into()).is_err()
+                {
+                    return;
+                }
+            }
+        });
+
+        let mut req = Request::stub();
+        req.method = Method::POST;
+        req.body = Some(body);
+        req.min_body_rate = Some(MinDataRate {
+            bytes_per_second: 1000,
+            window: Duration::from_millis(100),
+        });
+        req
+    }
+
+    #[test]
+    fn test_get_body_contents_too_slow() {
+        let mut req = streamed_request(100, 1, Duration::from_millis(20));
+        let start = Instant::now();
+
+        match req.get_body_contents() {
+            Err(BodyError::TooSlow(_)) => (),
+            other => panic!("Expected BodyError::TooSlow, got {:?}", other),
+        }
+        assert!(start.elapsed() < Duration::from_secs(1));
+
+        let err = IronError::from(BodyError::TooSlow(req.min_body_rate.unwrap()));
+        assert_eq!(err.response.status, Some(StatusCode::REQUEST_TIMEOUT));
+        assert_eq!(err.response.headers[headers::CONNECTION], "close");
+    }
+
+    #[test]
+    fn test_get_body_contents_fast_upload() {
+        let mut req = streamed_request(64, 16 * 1024, Duration::from_millis(1));
+        assert_eq!(req.get_body_contents().unwrap().len(), 1024 * 1024);
+    }
+
+    #[test]
+    fn test_headers_lower() {
+        let mut req = Request::stub();
+        req.headers
+            .insert(headers::CONTENT_TYPE, "text/plain".parse().unwrap());
+        req.headers.append("X-Forwarded-For", "10.0.0.1".parse().unwrap());
+        req.headers.append("x-forwarded-for", "10.0.0.2".parse().unwrap());
+
+        let headers = req.headers_lower();
+        assert_eq!(headers.len(), 2);
+        assert_eq!(headers["content-type"], "text/plain");
+        assert_eq!(headers["x-forwarded-for"], "10.0.0.1, 10.0.0.2");
+    }
+
+    #[test]
+    fn test_is_http2() {
+        let mut req = Request::stub();
+        assert!(!req.is_http2());
+
+        req.version = HttpVersion::HTTP_2;
+        assert!(req.is_http2());
+    }
+
+    #[test]
+    fn test_accepts_trailers() {
+        let mut req = Request::stub();
+        assert!(!req.accepts_trailers());
+
+        req.headers.insert(headers::TE, "gzip".parse().unwrap());
+        assert!(!req.accepts_trailers());
+
+        req.headers
+            .insert(headers::TE, "deflate;q=0.5, Trailers".parse().unwrap());
+        assert!(req.accepts_trailers());
+
+        req.version = HttpVersion::HTTP_10;
+        assert!(!req.accepts_trailers());
+
+        req.headers.remove(headers::TE);
+        req.version = HttpVersion::HTTP_2;
+        assert!(req.accepts_trailers());
+    }
+
+    #[test]
+    fn test_get_body_contents_without_body() {
+        // The sender is kept alive, so reading would wait forever.
+        let (_sender, body) = Body::channel();
+        let mut req = Request::stub();
+        req.body = Some(body);
+
+        assert!(req.get_body_contents().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_get_body_contents_with_content_length() {
+        let mut req = Request::stub();
+        req.method = Method::DELETE;
+        req.body = Some(Body::from("hello"));
+        req.headers
+            .insert(headers::CONTENT_LENGTH, "5".parse().unwrap());
+
+        assert_eq!(req.get_body_contents().unwrap(), b"hello");
+    }
+
+    fn text_request(body: &'static [u8], content_type: &'static str) -> Request {
+        let mut req = Request::stub();
+        req.method = Method::POST;
+        req.body = Some(Body::from(body));
+        req.headers
+            .insert(headers::CONTENT_TYPE, content_type.parse().unwrap());
+        req
+    }
+
+    #[test]
+    fn test_body_string_charset_utf8() {
+        let mut req = text_request("grüße".as_bytes(), "text/plain; charset=utf-8");
+        assert_eq!(req.body_string_charset().unwrap(), "grüße");
+    }
+
+    #[test]
+    fn test_body_string_charset_latin1() {
+        let mut req = text_request(b"gr\xfc\xdfe", "text/plain; charset=ISO-8859-1");
+        assert_eq!(req.body_string_charset().unwrap(), "grüße");
+    }
+
+    #[test]
+    fn test_body_string_charset_default() {
+        let mut req = text_request("grüße".as_bytes(), "text/plain");
+        assert_eq!(req.body_string_charset().unwrap(), "grüße");
+
+        let mut req = text_request(b"gr\xfc\xdfe", "text/plain");
+        let err = req.body_string_charset().unwrap_err();
+        assert_eq!(IronError::from(err).response.status, Some(StatusCode::BAD_REQUEST));
+    }
+
     #[test]
     fn test_request_parse_absolute_uri() {
         let mut hyper_request = HttpRequest::new(Body::empty());
         *hyper_request.method_mut() = Method::GET;
         *hyper_request.uri_mut() = "http://my-host/path".parse().unwrap();
 
-        let iron_request = Request::from_http(hyper_request, None, &Protocol::http())
+        let iron_request = Request::from_http(hyper_request, None, None, &Protocol::http())
             .expect("A valid Iron request");
 
         assert_eq!(iron_request.url.host(), Domain("my-host"));
@@ -234,7 +1173,7 @@ mod test {
             .headers_mut()
             .insert(headers::HOST, "my-host".parse().unwrap());
 
-        let iron_request = Request::from_http(hyper_request, None, &Protocol::http())
+        let iron_request = Request::from_http(hyper_request, None, None, &Protocol::http())
             .expect("A valid Iron request");
 
         assert_eq!(iron_request.url.host(), Domain("my-host"));
@@ -249,7 +1188,7 @@ mod test {
             .headers_mut()
             .insert(headers::HOST, "my-host-header".parse().unwrap());
 
-        let iron_request = Request::from_http(hyper_request, None, &Protocol::http())
+        let iron_request = Request::from_http(hyper_request, None, None, &Protocol::http())
             .expect("A valid Iron request");
 
         assert_eq!(iron_request.url.host(), Domain("my-host-uri"));
@@ -263,7 +1202,7 @@ mod test {
         *hyper_request.version_mut() = HttpVersion::HTTP_10;
 
         let socket_addr = Some("1.2.3.4:80".parse().unwrap());
-        let iron_request = Request::from_http(hyper_request, socket_addr, &Protocol::http())
+        let iron_request = Request::from_http(hyper_request, socket_addr, None, &Protocol::http())
             .expect("A valid Iron request");
 
         assert_eq!(iron_request.url.host(), Ipv4([1, 2, 3, 4].into()));
@@ -277,7 +1216,7 @@ mod test {
         *hyper_request.version_mut() = HttpVersion::HTTP_10;
 
         let socket_addr = Some("[1:2:3:4:5:6:7:8]:80".parse().unwrap());
-        let iron_request = Request::from_http(hyper_request, socket_addr, &Protocol::http())
+        let iron_request = Request::from_http(hyper_request, socket_addr, None, &Protocol::http())
             .expect("A valid Iron request");
 
         assert_eq!(
@@ -296,9 +1235,99 @@ mod test {
             .insert(headers::HOST, "my-host-header".parse().unwrap());
 
         let socket_addr = Some("1.2.3.4:80".parse().unwrap());
-        let iron_request = Request::from_http(hyper_request, socket_addr, &Protocol::http())
+        let iron_request = Request::from_http(hyper_request, socket_addr, None, &Protocol::http())
             .expect("A valid Iron request");
 
         assert_eq!(iron_request.url.host(), Domain("my-host-uri"));
     }
+
+    #[test]
+    fn test_outbound_request() {
+        let mut req = Request::stub();
+        req.method = Method::POST;
+        req.url = Url::parse("http://localhost:3000/search/items?q=iron").unwrap();
+        req.body = Some(Body::from("hello"));
+        for &(name, value) in &[
+            ("host", "localhost:3000"),
+            ("content-type", "text/plain"),
+            ("connection", "keep-alive, X-Hop"),
+            ("keep-alive", "timeout=5"),
+            ("x-hop", "1"),
+            ("transfer-encoding", "chunked"),
+        ] {
+            req.headers.insert(name, value.parse().unwrap());
+        }
+
+        let outbound = req.outbound_request().unwrap();
+        assert_eq!(outbound.method(), Method::POST);
+        assert_eq!(outbound.uri(), "/search/items?q=iron");
+
+        let headers = outbound.headers();
+        assert_eq!(headers[headers::CONTENT_TYPE], "text/plain");
+        assert_eq!(headers[headers::HOST], "localhost:3000");
+        assert!(!headers.contains_key(headers::CONNECTION));
+        assert!(!headers.contains_key(headers::TRANSFER_ENCODING));
+        assert!(!headers.contains_key("keep-alive"));
+        assert!(!headers.contains_key("x-hop"));
+
+        assert!(req.body.is_none());
+        let body = outbound.into_body().concat2().wait().unwrap();
+        assert_eq!(&*body, b"hello");
+    }
+
+    #[test]
+    fn test_get_form() {
+        let content_type = "application/x-www-form-urlencoded";
+        let body = b"name=a&name=b&x=1&greeting=hello+w%C3%B6rld";
+        let mut req = text_request(body, content_type);
+
+        let fields = req.get_form().unwrap();
+        assert_eq!(fields["name"], ["a", "b"]);
+        assert_eq!(fields["x"], ["1"]);
+        assert_eq!(fields["greeting"], ["hello wörld"]);
+        assert_eq!(fields.len(), 3);
+    }
+
+    #[test]
+    fn test_get_form_wrong_content_type() {
+        let mut req = text_request(br#"{"name": "a"}"#, "application/json");
+        let err = req.get_form().unwrap_err();
+        let status = StatusCode::UNSUPPORTED_MEDIA_TYPE;
+        assert_eq!(err.response.status, Some(status));
+        assert!(err.error.is::<NotForm>());
+    }
+
+    #[test]
+    fn test_cookies() {
+        let mut req = Request::stub();
+        req.headers
+            .insert(headers::COOKIE, "a=1; b=2".parse().unwrap());
+
+        assert_eq!(req.cookies().len(), 2);
+        assert_eq!(req.cookies()["a"], "1");
+        assert_eq!(req.cookies()["b"], "2");
+
+        // Parsed once, so later header changes are not seen.
+        req.headers.remove(headers::COOKIE);
+        assert_eq!(req.cookies()["a"], "1");
+    }
+
+    #[test]
+    fn test_query_pairs() {
+        let mut req = Request::stub();
+        let query = "a=1&a=2&q=hello+world&caf%C3%A9=%26&flag";
+        req.url = Url::parse(&format!("http://localhost/search?{}", query)).unwrap();
+
+        let pairs = req.query_pairs();
+        assert_eq!(pairs["a"], ["1", "2"]);
+        assert_eq!(pairs["q"], ["hello world"]);
+        assert_eq!(pairs["café"], ["&"]);
+        assert_eq!(pairs["flag"], [""]);
+        assert_eq!(pairs.len(), 4);
+
+        req.url = Url::parse("http://localhost/search").unwrap();
+        assert!(req.query_pairs().is_empty());
+        req.url = Url::parse("http://localhost/search?").unwrap();
+        assert!(req.query_pairs().is_empty());
+    }
 }
@@ -0,0 +1,567 @@
+//! Streaming `multipart/form-data` parsing into temporary files.
+
+use std::error::Error;
+use std::fmt;
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::mem;
+use std::vec;
+
+use futures::Stream;
+use mime::{self, Mime};
+use tempfile::NamedTempFile;
+
+use error::HttpError;
+use headers::{self, HeaderMap};
+use {IronError, StatusCode};
+
+use super::{Request, RequestBodyKey};
+
+// Part headers larger than this are rejected as malformed.
+const MAX_HEADER_SIZE: usize = 8 * 1024;
+
+/// Size limits applied by `Request::multipart`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MultipartLimits {
+    /// The maximum number of bytes in a single part's body.
+    pub part_size: Option<u64>,
+
+    /// The maximum number of bytes in the whole request body.
+    ///
+    /// Falls back to `Request::body_limit` when `None`.
+    pub total_size: Option<u64>,
+}
+
+/// A part of a `multipart/form-data` body, spooled to a temporary file.
+///
+/// The file is deleted when the part is dropped, unless it is persisted
+/// through `NamedTempFile::persist`.
+#[derive(Debug)]
+pub struct Part {
+    /// The form field name from the part's `Content-Disposition`.
+    pub name: String,
+
+    /// The file name sent by the client, for file uploads.
+    pub filename: Option<String>,
+
+    /// The part's `Content-Type`, if given.
+    pub content_type: Option<Mime>,
+
+    /// The number of bytes in the part's body.
+    pub size: u64,
+
+    /// The part's body, positioned at its start.
+    pub file: NamedTempFile,
+}
+
+impl Read for Part {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.file.read(buf)
+    }
+}
+
+/// The parts of a `multipart/form-data` body, in the order they were sent,
+/// as returned by `Request::get_multipart`.
+#[derive(Debug)]
+pub struct Multipart {
+    parts: vec::IntoIter<Part>,
+}
+
+impl Iterator for Multipart {
+    type Item = Part;
+
+    fn next(&mut self) -> Option<Part> {
+        self.parts.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.parts.size_hint()
+    }
+}
+
+impl ExactSizeIterator for Multipart {}
+
+/// The error returned when a multipart body cannot be read.
+#[derive(Debug)]
+pub enum MultipartError {
+    /// The request is not `multipart/form-data` with a boundary.
+    NotMultipart,
+
+    /// The body is not valid `multipart/form-data`.
+    Malformed(&'static str),
+
+    /// A part's body was larger than `MultipartLimits::part_size`.
+    PartTooLarge(u64),
+
+    /// The request body was larger than the total limit.
+    TooLarge(u64),
+
+    /// Writing a part to its temporary file failed.
+    Io(io::Error),
+
+    /// Reading from the connection failed.
+    Http(HttpError),
+}
+
+impl MultipartError {
+    /// The status code to respond with for this error.
+    ///
+    /// Exceeding a limit results in `413 Payload Too Large`, I/O errors in
+    /// `500 Internal Server Error` and everything else in `400 Bad Request`.
+    pub fn status(&self) -> StatusCode {
+        match *self {
+            MultipartError::PartTooLarge(_) | MultipartError::TooLarge(_) => {
+                StatusCode::PAYLOAD_TOO_LARGE
+            }
+            MultipartError::Io(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            _ => StatusCode::BAD_REQUEST,
+        }
+    }
+}
+
+impl fmt::Display for MultipartError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            MultipartError::NotMultipart => f.write_str("Request body is not multipart/form-data"),
+            MultipartError::Malformed(reason) => write!(f, "Malformed multipart body: {}", reason),
+            MultipartError::PartTooLarge(limit) => {
+                write!(f, "Multipart part exceeds the limit of {} bytes", limit)
+            }
+            MultipartError::TooLarge(limit) => {
+                write!(f, "Request body exceeds the limit of {} bytes", limit)
+            }
+            MultipartError::Io(ref e) => write!(f, "Error spooling multipart part: {}", e),
+            MultipartError::Http(ref e) => write!(f, "Error reading request body: {}", e),
+        }
+    }
+}
+
+impl Error for MultipartError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match *self {
+            MultipartError::Io(ref e) => Some(e),
+            MultipartError::Http(ref e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for MultipartError {
+    fn from(e: io::Error) -> MultipartError {
+        MultipartError::Io(e)
+    }
+}
+
+impl From<MultipartError> for IronError {
+    fn from(e: MultipartError) -> IronError {
+        let status = e.status();
+        IronError::new(e, status)
+    }
+}
+
+pub fn parts(req: &mut Request) -> Result<Multipart, MultipartError> {
+    let parts = parse(req, MultipartLimits::default())?;
+    Ok(Multipart {
+        parts: parts.into_iter(),
+    })
+}
+
+pub fn parse(req: &mut Request, limits: MultipartLimits) -> Result<Vec<Part>, MultipartError> {
+    let boundary = boundary(&req.headers).ok_or(MultipartError::NotMultipart)?;
+    let total_size = limits
+        .total_size
+        .or_else(|| req.body_limit.map(|limit| limit as u64));
+    let mut parser = Parser::new(&boundary, limits.part_size, total_size);
+
+    match req.body.take() {
+        Some(body) => {
+            for chunk in body.wait() {
+                parser.feed(&chunk.map_err(MultipartError::Http)?)?;
+            }
+        }
+        // The body was already read into memory by `get_body_contents`.
+        None => {
+            if let Some(body) = req.extensions.get::<RequestBodyKey>() {
+                parser.feed(body)?;
+            }
+        }
+    }
+
+    parser.finish()
+}
+
+fn boundary(headers: &HeaderMap) -> Option<String> {
+    let mime: Mime = headers
+        .get(headers::CONTENT_TYPE)?
+        .to_str()
+        .ok()?
+        .parse()
+        .ok()?;
+    if mime.type_() != mime::MULTIPART || mime.subtype() != mime::FORM_DATA {
+        return None;
+    }
+    mime.get_param(mime::BOUNDARY)
+        .map(|boundary| boundary.as_str().to_owned())
+}
+
+enum State {
+    Preamble,
+    Delimiter,
+    Headers,
+    Body(Part),
+    End,
+}
+
+struct Parser {
+    // `\r\n--boundary`; the body is prefixed with `\r\n` so this also
+    // matches the first boundary.
+    delimiter: Vec<u8>,
+    buf: Vec<u8>,
+    state: State,
+    parts: Vec<Part>,
+    part_size: Option<u64>,
+    total_size: Option<u64>,
+    total: u64,
+}
+
+impl Parser {
+    fn new(boundary: &str, part_size: Option<u64>, total_size: Option<u64>) -> Parser {
+        Parser {
+            delimiter: format!("\r\n--{}", boundary).into_bytes(),
+            buf: b"\r\n".to_vec(),
+            state: State::Preamble,
+            parts: vec![],
+            part_size,
+            total_size,
+            total: 0,
+        }
+    }
+
+    fn feed(&mut self, chunk: &[u8]) -> Result<(), MultipartError> {
+        self.total += chunk.len() as u64;
+        if let Some(limit) = self.total_size {
+            if self.total > limit {
+                return Err(MultipartError::TooLarge(limit));
+            }
+        }
+
+        self.buf.extend_from_slice(chunk);
+        while self.step()? {}
+        Ok(())
+    }
+
+    fn finish(self) -> Result<Vec<Part>, MultipartError> {
+        match self.state {
+            State::End => Ok(self.parts),
+            _ => Err(MultipartError::Malformed("unexpected end of body")),
+        }
+    }
+
+    // Advance through the buffered input, returning whether to continue.
+    fn step(&mut self) -> Result<bool, MultipartError> {
+        // Everything up to here may be the start of a delimiter, so keep it.
+        let keep = self.delimiter.len() - 1;
+
+        let (state, progressed) = match mem::replace(&mut self.state, State::End) {
+            State::Preamble => match find(&self.buf, &self.delimiter) {
+                Some(i) => {
+                    self.buf.drain(..i + self.delimiter.len());
+                    (State::Delimiter, true)
+                }
+                None => {
+                    let skip = self.buf.len().saturating_sub(keep);
+                    self.buf.drain(..skip);
+                    (State::Preamble, false)
+                }
+            },
+            State::Delimiter if self.buf.len() < 2 => (State::Delimiter, false),
+            State::Delimiter if self.buf.starts_with(b"--") => {
+                self.buf.clear();
+                (State::End, false)
+            }
+            State::Delimiter if self.buf.starts_with(b"\r\n") => {
+                self.buf.drain(..2);
+                (State::Headers, true)
+            }
+            State::Delimiter => return Err(MultipartError::Malformed("invalid boundary")),
+            State::Headers => match find(&self.buf, b"\r\n\r\n") {
+                Some(i) => {
+                    let part = parse_headers(&self.buf[..i])?;
+                    self.buf.drain(..i + 4);
+                    (State::Body(part), true)
+                }
+                None if self.buf.len() > MAX_HEADER_SIZE => {
+                    return Err(MultipartError::Malformed("part headers too large"))
+                }
+                None => (State::Headers, false),
+            },
+            State::Body(mut part) => match find(&self.buf, &self.delimiter) {
+                Some(i) => {
+                    self.write(&mut part, i)?;
+                    self.buf.drain(..self.delimiter.len());
+                    part.file.as_file_mut().flush()?;
+                    part.file.as_file_mut().seek(SeekFrom::Start(0))?;
+                    self.parts.push(part);
+                    (State::Delimiter, true)
+                }
+                None => {
+                    let len = self.buf.len().saturating_sub(keep);
+                    self.write(&mut part, len)?;
+                    (State::Body(part), false)
+                }
+            },
+            State::End => {
+                self.buf.clear();
+                (State::End, false)
+            }
+        };
+
+        self.state = state;
+        Ok(progressed)
+    }
+
+    // Move the first `len` buffered bytes into `part`'s file.
+    fn write(&mut self, part: &mut Part, len: usize) -> Result<(), MultipartError> {
+        part.size += len as u64;
+        if let Some(limit) = self.part_size {
+            if part.size > limit {
+                return Err(MultipartError::PartTooLarge(limit));
+            }
+        }
+        part.file.write_all(&self.buf[..len])?;
+        self.buf.drain(..len);
+        Ok(())
+    }
+}
+
+fn parse_headers(raw: &[u8]) -> Result<Part, MultipartError> {
+    let raw = ::std::str::from_utf8(raw)
+        .map_err(|_| MultipartError::Malformed("part headers are not UTF-8"))?;
+
+    let mut disposition = None;
+    let mut content_type = None;
+    for line in raw.split("\r\n") {
+        let mut header = line.splitn(2, ':');
+        let name = header.next().unwrap_or_default().trim();
+        let value = header
+            .next()
+            .ok_or(MultipartError::Malformed("invalid part header"))?
+            .trim();
+
+        if name.eq_ignore_ascii_case("content-disposition") {
+            disposition = Some(value);
+        } else if name.eq_ignore_ascii_case("content-type") {
+            content_type = value.parse().ok();
+        }
+    }
+
+    let disposition = disposition.ok_or(MultipartError::Malformed(
+        "part without Content-Disposition",
+    ))?;
+    let mut params = split_params(disposition).into_iter().map(str::trim);
+    if !params
+        .next()
+        .unwrap_or_default()
+        .eq_ignore_ascii_case("form-data")
+    {
+        return Err(MultipartError::Malformed("part is not form-data"));
+    }
+
+    let mut name = None;
+    let mut filename = None;
+    for param in params {
+        let mut param = param.splitn(2, '=');
+        let key = param.next().unwrap_or_default().trim();
+        let value = param.next().map(unquote);
+        if key.eq_ignore_ascii_case("name") {
+            name = value;
+        } else if key.eq_ignore_ascii_case("filename") {
+            filename = value;
+        }
+    }
+
+    Ok(Part {
+        name: name.ok_or(MultipartError::Malformed("part without a name"))?,
+        filename,
+        content_type,
+        size: 0,
+        file: NamedTempFile::new()?,
+    })
+}
+
+// Split `value` at the semicolons outside of quoted strings, so quoted file
+// names may contain them.
+fn split_params(value: &str) -> Vec<&str> {
+    let mut params = vec![];
+    let (mut start, mut quoted, mut escaped) = (0, false, false);
+    for (i, c) in value.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if quoted => escaped = true,
+            '"' => quoted = !quoted,
+            ';' if !quoted => {
+                params.push(&value[start..i]);
+                start = i + 1;
+            }
+            _ => (),
+        }
+    }
+    params.push(&value[start..]);
+    params
+}
+
+fn unquote(value: &str) -> String {
+    let value = value.trim();
+    if value.len() >= 2 && value.starts_with('"') && value.ends_with('"') {
+        value[1..value.len() - 1]
+            .replace("\\\"", "\"")
+            .replace("\\\\", "\\")
+    } else {
+        value.to_owned()
+    }
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::io::Read;
+
+    use futures::stream;
+    use hyper::{Body, Chunk};
+
+    const BODY: &str = "preamble\r\n\
+        --XyZ\r\n\
+        Content-Disposition: form-data; name=\"title\"\r\n\
+        \r\n\
+        Holiday\r\n\
+        --XyZ\r\n\
+        Content-Disposition: form-data; name=\"photo\"; filename=\"beach.txt\"\r\n\
+        Content-Type: text/plain\r\n\
+        \r\n\
+        sand\r\nand sea\r\n\
+        --XyZ--\r\n";
+
+    // Split the body into small chunks to exercise boundaries across chunks.
+    fn request(body: &'static str, chunk_size: usize) -> Request {
+        let chunks: Vec<Chunk> = body
+            .as_bytes()
+            .chunks(chunk_size)
+            .map(|chunk| Chunk::from(chunk.to_vec()))
+            .collect();
+
+        let mut req = Request::stub();
+        req.headers.insert(
+            headers::CONTENT_TYPE,
+            "multipart/form-data; boundary=XyZ".parse().unwrap(),
+        );
+        req.body = Some(Body::wrap_stream(stream::iter_ok::<_, io::Error>(chunks)));
+        req
+    }
+
+    fn contents(part: &mut Part) -> String {
+        let mut contents = String::new();
+        part.file.read_to_string(&mut contents).unwrap();
+        contents
+    }
+
+    #[test]
+    fn test_parts_spooled_to_disk() {
+        for &chunk_size in &[1, 3, 7, BODY.len()] {
+            let limits = MultipartLimits {
+                part_size: Some(16),
+                total_size: None,
+            };
+            let mut parts = request(BODY, chunk_size).multipart(limits).unwrap();
+
+            assert_eq!(parts.len(), 2);
+            assert_eq!(parts[0].name, "title");
+            assert_eq!(parts[0].filename, None);
+            assert_eq!(contents(&mut parts[0]), "Holiday");
+
+            assert_eq!(parts[1].name, "photo");
+            assert_eq!(parts[1].filename, Some("beach.txt".into()));
+            assert_eq!(parts[1].content_type, Some(mime::TEXT_PLAIN));
+            assert_eq!(parts[1].size, 13);
+            assert!(parts[1].file.path().exists());
+            assert_eq!(contents(&mut parts[1]), "sand\r\nand sea");
+        }
+    }
+
+    #[test]
+    fn test_get_multipart() {
+        let body = "--XyZ\r\n\
+            Content-Disposition: form-data; name=\"title\"\r\n\
+            \r\n\
+            Holiday\r\n\
+            --XyZ\r\n\
+            Content-Disposition: form-data; name=\"report\"; \
+            filename=\"notes; \\\"final\\\".txt\"\r\n\
+            Content-Type: text/plain\r\n\
+            \r\n\
+            all done\r\n\
+            --XyZ--\r\n";
+        let mut multipart = request(body, 5).get_multipart().unwrap();
+        assert_eq!(multipart.len(), 2);
+
+        let mut title = multipart.next().unwrap();
+        assert_eq!(title.name, "title");
+        let mut contents = String::new();
+        title.read_to_string(&mut contents).unwrap();
+        assert_eq!(contents, "Holiday");
+
+        let mut report = multipart.next().unwrap();
+        assert_eq!(report.name, "report");
+        assert_eq!(report.filename, Some("notes; \"final\".txt".into()));
+        assert_eq!(report.content_type, Some(mime::TEXT_PLAIN));
+        let mut contents = String::new();
+        report.read_to_string(&mut contents).unwrap();
+        assert_eq!(contents, "all done");
+
+        assert!(multipart.next().is_none());
+    }
+
+    #[test]
+    fn test_part_too_large() {
+        let limits = MultipartLimits {
+            part_size: Some(8),
+            total_size: None,
+        };
+        let err = request(BODY, 5).multipart(limits).unwrap_err();
+        assert!(matches!(err, MultipartError::PartTooLarge(8)));
+        assert_eq!(
+            IronError::from(err).response.status,
+            Some(StatusCode::PAYLOAD_TOO_LARGE)
+        );
+    }
+
+    #[test]
+    fn test_total_too_large() {
+        let limits = MultipartLimits {
+            part_size: None,
+            total_size: Some(64),
+        };
+        let err = request(BODY, 5).multipart(limits).unwrap_err();
+        assert!(matches!(err, MultipartError::TooLarge(64)));
+    }
+
+    #[test]
+    fn test_malformed() {
+        let err = request("--XyZ\r\nContent-Disposition: form-data; name=\"a\"\r\n\r\nabc", 4)
+            .multipart(MultipartLimits::default())
+            .unwrap_err();
+        assert_eq!(err.status(), StatusCode::BAD_REQUEST);
+
+        let mut req = request(BODY, 4);
+        req.headers.remove(headers::CONTENT_TYPE);
+        assert!(matches!(
+            req.multipart(MultipartLimits::default()),
+            Err(MultipartError::NotMultipart)
+        ));
+    }
+}
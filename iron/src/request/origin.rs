@@ -0,0 +1,169 @@
+//! Parsing of the `Origin` request header.
+
+use std::fmt;
+
+use headers::{self, HeaderMap};
+use url_ext;
+
+/// The origin a request was made from, as sent in the `Origin` header.
+///
+/// The scheme and host are lowercase, and the port is `None` when it is the
+/// default port of the scheme.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Origin {
+    /// The scheme, e.g. `https`.
+    pub scheme: String,
+
+    /// The host name or IP address, with IPv6 addresses in brackets.
+    pub host: String,
+
+    /// The port, unless it is the default for the scheme.
+    pub port: Option<u16>,
+}
+
+impl Origin {
+    /// Parse the `Origin` header in `headers`.
+    ///
+    /// Returns `None` if the header is absent, malformed, or `null`, which
+    /// browsers send for privacy-sensitive contexts and which no allow-list
+    /// should match.
+    pub fn from_headers(headers: &HeaderMap) -> Option<Origin> {
+        Origin::parse(headers.get(headers::ORIGIN)?.to_str().ok()?)
+    }
+
+    /// Parse an origin like `https://example.com:8443`.
+    pub fn parse(value: &str) -> Option<Origin> {
+        let value = value.trim();
+        let (_, rest) = value.split_at(value.find("://")? + 3);
+        // An origin has no path, query or user info.
+        if rest.is_empty() || rest.contains(['/', '?', '#', '@']) {
+            return None;
+        }
+        let url = url_ext::Url::parse(value).ok()?;
+        Some(Origin {
+            scheme: url.scheme().to_owned(),
+            host: url.host_str()?.to_owned(),
+            port: url.port(),
+        })
+    }
+
+    /// Whether the origin matches one of the patterns in `allowed`.
+    ///
+    /// A pattern is either an origin like `https://example.com`, which must
+    /// match exactly, an origin whose host starts with `*.`, like
+    /// `https://*.example.com`, matching all subdomains but not the domain
+    /// itself, or `*`, matching any origin. Schemes and hosts are compared
+    /// ignoring case, and default ports may be given or left out.
+    pub fn is_allowed(&self, allowed: &[&str]) -> bool {
+        allowed.iter().any(|pattern| self.matches(pattern))
+    }
+
+    fn matches(&self, pattern: &str) -> bool {
+        let pattern = pattern.trim();
+        if pattern == "*" {
+            return true;
+        }
+        let (scheme, rest) = match pattern.find("://") {
+            Some(i) => (&pattern[..i], &pattern[i + 3..]),
+            None => return false,
+        };
+        // The port follows the last colon, unless that is within an IPv6 address.
+        let (host, port) = match rest.rfind(':') {
+            Some(i) if !rest[i..].contains(']') => match rest[i + 1..].parse::<u16>() {
+                Ok(port) => (&rest[..i], Some(port)),
+                Err(_) => return false,
+            },
+            _ => (rest, None),
+        };
+        let port = port.filter(|&port| Some(port) != default_port(scheme));
+
+        let host_matches = match host.strip_prefix("*.") {
+            // The host is lowercase already.
+            Some(domain) => self
+                .host
+                .ends_with(&format!(".{}", domain.to_ascii_lowercase())),
+            None => self.host.eq_ignore_ascii_case(host),
+        };
+        scheme.eq_ignore_ascii_case(&self.scheme) && host_matches && port == self.port
+    }
+}
+
+// The port used for `scheme` when none is given.
+fn default_port(scheme: &str) -> Option<u16> {
+    match &*scheme.to_ascii_lowercase() {
+        "http" | "ws" => Some(80),
+        "https" | "wss" => Some(443),
+        _ => None,
+    }
+}
+
+impl fmt::Display for Origin {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}://{}", self.scheme, self.host)?;
+        if let Some(port) = self.port {
+            write!(f, ":{}", port)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn parse(value: &'static str) -> Option<Origin> {
+        let mut headers = HeaderMap::new();
+        headers.insert(headers::ORIGIN, value.parse().unwrap());
+        Origin::from_headers(&headers)
+    }
+
+    #[test]
+    fn test_parse() {
+        let origin = parse("HTTPS://App.Example.com:8443").unwrap();
+        assert_eq!(origin.scheme, "https");
+        assert_eq!(origin.host, "app.example.com");
+        assert_eq!(origin.port, Some(8443));
+        assert_eq!(origin.to_string(), "https://app.example.com:8443");
+
+        assert_eq!(parse("https://example.com:443").unwrap().port, None);
+        assert_eq!(parse("http://[::1]:3000").unwrap().host, "[::1]");
+    }
+
+    #[test]
+    fn test_null_or_absent() {
+        assert_eq!(parse("null"), None);
+        assert_eq!(parse("https://example.com/path"), None);
+        assert_eq!(parse("https://user@example.com"), None);
+        assert_eq!(Origin::from_headers(&HeaderMap::new()), None);
+    }
+
+    const ALLOWED: [&str; 3] = [
+        "https://example.com",
+        "https://*.example.org",
+        "http://localhost:3000",
+    ];
+
+    #[test]
+    fn test_allowed() {
+        let is_allowed = |origin| parse(origin).unwrap().is_allowed(&ALLOWED);
+
+        assert!(is_allowed("https://example.com"));
+        assert!(is_allowed("https://EXAMPLE.com:443"));
+        assert!(is_allowed("https://api.example.org"));
+        assert!(is_allowed("https://a.b.example.org"));
+        assert!(is_allowed("http://localhost:3000"));
+    }
+
+    #[test]
+    fn test_disallowed() {
+        let is_allowed = |origin| parse(origin).unwrap().is_allowed(&ALLOWED);
+
+        assert!(!is_allowed("http://example.com"));
+        assert!(!is_allowed("https://example.com:8443"));
+        assert!(!is_allowed("https://evil-example.com"));
+        assert!(!is_allowed("https://example.org"));
+        assert!(!is_allowed("https://evilexample.org"));
+        assert!(!is_allowed("http://localhost"));
+        assert!(parse("https://anything.test").unwrap().is_allowed(&["*"]));
+    }
+}
@@ -0,0 +1,168 @@
+//! Parsing of request `Prefer` headers (RFC 7240).
+
+use std::time::Duration;
+
+use headers::HeaderMap;
+
+/// The name of the `Prefer` request header.
+pub const PREFER: &str = "prefer";
+
+/// The name of the `Preference-Applied` response header.
+pub const PREFERENCE_APPLIED: &str = "preference-applied";
+
+/// The preferences sent by a client in `Prefer` headers.
+///
+/// Only the first occurrence of a preference counts. Parameters after a `;`
+/// are ignored, as are unknown preferences and preferences with invalid
+/// values.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Preferences {
+    /// `return`: what the response to a successful request should contain.
+    pub return_: Option<Return>,
+
+    /// `respond-async`: the client prefers a `202 Accepted` to waiting.
+    pub respond_async: bool,
+
+    /// `wait`: how long the client is willing to wait for a response.
+    pub wait: Option<Duration>,
+
+    /// `handling`: how strictly the request should be validated.
+    pub handling: Option<Handling>,
+}
+
+/// The values of the `return` preference.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Return {
+    /// `return=minimal`: a response without the resource.
+    Minimal,
+
+    /// `return=representation`: a response with the current resource.
+    Representation,
+}
+
+impl Return {
+    /// The preference as it appears in `Prefer` and `Preference-Applied`.
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            Return::Minimal => "return=minimal",
+            Return::Representation => "return=representation",
+        }
+    }
+}
+
+/// The values of the `handling` preference.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Handling {
+    /// `handling=strict`: fail on any error in the request.
+    Strict,
+
+    /// `handling=lenient`: process the request as far as possible.
+    Lenient,
+}
+
+impl Handling {
+    /// The preference as it appears in `Prefer` and `Preference-Applied`.
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            Handling::Strict => "handling=strict",
+            Handling::Lenient => "handling=lenient",
+        }
+    }
+}
+
+impl Preferences {
+    /// Parse the preferences from all `Prefer` headers in `headers`.
+    pub fn from_headers(headers: &HeaderMap) -> Preferences {
+        let mut preferences = Preferences::default();
+        let mut seen = Vec::new();
+
+        let tokens = headers
+            .get_all(PREFER)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .map(|preference| preference.split(';').next().unwrap_or_default());
+
+        for token in tokens {
+            let mut parts = token.splitn(2, '=');
+            let name = parts.next().unwrap_or_default().trim().to_ascii_lowercase();
+            let value = parts.next().map(|value| value.trim().trim_matches('"'));
+
+            if name.is_empty() || seen.contains(&name) {
+                continue;
+            }
+
+            match (&*name, value) {
+                ("return", Some(value)) if value.eq_ignore_ascii_case("minimal") => {
+                    preferences.return_ = Some(Return::Minimal);
+                }
+                ("return", Some(value)) if value.eq_ignore_ascii_case("representation") => {
+                    preferences.return_ = Some(Return::Representation);
+                }
+                ("respond-async", None) => preferences.respond_async = true,
+                ("wait", Some(value)) => {
+                    preferences.wait = value.parse().ok().map(Duration::from_secs);
+                }
+                ("handling", Some(value)) if value.eq_ignore_ascii_case("strict") => {
+                    preferences.handling = Some(Handling::Strict);
+                }
+                ("handling", Some(value)) if value.eq_ignore_ascii_case("lenient") => {
+                    preferences.handling = Some(Handling::Lenient);
+                }
+                _ => (),
+            }
+            seen.push(name);
+        }
+
+        preferences
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn parse(value: &'static str) -> Preferences {
+        let mut headers = HeaderMap::new();
+        headers.insert(PREFER, value.parse().unwrap());
+        Preferences::from_headers(&headers)
+    }
+
+    #[test]
+    fn test_return_minimal() {
+        let preferences = parse("return=minimal");
+        assert_eq!(preferences.return_, Some(Return::Minimal));
+        assert!(!preferences.respond_async);
+        assert_eq!(preferences.wait, None);
+    }
+
+    #[test]
+    fn test_wait() {
+        let preferences = parse("respond-async, wait=10");
+        assert_eq!(preferences.wait, Some(Duration::from_secs(10)));
+        assert!(preferences.respond_async);
+        assert_eq!(preferences.return_, None);
+    }
+
+    #[test]
+    fn test_first_occurrence_wins() {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            PREFER,
+            "Return=\"representation\"; foo=bar".parse().unwrap(),
+        );
+        headers.append(PREFER, "return=minimal, handling=lenient".parse().unwrap());
+
+        let preferences = Preferences::from_headers(&headers);
+        assert_eq!(preferences.return_, Some(Return::Representation));
+        assert_eq!(preferences.handling, Some(Handling::Lenient));
+    }
+
+    #[test]
+    fn test_absent_header() {
+        assert_eq!(
+            Preferences::from_headers(&HeaderMap::new()),
+            Preferences::default()
+        );
+    }
+}
@@ -0,0 +1,154 @@
+//! Parsing of the `Range` request header.
+
+use headers::{self, HeaderMap};
+
+/// One range of bytes in a `Range` header.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ByteRange {
+    /// The bytes from the first to the last offset, both inclusive.
+    FromTo(u64, u64),
+
+    /// The bytes from an offset to the end.
+    From(u64),
+
+    /// The given number of bytes at the end.
+    Last(u64),
+}
+
+/// The byte ranges a client asked for in a `Range` header, e.g.
+/// `bytes=0-9,20-29`.
+///
+/// Use `resolve` with the length of the representation to get the ranges
+/// to send.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ByteRanges(pub Vec<ByteRange>);
+
+impl ByteRanges {
+    /// Parse the `Range` header in `headers`.
+    ///
+    /// Returns `None` if the header is absent, is not in `bytes`, or is
+    /// malformed, in which case the whole representation should be sent.
+    pub fn from_headers(headers: &HeaderMap) -> Option<ByteRanges> {
+        let value = headers.get(headers::RANGE)?.to_str().ok()?.trim();
+        let (unit, specs) = value.split_at(value.find('=')?);
+        if !unit.trim().eq_ignore_ascii_case("bytes") {
+            return None;
+        }
+
+        let ranges = specs[1..]
+            .split(',')
+            .map(str::trim)
+            .filter(|spec| !spec.is_empty())
+            .map(|spec| {
+                let (first, last) = spec.split_at(spec.find('-')?);
+                let (first, last) = (first.trim(), last[1..].trim());
+                match (first.is_empty(), last.is_empty()) {
+                    (true, false) => last.parse().ok().map(ByteRange::Last),
+                    (false, true) => first.parse().ok().map(ByteRange::From),
+                    (false, false) => {
+                        let (first, last) = (first.parse().ok()?, last.parse().ok()?);
+                        if first <= last {
+                            Some(ByteRange::FromTo(first, last))
+                        } else {
+                            None
+                        }
+                    }
+                    (true, true) => None,
+                }
+            })
+            .collect::<Option<Vec<_>>>()?;
+
+        if ranges.is_empty() {
+            None
+        } else {
+            Some(ByteRanges(ranges))
+        }
+    }
+
+    /// The ranges to send of a representation `len` bytes long, as pairs of
+    /// the first and last offset, both inclusive.
+    ///
+    /// Ranges are clipped to the representation and sorted, and overlapping
+    /// or adjacent ranges are coalesced, so `bytes=0-9,5-19` yields a single
+    /// range. An empty result means no range is satisfiable, which should be
+    /// answered with `416 Range Not Satisfiable`.
+    pub fn resolve(&self, len: u64) -> Vec<(u64, u64)> {
+        let mut ranges: Vec<(u64, u64)> = self
+            .0
+            .iter()
+            .filter_map(|range| match *range {
+                ByteRange::FromTo(first, last) if first < len => Some((first, last.min(len - 1))),
+                ByteRange::From(first) if first < len => Some((first, len - 1)),
+                ByteRange::Last(count) if count > 0 && len > 0 => {
+                    Some((len.saturating_sub(count), len - 1))
+                }
+                _ => None,
+            })
+            .collect();
+        ranges.sort_unstable();
+
+        let mut coalesced: Vec<(u64, u64)> = Vec::with_capacity(ranges.len());
+        for (first, last) in ranges {
+            match coalesced.last_mut() {
+                Some(previous) if first <= previous.1 + 1 => previous.1 = previous.1.max(last),
+                _ => coalesced.push((first, last)),
+            }
+        }
+        coalesced
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn parse(value: &'static str) -> Option<ByteRanges> {
+        let mut headers = HeaderMap::new();
+        headers.insert(headers::RANGE, value.parse().unwrap());
+        ByteRanges::from_headers(&headers)
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            parse("bytes=0-9, 20-, -5"),
+            Some(ByteRanges(vec![
+                ByteRange::FromTo(0, 9),
+                ByteRange::From(20),
+                ByteRange::Last(5),
+            ]))
+        );
+    }
+
+    #[test]
+    fn test_invalid() {
+        assert_eq!(parse("items=0-9"), None);
+        assert_eq!(parse("bytes=9-0"), None);
+        assert_eq!(parse("bytes=0-9,x"), None);
+        assert_eq!(parse("bytes=-"), None);
+        assert_eq!(parse("bytes="), None);
+        assert_eq!(ByteRanges::from_headers(&HeaderMap::new()), None);
+    }
+
+    #[test]
+    fn test_resolve_disjoint() {
+        let ranges = parse("bytes=20-29,0-9").unwrap();
+        assert_eq!(ranges.resolve(100), [(0, 9), (20, 29)]);
+    }
+
+    #[test]
+    fn test_resolve_coalesces() {
+        assert_eq!(parse("bytes=0-9,5-19").unwrap().resolve(100), [(0, 19)]);
+        assert_eq!(parse("bytes=0-9,10-19").unwrap().resolve(100), [(0, 19)]);
+        assert_eq!(parse("bytes=50-,-60").unwrap().resolve(100), [(40, 99)]);
+    }
+
+    #[test]
+    fn test_resolve_clips() {
+        assert_eq!(parse("bytes=90-199").unwrap().resolve(100), [(90, 99)]);
+        assert_eq!(parse("bytes=-500").unwrap().resolve(100), [(0, 99)]);
+        assert!(parse("bytes=100-").unwrap().resolve(100).is_empty());
+        assert!(parse("bytes=-0").unwrap().resolve(100).is_empty());
+        assert!(parse("bytes=-5").unwrap().resolve(0).is_empty());
+    }
+}
@@ -2,6 +2,7 @@
 
 use std::fmt;
 use std::str::FromStr;
+use url::percent_encoding::percent_decode;
 use url::{self, Host};
 
 /// HTTP/HTTPS URL type for Iron.
@@ -78,6 +79,21 @@ impl Url {
         self.generic_url.path_segments().unwrap().collect()
     }
 
+    /// The segments of `path`, each percent-decoded.
+    ///
+    /// Segments are decoded one by one, so an encoded slash like `%2F` stays
+    /// within its segment. Invalid UTF-8 is replaced with `U+FFFD`.
+    pub fn decoded_path_segments(&self) -> Vec<String> {
+        self.path()
+            .into_iter()
+            .map(|segment| {
+                percent_decode(segment.as_bytes())
+                    .decode_utf8_lossy()
+                    .into_owned()
+            })
+            .collect()
+    }
+
     /// The URL username field, from the userinfo section of the URL.
     ///
     /// `None` if the `@` character was not part of the input OR
@@ -99,8 +115,7 @@ impl Url {
     pub fn password(&self) -> Option<&str> {
         // Map empty passwords to None.
         match self.generic_url.password() {
-            None => None,
-            Some(x) if x.is_empty() => None,
+            None | Some("") => None,
             Some(password) => Some(password),
         }
     }
@@ -124,14 +139,13 @@ impl Url {
 
 impl fmt::Display for Url {
     fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
-        try!(self.generic_url.fmt(formatter));
-        Ok(())
+        self.generic_url.fmt(formatter)
     }
 }
 
-impl Into<url::Url> for Url {
-    fn into(self) -> url::Url {
-        self.generic_url
+impl From<Url> for url::Url {
+    fn from(val: Url) -> Self {
+        val.generic_url
     }
 }
 
@@ -259,6 +273,13 @@ mod test {
         assert_eq!(parsed, "https://example.com/");
     }
 
+    #[test]
+    fn test_decoded_path_segments() {
+        let url = Url::parse("http://example.com/my%20files/a%2Eb/c%2Fd/").unwrap();
+        assert_eq!(url.path(), ["my%20files", "a%2Eb", "c%2Fd", ""]);
+        assert_eq!(url.decoded_path_segments(), ["my files", "a.b", "c/d", ""]);
+    }
+
     #[test]
     fn test_from_str_positive() {
         let u = "http://example.com".parse::<Url>();
@@ -0,0 +1,487 @@
+//! Iron's HTTP Response representation and associated methods.
+
+use std::fmt::{self, Debug};
+use std::fs::File;
+use std::io::{self, Write};
+use std::mem;
+use std::time::SystemTime;
+
+use bytes::Bytes;
+use futures::{future, Future};
+use httpdate;
+
+use modifier::{Modifier, Set};
+use plugin::Extensible;
+use typemap::TypeMap;
+
+use {headers, Plugin, StatusCode};
+
+use hyper::body::Sender;
+use hyper::Body;
+use hyper::Method;
+pub use hyper::Response as HttpResponse;
+
+pub use self::sse::{Event, EventStream};
+
+mod sse;
+
+// Streamed bodies are sent to the client whenever this much data is buffered,
+// or when the body flushes its writer.
+const STREAM_CHUNK_SIZE: usize = 8 * 1024;
+
+/// Wrapper type to set `Read`ers as response bodies
+pub struct BodyReader<R: Send>(pub R);
+
+/// Wrapper type to stream a body written by a closure.
+///
+/// The closure runs after the response head has been sent. Each `flush` of
+/// the writer it is given sends the data written so far to the client as one
+/// chunk, so protocols sending a message at a time can flush after each one.
+///
+/// ```
+/// # use std::io::Write;
+/// # use iron::prelude::*;
+/// # use iron::response::Streaming;
+/// Response::with(Streaming::new(|body: &mut dyn Write| {
+///     body.write_all(b"first")?;
+///     body.flush()?;
+///     body.write_all(b"second")
+/// }));
+/// ```
+pub struct Streaming<F> {
+    write: Option<F>,
+}
+
+impl<F> Streaming<F>
+where
+    F: FnOnce(&mut dyn Write) -> io::Result<()> + Send,
+{
+    /// Stream the body written by `write`.
+    pub fn new(write: F) -> Streaming<F> {
+        Streaming { write: Some(write) }
+    }
+}
+
+impl<F> WriteBody for Streaming<F>
+where
+    F: FnOnce(&mut dyn Write) -> io::Result<()> + Send,
+{
+    fn write_body(&mut self, res: &mut dyn Write) -> io::Result<()> {
+        match self.write.take() {
+            Some(write) => write(res),
+            None => Ok(()),
+        }
+    }
+
+    fn is_streaming(&self) -> bool {
+        true
+    }
+}
+
+/// Stream the items of `iter` as the body, without collecting them first.
+///
+/// The iterator is driven on a blocking thread of its own once the response
+/// head has been sent, so it may block, e.g. while fetching rows from a
+/// database. Items are anything convertible to `Bytes`, such as `String` or
+/// `Vec<u8>`; small items are sent together in chunks.
+///
+/// ```
+/// # use iron::prelude::*;
+/// # use iron::response;
+/// let lines = (1..=3).map(|i| format!("line {}\n", i));
+/// Response::with(response::from_iter(lines));
+/// ```
+pub fn from_iter<I>(iter: I) -> Streaming<impl FnOnce(&mut dyn Write) -> io::Result<()> + Send>
+where
+    I: IntoIterator,
+    I::IntoIter: Send,
+    I::Item: Into<Bytes>,
+{
+    let iter = iter.into_iter();
+    Streaming::new(move |body: &mut dyn Write| {
+        for item in iter {
+            body.write_all(&item.into())?;
+        }
+        Ok(())
+    })
+}
+
+/// A trait which writes the body of an HTTP response.
+pub trait WriteBody: Send {
+    /// Writes the body to the provided `Write`.
+    fn write_body(&mut self, res: &mut dyn Write) -> io::Result<()>;
+
+    /// Whether the body is sent to the client while it is being written.
+    ///
+    /// Bodies are buffered completely by default. A streaming body is written
+    /// after the response head has been sent, and each `flush` of the writer
+    /// passed to `write_body` sends the data written so far. Streamed bodies
+    /// use chunked transfer encoding unless a `Content-Length` is set.
+    fn is_streaming(&self) -> bool {
+        false
+    }
+}
+
+impl WriteBody for String {
+    fn write_body(&mut self, res: &mut dyn Write) -> io::Result<()> {
+        self.as_bytes().write_body(res)
+    }
+}
+
+impl WriteBody for &str {
+    fn write_body(&mut self, res: &mut dyn Write) -> io::Result<()> {
+        self.as_bytes().write_body(res)
+    }
+}
+
+impl WriteBody for Vec<u8> {
+    fn write_body(&mut self, res: &mut dyn Write) -> io::Result<()> {
+        res.write_all(self)
+    }
+}
+
+impl WriteBody for &[u8] {
+    fn write_body(&mut self, res: &mut dyn Write) -> io::Result<()> {
+        res.write_all(self)
+    }
+}
+
+impl WriteBody for File {
+    fn write_body(&mut self, res: &mut dyn Write) -> io::Result<()> {
+        io::copy(self, res).map(|_| ())
+    }
+}
+
+impl WriteBody for Box<dyn io::Read + Send> {
+    fn write_body(&mut self, res: &mut dyn Write) -> io::Result<()> {
+        io::copy(self, res).map(|_| ())
+    }
+}
+
+impl<R: io::Read + Send> WriteBody for BodyReader<R> {
+    fn write_body(&mut self, res: &mut dyn Write) -> io::Result<()> {
+        io::copy(&mut self.0, res).map(|_| ())
+    }
+}
+
+/* Needs specialization :(
+impl<R: Read + Send> WriteBody for R {
+    fn write_body(&mut self, res: &mut Write) -> io::Result<()> {
+        io::copy(self, res)
+    }
+}
+*/
+
+/// The response representation given to `Middleware`
+pub struct Response {
+    /// The response status-code.
+    pub status: Option<StatusCode>,
+
+    /// The headers of the response.
+    pub headers: headers::HeaderMap,
+
+    /// A TypeMap to be used as an extensible storage for data
+    /// associated with this Response.
+    pub extensions: TypeMap,
+
+    /// The body of the response.
+    pub body: Option<Box<dyn WriteBody>>,
+}
+
+impl Default for Response {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Response {
+    /// Construct a blank Response
+    pub fn new() -> Response {
+        Response {
+            status: None, // Start with no response code.
+            body: None,   // Start with no body.
+            headers: headers::HeaderMap::new(),
+            extensions: TypeMap::new(),
+        }
+    }
+
+    /// Construct a Response with the specified modifier pre-applied.
+    pub fn with<M: Modifier<Response>>(m: M) -> Response {
+        Response::new().set(m)
+    }
+
+    // `write_back` is used to put all the data added to `self`
+    // back onto an `HttpResponse` so that it is sent back to the
+    // client.
+    //
+    // `write_back` consumes the `Response`. Streaming bodies are returned
+    // as a `BodyStream`, which must be written once the head is sent.
+    #[doc(hidden)]
+    pub fn write_back(self, http_res: &mut HttpResponse<Body>, req_method: Method) -> Option<BodyStream> {
+        *http_res.headers_mut() = self.headers;
+
+        // Caches rely on every response being dated.
+        if !http_res.headers().contains_key(headers::DATE) {
+            // An HTTP-date is visible ASCII, so unwrap is safe here.
+            let date = httpdate::fmt_http_date(SystemTime::now()).parse().unwrap();
+            http_res.headers_mut().insert(headers::DATE, date);
+        }
+
+        // Default to a 404 if no response code was set
+        *http_res.status_mut() = self.status.unwrap_or(StatusCode::NOT_FOUND);
+
+        // A 304 has no body. Validators like `ETag` and `Cache-Control` are kept.
+        if http_res.status() == StatusCode::NOT_MODIFIED {
+            http_res.headers_mut().remove(headers::CONTENT_LENGTH);
+            http_res.headers_mut().remove(headers::CONTENT_TYPE);
+            return None;
+        }
+
+        let out = match (self.body, req_method) {
+            (Some(ref body), Method::HEAD) if body.is_streaming() => Ok(None),
+            (Some(body), _) => write_with_body(http_res, body),
+            (None, Method::HEAD) => Ok(None),
+            (None, _) => {
+                http_res.headers_mut().insert(
+                    headers::CONTENT_LENGTH,
+                    headers::HeaderValue::from_static("0"),
+                );
+                Ok(None)
+            }
+        };
+
+        out.unwrap_or_else(|e| {
+            error!("Error writing response: {}", e);
+            None
+        })
+    }
+}
+
+fn write_with_body(
+    res: &mut HttpResponse<Body>,
+    mut body: Box<dyn WriteBody>,
+) -> io::Result<Option<BodyStream>> {
+    let content_type = res.headers().get(headers::CONTENT_TYPE).map_or_else(
+        || headers::HeaderValue::from_static("text/plain"),
+        |cx| cx.clone(),
+    );
+    res.headers_mut()
+        .insert(headers::CONTENT_TYPE, content_type);
+
+    if body.is_streaming() {
+        let (sender, stream) = Body::channel();
+        *res.body_mut() = stream;
+        return Ok(Some(BodyStream {
+            body,
+            writer: StreamWriter {
+                sender,
+                buffer: Vec::new(),
+            },
+        }));
+    }
+
+    let mut body_contents: Vec<u8> = vec![];
+    body.write_body(&mut body_contents)?;
+    *res.body_mut() = Body::from(body_contents);
+    Ok(None)
+}
+
+/// A streaming response body which still has to be written to the client.
+#[doc(hidden)]
+pub struct BodyStream {
+    body: Box<dyn WriteBody>,
+    writer: StreamWriter,
+}
+
+impl BodyStream {
+    /// Write the body to the client, blocking until it is done or the
+    /// client goes away.
+    pub fn write(mut self) {
+        match self
+            .body
+            .write_body(&mut self.writer)
+            .and_then(|()| self.writer.flush())
+        {
+            Ok(()) => (),
+            Err(ref e) if e.kind() == io::ErrorKind::BrokenPipe => {
+                debug!("Client disconnected while streaming response");
+            }
+            Err(e) => {
+                error!("Error writing response: {}", e);
+                // Abort so the client doesn't mistake a truncated body for a complete one.
+                self.writer.sender.abort();
+            }
+        }
+    }
+}
+
+// Sends everything written to it down a hyper body channel.
+struct StreamWriter {
+    sender: Sender,
+    buffer: Vec<u8>,
+}
+
+impl Write for StreamWriter {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        self.buffer.extend_from_slice(data);
+        if self.buffer.len() >= STREAM_CHUNK_SIZE {
+            self.flush()?;
+        }
+        Ok(data.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        if self.buffer.is_empty() {
+            return Ok(());
+        }
+
+        let disconnected = || io::Error::new(io::ErrorKind::BrokenPipe, "client disconnected");
+        let sender = &mut self.sender;
+        future::poll_fn(|| sender.poll_ready())
+            .wait()
+            .map_err(|_| disconnected())?;
+
+        let chunk = mem::take(&mut self.buffer);
+        self.sender
+            .send_data(chunk.into())
+            .map_err(|_| disconnected())
+    }
+}
+
+impl Debug for Response {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "HTTP/1.1 {}\n{:?}",
+            self.status.unwrap_or(StatusCode::NOT_FOUND),
+            self.headers
+        )
+    }
+}
+
+impl fmt::Display for Response {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        Debug::fmt(self, f)
+    }
+}
+
+// Allow plugins to attach to responses.
+impl Extensible for Response {
+    fn extensions(&self) -> &TypeMap {
+        &self.extensions
+    }
+
+    fn extensions_mut(&mut self) -> &mut TypeMap {
+        &mut self.extensions
+    }
+}
+
+impl Plugin for Response {}
+impl Set for Response {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use futures::Stream;
+
+    fn body(res: &mut Response) -> Vec<u8> {
+        let mut body = vec![];
+        res.body.as_mut().unwrap().write_body(&mut body).unwrap();
+        body
+    }
+
+    #[test]
+    fn test_with_tuple() {
+        let mut res = Response::with((StatusCode::NOT_FOUND, "nope"));
+        assert_eq!(res.status, Some(StatusCode::NOT_FOUND));
+        assert_eq!(body(&mut res), b"nope");
+    }
+
+    #[test]
+    fn test_with_single_modifier() {
+        let res = Response::with(StatusCode::NO_CONTENT);
+        assert_eq!(res.status, Some(StatusCode::NO_CONTENT));
+        assert!(res.body.is_none());
+
+        let mut res = Response::with("text".to_owned());
+        assert_eq!(res.status, None);
+        assert_eq!(body(&mut res), b"text");
+
+        let mut res = Response::with(vec![1u8, 2, 3]);
+        assert_eq!(body(&mut res), [1, 2, 3]);
+    }
+
+    #[test]
+    fn test_date_set_when_absent() {
+        let res = Response::with((StatusCode::OK, "fresh"));
+        let mut http_res = HttpResponse::new(Body::empty());
+        res.write_back(&mut http_res, Method::GET);
+
+        let date = http_res.headers()[headers::DATE].to_str().unwrap();
+        let date = httpdate::parse_http_date(date).unwrap();
+        let age = SystemTime::now().duration_since(date).unwrap_or_default();
+        assert!(age.as_secs() <= 1);
+    }
+
+    #[test]
+    fn test_date_preserved() {
+        let date = "Sun, 06 Nov 1994 08:49:37 GMT";
+        let mut res = Response::with((StatusCode::OK, "cached"));
+        res.headers.insert(headers::DATE, date.parse().unwrap());
+        let mut http_res = HttpResponse::new(Body::empty());
+        res.write_back(&mut http_res, Method::GET);
+
+        assert_eq!(http_res.headers()[headers::DATE], date);
+    }
+
+    #[test]
+    fn test_not_modified_strips_body() {
+        let mut res = Response::with((StatusCode::NOT_MODIFIED, "stale body"));
+        res.headers
+            .insert(headers::ETAG, "\"v1\"".parse().unwrap());
+        res.headers
+            .insert(headers::CACHE_CONTROL, "max-age=60".parse().unwrap());
+        res.headers
+            .insert(headers::CONTENT_LENGTH, "10".parse().unwrap());
+
+        let mut http_res = HttpResponse::new(Body::empty());
+        assert!(res.write_back(&mut http_res, Method::GET).is_none());
+
+        assert_eq!(http_res.status(), StatusCode::NOT_MODIFIED);
+        assert!(!http_res.headers().contains_key(headers::CONTENT_LENGTH));
+        assert!(!http_res.headers().contains_key(headers::CONTENT_TYPE));
+        assert_eq!(http_res.headers()[headers::ETAG], "\"v1\"");
+        assert_eq!(http_res.headers()[headers::CACHE_CONTROL], "max-age=60");
+
+        let body = http_res.into_body().concat2().wait().unwrap();
+        assert!(body.is_empty());
+    }
+
+    #[test]
+    fn test_streaming_flush() {
+        let res = Response::with((
+            StatusCode::OK,
+            Streaming::new(|body: &mut dyn Write| {
+                body.write_all(b"first")?;
+                body.flush()?;
+                body.write_all(b"sec")?;
+                body.write_all(b"ond")?;
+                body.flush()
+            }),
+        ));
+
+        let mut http_res = HttpResponse::new(Body::empty());
+        let stream = res.write_back(&mut http_res, Method::GET).unwrap();
+        let writer = ::std::thread::spawn(move || stream.write());
+
+        let chunks: Vec<Vec<u8>> = http_res
+            .into_body()
+            .map(|chunk| chunk.to_vec())
+            .collect()
+            .wait()
+            .unwrap();
+        writer.join().unwrap();
+        assert_eq!(chunks, [b"first".to_vec(), b"second".to_vec()]);
+    }
+}
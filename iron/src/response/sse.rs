@@ -0,0 +1,135 @@
+//! Server-sent event bodies.
+
+use std::io::{self, Write};
+use std::sync::mpsc::{Receiver, RecvTimeoutError};
+use std::time::Duration;
+
+use super::WriteBody;
+
+/// A single server-sent event.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Event {
+    name: Option<String>,
+    id: Option<String>,
+    data: String,
+}
+
+impl Event {
+    /// Create an unnamed event carrying `data`.
+    ///
+    /// Multi-line data is sent as one `data:` field per line.
+    pub fn new<S: Into<String>>(data: S) -> Event {
+        Event {
+            data: data.into(),
+            ..Event::default()
+        }
+    }
+
+    /// Set the event type, sent as the `event:` field.
+    pub fn name<S: Into<String>>(mut self, name: S) -> Event {
+        self.name = Some(name.into());
+        self
+    }
+
+    /// Set the event id, sent as the `id:` field.
+    pub fn id<S: Into<String>>(mut self, id: S) -> Event {
+        self.id = Some(id.into());
+        self
+    }
+
+    fn write_to(&self, out: &mut dyn Write) -> io::Result<()> {
+        if let Some(ref name) = self.name {
+            writeln!(out, "event: {}", name)?;
+        }
+        if let Some(ref id) = self.id {
+            writeln!(out, "id: {}", id)?;
+        }
+        for line in self.data.split('\n') {
+            writeln!(out, "data: {}", line)?;
+        }
+        writeln!(out)
+    }
+}
+
+/// A `text/event-stream` response body fed by a channel of `Event`s.
+///
+/// Each event is sent to the client as soon as it is received. The stream
+/// ends once every `Sender` of the channel is dropped or the client
+/// disconnects.
+///
+/// Note that the body is written on a thread of its own for as long as the
+/// stream is open, so open streams do not hold up the handler pool.
+pub struct EventStream {
+    events: Receiver<Event>,
+    heartbeat: Option<Duration>,
+}
+
+impl EventStream {
+    /// Create an event stream sending the events received on `events`.
+    pub fn new(events: Receiver<Event>) -> EventStream {
+        EventStream {
+            events,
+            heartbeat: None,
+        }
+    }
+
+    /// Send a `: ping` comment whenever no event was sent for `interval`.
+    ///
+    /// Proxies tend to close connections that stay idle for too long, the
+    /// heartbeat keeps an otherwise quiet stream alive.
+    pub fn heartbeat(mut self, interval: Duration) -> EventStream {
+        self.heartbeat = Some(interval);
+        self
+    }
+
+    fn next_event(&self) -> Result<Option<Event>, RecvTimeoutError> {
+        match self.heartbeat {
+            Some(interval) => match self.events.recv_timeout(interval) {
+                Ok(event) => Ok(Some(event)),
+                Err(RecvTimeoutError::Timeout) => Ok(None),
+                Err(e) => Err(e),
+            },
+            None => self
+                .events
+                .recv()
+                .map(Some)
+                .map_err(|_| RecvTimeoutError::Disconnected),
+        }
+    }
+}
+
+impl WriteBody for EventStream {
+    fn write_body(&mut self, res: &mut dyn Write) -> io::Result<()> {
+        while let Ok(event) = self.next_event() {
+            match event {
+                Some(event) => event.write_to(res)?,
+                None => res.write_all(b": ping\n\n")?,
+            }
+            res.flush()?;
+        }
+        Ok(())
+    }
+
+    fn is_streaming(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_event_format() {
+        let mut out = vec![];
+        Event::new("first\nsecond")
+            .name("update")
+            .id("7")
+            .write_to(&mut out)
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "event: update\nid: 7\ndata: first\ndata: second\n\n"
+        );
+    }
+}
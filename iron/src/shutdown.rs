@@ -0,0 +1,92 @@
+//! Graceful shutdown of a running server.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use futures::task::AtomicTask;
+use futures::{Async, Future, Poll};
+
+/// A handle for shutting down a server gracefully, shared through
+/// `Iron::shutdown`.
+///
+/// Once shutdown is initiated the server stops accepting connections and
+/// drains the open ones: in-flight requests are answered, with
+/// `Connection: close` so clients do not reuse the connection, and the
+/// server's `Listening` handle is released when the last connection is
+/// closed.
+#[derive(Clone, Default)]
+pub struct Shutdown {
+    inner: Arc<Inner>,
+}
+
+#[derive(Default)]
+struct Inner {
+    initiated: AtomicBool,
+    task: AtomicTask,
+}
+
+impl Shutdown {
+    /// A handle for a server which is not shutting down.
+    pub fn new() -> Shutdown {
+        Shutdown::default()
+    }
+
+    /// Start shutting down the server.
+    pub fn initiate(&self) {
+        self.inner.initiated.store(true, Ordering::SeqCst);
+        self.inner.task.notify();
+    }
+
+    /// Whether shutdown has been initiated.
+    pub fn is_initiated(&self) -> bool {
+        self.inner.initiated.load(Ordering::SeqCst)
+    }
+
+    pub(crate) fn signal(&self) -> Signal {
+        Signal {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+// A future completing once shutdown is initiated.
+pub(crate) struct Signal {
+    inner: Arc<Inner>,
+}
+
+impl Future for Signal {
+    type Item = ();
+    type Error = ();
+
+    fn poll(&mut self) -> Poll<(), ()> {
+        self.inner.task.register();
+        if self.inner.initiated.load(Ordering::SeqCst) {
+            Ok(Async::Ready(()))
+        } else {
+            Ok(Async::NotReady)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::thread;
+    use std::time::Duration;
+
+    #[test]
+    fn test_signal() {
+        let shutdown = Shutdown::new();
+        let signal = shutdown.signal();
+        assert!(!shutdown.is_initiated());
+
+        let initiator = shutdown.clone();
+        thread::spawn(move || {
+            thread::sleep(Duration::from_millis(10));
+            initiator.initiate();
+        });
+        assert_eq!(signal.wait(), Ok(()));
+        assert!(shutdown.is_initiated());
+    }
+}
@@ -0,0 +1,198 @@
+//! TLS for connections served by `Iron::https`.
+
+use std::io::{self, Read, Write};
+use std::net::SocketAddr;
+use std::sync::{Arc, OnceLock};
+
+use futures::{Async, Poll};
+use rustls::{ServerConfig, ServerConnection};
+use tokio_io::{AsyncRead, AsyncWrite};
+
+use request::TlsInfo;
+
+/// A connection decrypting what is read from `io` and encrypting what is
+/// written to it.
+///
+/// The handshake is driven by the first reads. Once it is complete, the
+/// negotiated parameters are available through `info`.
+pub struct TlsStream<T> {
+    io: T,
+    conn: ServerConnection,
+    peer: Option<SocketAddr>,
+    info: Arc<OnceLock<TlsInfo>>,
+    closing: bool,
+}
+
+impl<T> TlsStream<T> {
+    pub fn new(
+        io: T,
+        config: Arc<ServerConfig>,
+        peer: Option<SocketAddr>,
+    ) -> Result<TlsStream<T>, rustls::Error> {
+        Ok(TlsStream {
+            io,
+            conn: ServerConnection::new(config)?,
+            peer,
+            info: Arc::new(OnceLock::new()),
+            closing: false,
+        })
+    }
+
+    /// The wrapped connection.
+    pub fn get_ref(&self) -> &T {
+        &self.io
+    }
+
+    /// The parameters of the connection, set once the handshake is complete.
+    pub fn info(&self) -> Arc<OnceLock<TlsInfo>> {
+        self.info.clone()
+    }
+
+    fn record_info(&self) {
+        if self.conn.is_handshaking() || self.info.get().is_some() {
+            return;
+        }
+        if let (Some(version), Some(suite)) = (
+            self.conn.protocol_version(),
+            self.conn.negotiated_cipher_suite(),
+        ) {
+            let info = TlsInfo {
+                version: format!("{:?}", version),
+                cipher_suite: format!("{:?}", suite.suite()),
+            };
+            match self.peer {
+                Some(peer) => debug!(
+                    "TLS connection from {}: {} {}",
+                    peer, info.version, info.cipher_suite
+                ),
+                None => debug!("TLS connection: {} {}", info.version, info.cipher_suite),
+            }
+            let _ = self.info.set(info);
+        }
+    }
+}
+
+impl<T: Write> TlsStream<T> {
+    // Send all records the connection has queued.
+    fn write_pending(&mut self) -> io::Result<()> {
+        while self.conn.wants_write() {
+            self.conn.write_tls(&mut self.io)?;
+        }
+        Ok(())
+    }
+}
+
+impl<T: Read + Write> Read for TlsStream<T> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        loop {
+            match self.conn.reader().read(buf) {
+                Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => (),
+                result => return result,
+            }
+
+            // Handshake messages have to go out before the peer answers them.
+            self.write_pending()?;
+            if self.conn.read_tls(&mut self.io)? == 0 {
+                return Ok(0);
+            }
+            if let Err(e) = self.conn.process_new_packets() {
+                // Tell the peer what went wrong, if we can.
+                let _ = self.write_pending();
+                return Err(io::Error::new(io::ErrorKind::InvalidData, e));
+            }
+            self.record_info();
+        }
+    }
+}
+
+impl<T: Write> Write for TlsStream<T> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.write_pending()?;
+        let written = self.conn.writer().write(buf)?;
+        // Anything the socket does not take now goes out on the next write or flush.
+        match self.write_pending() {
+            Err(e) if e.kind() != io::ErrorKind::WouldBlock => Err(e),
+            _ => Ok(written),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.conn.writer().flush()?;
+        self.write_pending()?;
+        self.io.flush()
+    }
+}
+
+impl<T: AsyncRead + AsyncWrite> AsyncRead for TlsStream<T> {}
+
+impl<T: AsyncRead + AsyncWrite> AsyncWrite for TlsStream<T> {
+    fn shutdown(&mut self) -> Poll<(), io::Error> {
+        if !self.closing {
+            self.conn.send_close_notify();
+            self.closing = true;
+        }
+        match self.write_pending() {
+            Ok(()) => self.io.shutdown(),
+            Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => Ok(Async::NotReady),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::convert::TryFrom;
+    use std::io::{Read, Write};
+    use std::net::TcpStream;
+    use std::sync::Arc;
+
+    use rcgen;
+    use rustls::pki_types::{PrivateKeyDer, PrivatePkcs8KeyDer, ServerName};
+    use rustls::{ClientConfig, ClientConnection, RootCertStore, ServerConfig, StreamOwned};
+
+    use iron::test::{capture_logs, logged};
+    use {Iron, Request, Response, StatusCode};
+
+    #[test]
+    fn test_https() {
+        capture_logs();
+        let cert = rcgen::generate_simple_self_signed(vec!["localhost".to_owned()]).unwrap();
+        let key = PrivatePkcs8KeyDer::from(cert.key_pair.serialize_der());
+        let config = ServerConfig::builder()
+            .with_no_client_auth()
+            .with_single_cert(vec![cert.cert.der().clone()], PrivateKeyDer::Pkcs8(key))
+            .unwrap();
+
+        let iron = Iron::new(|req: &mut Request| {
+            let version = req.tls_info().map(|info| info.version).unwrap_or_default();
+            let body = format!("{} {}", req.url.scheme(), version);
+            Ok(Response::with((StatusCode::OK, body)))
+        });
+        let listening = iron.https("127.0.0.1:0", config).unwrap();
+
+        let mut roots = RootCertStore::empty();
+        roots.add(cert.cert.der().clone()).unwrap();
+        let client = ClientConfig::builder()
+            .with_root_certificates(roots)
+            .with_no_client_auth();
+        let conn =
+            ClientConnection::new(Arc::new(client), ServerName::try_from("localhost").unwrap())
+                .unwrap();
+
+        let socket = TcpStream::connect(listening.socket).unwrap();
+        let mut stream = StreamOwned::new(conn, socket);
+        stream
+            .write_all(b"GET / HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n")
+            .unwrap();
+        let mut received = String::new();
+        stream.read_to_string(&mut received).unwrap();
+
+        assert!(received.starts_with("HTTP/1.1 200 OK"));
+        assert!(received.ends_with("\r\n\r\nhttps TLSv1_3"));
+        let client = stream.sock.local_addr().unwrap();
+        let expected = format!("TLS connection from {}: TLSv1_3 TLS13_", client);
+        assert!(logged(&expected));
+
+        listening.close();
+    }
+}
@@ -0,0 +1,35 @@
+// Raising a signal affects the whole process, so this test gets a binary of
+// its own instead of living with the unit tests.
+#![cfg(unix)]
+
+extern crate iron;
+extern crate libc;
+
+use std::sync::mpsc;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use iron::prelude::*;
+use iron::{Lifecycle, StatusCode};
+
+#[test]
+fn test_close_on_ctrl_c() {
+    let (events, received) = mpsc::channel();
+    let events = Mutex::new(events);
+    let mut iron = Iron::new(|_: &mut Request| Ok(Response::with(StatusCode::OK)));
+    iron.on_lifecycle = Some(Arc::new(move |event| {
+        let _ = events.lock().unwrap().send(event);
+    }));
+    let listening = iron.http("127.0.0.1:0").unwrap();
+    let addr = listening.socket;
+    listening.close_on_ctrl_c().unwrap();
+
+    unsafe {
+        libc::raise(libc::SIGINT);
+    }
+    let next = || received.recv_timeout(Duration::from_secs(5)).unwrap();
+    assert_eq!(next(), Lifecycle::Bound(addr));
+    assert_eq!(next(), Lifecycle::Serving(addr));
+    assert_eq!(next(), Lifecycle::Draining(addr));
+    assert_eq!(next(), Lifecycle::Stopped(addr));
+}
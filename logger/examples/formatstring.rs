@@ -8,7 +8,7 @@ use iron::prelude::*;
 use logger::Logger;
 use logger::Format;
 
-static FORMAT: &'static str =
+static FORMAT: &str =
     "Uri: {uri}, Method: {method}, Status: {status}, Duration: {response-time}, Time: {request-time}";
 
 // This is an example of using a format string that can specify colors and attributes
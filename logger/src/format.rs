@@ -50,6 +50,7 @@ impl Format {
 pub trait ContextDisplay<'a> {
     type Item;
     type Display: fmt::Display;
+    #[allow(clippy::type_complexity)]
     fn display_with(&'a self,
                     render: &'a dyn Fn(&mut Formatter, &Self::Item) -> Result<(), fmt::Error>)
                     -> Self::Display;
@@ -64,7 +65,7 @@ impl<'a> ContextDisplay<'a> for Format {
                     -> FormatDisplay<'a> {
         FormatDisplay {
             format: self,
-            render: render,
+            render,
         }
     }
 }
@@ -82,7 +83,7 @@ struct FormatParser<'a> {
 impl<'a> FormatParser<'a> {
     fn new(chars: Peekable<Chars>) -> FormatParser {
         FormatParser {
-            chars: chars,
+            chars,
 
             // No attributes are longer than 14 characters, so we can avoid reallocating.
             object_buffer: String::with_capacity(14),
@@ -115,11 +116,11 @@ impl<'a> Iterator for FormatParser<'a> {
                 self.object_buffer.clear();
 
                 let mut chr = self.chars.next();
-                while chr != None {
+                while chr.is_some() {
                     match chr.unwrap() {
                         // Finished parsing, parse buffer.
                         '}' => break,
-                        c => self.object_buffer.push(c.clone())
+                        c => self.object_buffer.push(c)
                     }
 
                     chr = self.chars.next();
@@ -169,6 +170,7 @@ impl<'a> Iterator for FormatParser<'a> {
 /// fields supported by the `Logger`, or a custom `String`.
 #[derive(Clone)]
 #[doc(hidden)]
+#[allow(clippy::upper_case_acronyms)]
 pub enum FormatText {
     Str(String),
     Method,
@@ -41,7 +41,7 @@ impl Logger {
     /// ```
     pub fn new(format: Option<Format>) -> (Logger, Logger) {
         let format = format.unwrap_or_default();
-        (Logger { format: format.clone() }, Logger { format: format })
+        (Logger { format: format.clone() }, Logger { format })
     }
 }
 
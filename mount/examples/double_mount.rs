@@ -18,6 +18,6 @@ fn main() {
     second.mount("/leveltwo/", level_two);
     first.mount("/levelone/", second);
 
-    Iron::new(first).http("localhost:3000");
+    Iron::new(first).http("localhost:3000").unwrap();
 }
 
@@ -18,5 +18,5 @@ fn main() {
     let mut mount = Mount::new();
     mount.mount("/blocked/", intercept).mount("/", send_hello);
 
-    Iron::new(mount).http("localhost:3000");
+    Iron::new(mount).http("localhost:3000").unwrap();
 }
@@ -41,7 +41,7 @@ impl Error for NoMatch {
 
 impl fmt::Display for NoMatch {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        f.write_str(self.description())
+        f.write_str("No Match")
     }
 }
 
@@ -78,6 +78,12 @@ impl Mount {
     }
 }
 
+impl Default for Mount {
+    fn default() -> Mount {
+        Mount::new()
+    }
+}
+
 impl Handler for Mount {
     fn handle(&self, req: &mut Request) -> IronResult<Response> {
         // Find the matching handler.
@@ -90,11 +96,11 @@ impl Handler for Mount {
             // Path::str_components ignores trailing slashes and will never create routes
             // ending in "".
             let key = match path.last() {
-                Some(s) if s.is_empty() => &path[..path.len() - 1],
+                Some(&"") => &path[..path.len() - 1],
                 _ => &path
             };
 
-            let key: Vec<_> = key.into_iter().map(|s| String::from(*s)).collect();
+            let key: Vec<_> = key.iter().map(|s| String::from(*s)).collect();
 
             // Search the Trie for the nearest most specific match.
             match self.inner.get_ancestor(&key) {
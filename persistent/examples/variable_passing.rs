@@ -26,6 +26,6 @@ fn main() {
     let log_path = String::from("/path/to/a/log/file.log");
     let mut chain = Chain::new(serve_hits);
     chain.link(Read::<Log>::both(log_path));
-    Iron::new(chain).http("localhost:3000");
+    Iron::new(chain).http("localhost:3000").unwrap();
 }
 
@@ -31,7 +31,9 @@ impl Error for PersistentError {
 
 impl fmt::Display for PersistentError {
     fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
-        self.description().fmt(f)
+        match *self {
+            PersistentError::NotFound => f.write_str("Value not found in extensions.")
+        }
     }
 }
 
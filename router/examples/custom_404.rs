@@ -30,7 +30,7 @@ fn main() {
     let mut chain = Chain::new(router);
     chain.link_after(Custom404);
 
-    Iron::new(chain).http("localhost:3000");
+    Iron::new(chain).http("localhost:3000").unwrap();
 }
 
 fn handler(_: &mut Request) -> IronResult<Response> {
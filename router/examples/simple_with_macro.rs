@@ -12,15 +12,15 @@ use router::{Router};
 fn main() {
     let router = router!(root: get "/" => handler, query: get "/:query" => query_handler);
 
-    Iron::new(router).http("localhost:3000");
+    Iron::new(router).http("localhost:3000").unwrap();
 
     fn handler(_: &mut Request) -> IronResult<Response> {
         Ok(Response::with((StatusCode::OK, "OK")))
     }
 
     fn query_handler(req: &mut Request) -> IronResult<Response> {
-        let ref query = req.extensions.get::<Router>()
+        let query = req.extensions.get::<Router>()
             .unwrap().find("query").unwrap_or("/");
-        Ok(Response::with((StatusCode::OK, *query)))
+        Ok(Response::with((StatusCode::OK, query)))
     }
 }
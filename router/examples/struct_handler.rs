@@ -22,5 +22,5 @@ fn main() {
     let mut router = Router::new();
     router.get("/", handler, "index");
 
-    Iron::new(router).http("localhost:3000");
+    Iron::new(router).http("localhost:3000").unwrap();
 }
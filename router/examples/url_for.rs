@@ -17,7 +17,7 @@ fn main() {
         id_2: get "/:query" => query_handler
     };
 
-    Iron::new(router).http("localhost:3000");
+    Iron::new(router).http("localhost:3000").unwrap();
 
     fn handler(r: &mut Request) -> IronResult<Response> {
         Ok(Response::with((
@@ -30,9 +30,9 @@ fn main() {
     }
 
     fn query_handler(req: &mut Request) -> IronResult<Response> {
-        let ref query = req.extensions.get::<Router>()
+        let query = req.extensions.get::<Router>()
             .unwrap().find("query").unwrap_or("/");
-        Ok(Response::with((StatusCode::OK, *query)))
+        Ok(Response::with((StatusCode::OK, query)))
     }
 
 
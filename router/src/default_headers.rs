@@ -0,0 +1,93 @@
+use iron::headers::{HeaderMap, HeaderValue, IntoHeaderName};
+use iron::{Handler, IronResult, Request, Response};
+
+/// Wraps a route handler, or a whole `Router` acting as a group of routes,
+/// and adds default headers to its responses.
+///
+/// A default header is only added if the handler did not set that header
+/// itself, so handlers can still override it.
+///
+/// ```ignore
+/// let mut router = Router::new();
+/// router.get("/config.json", DefaultHeaders::new(config)
+///     .header(headers::CACHE_CONTROL, "max-age=3600".parse().unwrap()), "config");
+/// ```
+pub struct DefaultHeaders<H> {
+    handler: H,
+    headers: HeaderMap
+}
+
+impl<H: Handler> DefaultHeaders<H> {
+    /// Wrap `handler` without any default headers.
+    pub fn new(handler: H) -> DefaultHeaders<H> {
+        DefaultHeaders {
+            handler,
+            headers: HeaderMap::new()
+        }
+    }
+
+    /// Add a default header to the wrapped handler's responses.
+    pub fn header<K: IntoHeaderName>(mut self, name: K, value: HeaderValue) -> DefaultHeaders<H> {
+        self.headers.insert(name, value);
+        self
+    }
+}
+
+impl<H: Handler> Handler for DefaultHeaders<H> {
+    fn handle(&self, req: &mut Request) -> IronResult<Response> {
+        let mut res = self.handler.handle(req)?;
+        for (name, value) in self.headers.iter() {
+            if !res.headers.contains_key(name) {
+                res.headers.insert(name.clone(), value.clone());
+            }
+        }
+        Ok(res)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::DefaultHeaders;
+    use iron::{headers, Handler, Protocol, Request, Response, StatusCode};
+    use iron::request::{Body, HttpRequest};
+    use Router;
+
+    fn get(router: &Router, path: &str) -> Response {
+        let mut req = HttpRequest::new(Body::empty());
+        *req.uri_mut() = path.parse().unwrap();
+        req.headers_mut().insert(headers::HOST, "localhost".parse().unwrap());
+        let mut req = Request::from_http(req, None, None, &Protocol::http()).unwrap();
+        router.handle(&mut req).unwrap()
+    }
+
+    fn ok(_: &mut Request) -> ::iron::IronResult<Response> {
+        Ok(Response::with(StatusCode::OK))
+    }
+
+    #[test]
+    fn test_default_headers() {
+        let mut router = Router::new();
+        router.get("/cached", DefaultHeaders::new(ok)
+            .header(headers::CACHE_CONTROL, "max-age=60".parse().unwrap()), "cached");
+        router.get("/uncached", ok, "uncached");
+
+        let res = get(&router, "/cached");
+        assert_eq!(res.headers[headers::CACHE_CONTROL], "max-age=60");
+
+        let res = get(&router, "/uncached");
+        assert!(!res.headers.contains_key(headers::CACHE_CONTROL));
+    }
+
+    #[test]
+    fn test_handler_overrides_default_header() {
+        let mut router = Router::new();
+        router.get("/private", DefaultHeaders::new(|_: &mut Request| {
+            let mut res = Response::with(StatusCode::OK);
+            res.headers.insert(headers::CACHE_CONTROL, "no-store".parse().unwrap());
+            Ok(res)
+        }).header(headers::CACHE_CONTROL, "max-age=60".parse().unwrap()), "private");
+
+        let res = get(&router, "/private");
+        assert_eq!(res.headers[headers::CACHE_CONTROL], "no-store");
+    }
+}
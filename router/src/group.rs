@@ -0,0 +1,102 @@
+use std::sync::Arc;
+
+use iron::{AfterMiddleware, BeforeMiddleware, Handler, Method};
+
+/// The routes of a group added with `Router::group`, sharing a path prefix
+/// and middleware.
+pub struct RouteGroup {
+    pub(crate) prefix: String,
+    pub(crate) befores: Vec<Arc<Box<dyn BeforeMiddleware>>>,
+    pub(crate) afters: Vec<Arc<Box<dyn AfterMiddleware>>>,
+    pub(crate) routes: Vec<GroupRoute>,
+    pub(crate) defaults: Vec<(String, Vec<(String, String)>)>
+}
+
+pub(crate) struct GroupRoute {
+    pub method: Option<Method>,
+    pub glob: String,
+    pub handler: Box<dyn Handler>,
+    pub route_id: String
+}
+
+impl RouteGroup {
+    pub(crate) fn new(prefix: &str) -> RouteGroup {
+        RouteGroup {
+            prefix: prefix.trim_end_matches('/').to_owned(),
+            befores: Vec::new(),
+            afters: Vec::new(),
+            routes: Vec::new(),
+            defaults: Vec::new()
+        }
+    }
+
+    // The full glob of a route registered as `glob` in this group.
+    fn glob(&self, glob: &str) -> String {
+        match glob {
+            "" | "/" if !self.prefix.is_empty() => self.prefix.clone(),
+            glob if glob.starts_with('/') => format!("{}{}", self.prefix, glob),
+            glob => format!("{}/{}", self.prefix, glob)
+        }
+    }
+
+    fn push(&mut self, method: Option<Method>, glob: &str, handler: Box<dyn Handler>, route_id: &str) -> &mut RouteGroup {
+        let glob = self.glob(glob);
+        self.routes.push(GroupRoute { method, glob, handler, route_id: route_id.to_owned() });
+        self
+    }
+
+    /// Run `before` ahead of the handlers of all routes in the group.
+    pub fn link_before<B: BeforeMiddleware>(&mut self, before: B) -> &mut RouteGroup {
+        self.befores.push(Arc::new(Box::new(before)));
+        self
+    }
+
+    /// Run `after` on the responses of all routes in the group.
+    pub fn link_after<A: AfterMiddleware>(&mut self, after: A) -> &mut RouteGroup {
+        self.afters.push(Arc::new(Box::new(after)));
+        self
+    }
+
+    /// Like `Router::route`, with `glob` below the group's prefix.
+    pub fn route<S: AsRef<str>, H: Handler, I: AsRef<str>>(&mut self, method: Method, glob: S, handler: H, route_id: I) -> &mut RouteGroup {
+        self.push(Some(method), glob.as_ref(), Box::new(handler), route_id.as_ref())
+    }
+
+    /// Like `Router::defaults`, with `glob` below the group's prefix.
+    pub fn defaults<S: AsRef<str>>(&mut self, glob: S, defaults: &[(&str, &str)]) -> &mut RouteGroup {
+        let glob = self.glob(glob.as_ref());
+        let defaults = defaults.iter().map(|&(name, value)| (name.to_owned(), value.to_owned())).collect();
+        self.defaults.push((glob, defaults));
+        self
+    }
+
+    /// Like `Router::get`, with `glob` below the group's prefix.
+    pub fn get<S: AsRef<str>, H: Handler, I: AsRef<str>>(&mut self, glob: S, handler: H, route_id: I) -> &mut RouteGroup {
+        self.route(Method::GET, glob, handler, route_id)
+    }
+
+    /// Like `Router::post`, with `glob` below the group's prefix.
+    pub fn post<S: AsRef<str>, H: Handler, I: AsRef<str>>(&mut self, glob: S, handler: H, route_id: I) -> &mut RouteGroup {
+        self.route(Method::POST, glob, handler, route_id)
+    }
+
+    /// Like `Router::put`, with `glob` below the group's prefix.
+    pub fn put<S: AsRef<str>, H: Handler, I: AsRef<str>>(&mut self, glob: S, handler: H, route_id: I) -> &mut RouteGroup {
+        self.route(Method::PUT, glob, handler, route_id)
+    }
+
+    /// Like `Router::delete`, with `glob` below the group's prefix.
+    pub fn delete<S: AsRef<str>, H: Handler, I: AsRef<str>>(&mut self, glob: S, handler: H, route_id: I) -> &mut RouteGroup {
+        self.route(Method::DELETE, glob, handler, route_id)
+    }
+
+    /// Like `Router::patch`, with `glob` below the group's prefix.
+    pub fn patch<S: AsRef<str>, H: Handler, I: AsRef<str>>(&mut self, glob: S, handler: H, route_id: I) -> &mut RouteGroup {
+        self.route(Method::PATCH, glob, handler, route_id)
+    }
+
+    /// Like `Router::any`, with `glob` below the group's prefix.
+    pub fn any<S: AsRef<str>, H: Handler, I: AsRef<str>>(&mut self, glob: S, handler: H, route_id: I) -> &mut RouteGroup {
+        self.push(None, glob.as_ref(), Box::new(handler), route_id.as_ref())
+    }
+}
@@ -7,10 +7,15 @@ extern crate iron;
 extern crate route_recognizer as recognizer;
 extern crate url;
 
-pub use router::{Router, NoRoute, TrailingSlash};
+pub use router::{Router, RouteInfo, NoRoute, TrailingSlash, AmbiguousRoutes};
+pub use default_headers::DefaultHeaders;
+pub use group::RouteGroup;
 pub use recognizer::Params;
 pub use url_for::url_for;
 
 mod router;
+mod default_headers;
+mod group;
 mod macros;
+mod match_cache;
 mod url_for;
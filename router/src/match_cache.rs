@@ -0,0 +1,103 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+
+use iron::method;
+
+use recognizer::Params;
+use router::Route;
+
+type Key = (method::Method, String);
+
+// A least recently used cache of resolved routes, keyed by method and path.
+//
+// Entries are keyed by the full path, so the captured params of a
+// parameterized route are only reused for exactly the same path.
+pub struct MatchCache {
+    capacity: usize,
+    entries: HashMap<Key, (Arc<Route>, Params, u64)>,
+    // Keys by the tick they were last used at, oldest first.
+    recency: BTreeMap<u64, Key>,
+    tick: u64,
+    pub hits: u64
+}
+
+impl MatchCache {
+    pub fn new(capacity: usize) -> MatchCache {
+        MatchCache {
+            capacity,
+            entries: HashMap::new(),
+            recency: BTreeMap::new(),
+            tick: 0,
+            hits: 0
+        }
+    }
+
+    pub fn get(&mut self, method: &method::Method, path: &str) -> Option<(Arc<Route>, Params)> {
+        self.tick += 1;
+        let key = (method.clone(), path.to_owned());
+        let entry = self.entries.get_mut(&key)?;
+
+        self.recency.remove(&entry.2);
+        entry.2 = self.tick;
+        self.recency.insert(self.tick, key);
+        self.hits += 1;
+        Some((entry.0.clone(), entry.1.clone()))
+    }
+
+    pub fn insert(&mut self, method: &method::Method, path: &str, route: Arc<Route>, params: Params) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        self.tick += 1;
+        let key = (method.clone(), path.to_owned());
+        if let Some((_, _, tick)) = self.entries.insert(key.clone(), (route, params, self.tick)) {
+            self.recency.remove(&tick);
+        }
+        self.recency.insert(self.tick, key);
+
+        while self.entries.len() > self.capacity {
+            let oldest = *self.recency.keys().next().unwrap();
+            let key = self.recency.remove(&oldest).unwrap();
+            self.entries.remove(&key);
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.recency.clear();
+    }
+
+    #[cfg(test)]
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::MatchCache;
+    use std::sync::Arc;
+    use iron::Method;
+    use recognizer::Params;
+    use router::Route;
+
+    fn route() -> Arc<Route> {
+        Arc::new(Route::default())
+    }
+
+    #[test]
+    fn test_evicts_least_recently_used() {
+        let mut cache = MatchCache::new(2);
+        cache.insert(&Method::GET, "a", route(), Params::new());
+        cache.insert(&Method::GET, "b", route(), Params::new());
+        assert!(cache.get(&Method::GET, "a").is_some());
+
+        cache.insert(&Method::GET, "c", route(), Params::new());
+        assert_eq!(cache.len(), 2);
+        assert!(cache.get(&Method::GET, "a").is_some());
+        assert!(cache.get(&Method::GET, "b").is_none());
+        assert!(cache.get(&Method::POST, "c").is_none());
+        assert!(cache.get(&Method::GET, "c").is_some());
+    }
+}
@@ -1,9 +1,9 @@
 use std::collections::HashMap;
 use std::error::Error;
 use std::fmt;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 
-use iron::{Request, Response, Handler, IronResult, IronError};
+use iron::{Request, Response, Handler, IronResult, IronError, Chain};
 use iron::{StatusCode, method, Method, headers};
 use iron::typemap::Key;
 use iron::modifiers::Redirect;
@@ -11,20 +11,72 @@ use iron::modifiers::Redirect;
 use recognizer::Router as Recognizer;
 use recognizer::{Match, Params};
 
+use group::RouteGroup;
+use match_cache::MatchCache;
+use url_for::{param_name, set_path};
+
+
+// A predicate a request must pass for a guarded route to match.
+type Guard = dyn Fn(&Request) -> bool + Send + Sync;
+
+// The handlers registered for one method and glob pattern.
+//
+// Guarded handlers are tried in the order they were added, before the
+// unguarded handler.
+#[derive(Clone, Default)]
+pub struct Route {
+    guarded: Vec<(Arc<Guard>, Arc<dyn Handler>)>,
+    unguarded: Option<Arc<dyn Handler>>,
+    // The glob as it was registered, which may end in an optional parameter.
+    pattern: String
+}
+
+impl Route {
+    // The handler for `req`, if any guard passes or there is an unguarded handler.
+    fn handler(&self, req: &Request) -> Option<Arc<dyn Handler>> {
+        self.guarded.iter()
+            .find(|&(guard, _)| guard(req))
+            .map(|(_, handler)| handler)
+            .or(self.unguarded.as_ref())
+            .cloned()
+    }
+}
 
 pub struct RouterInner {
     // The routers, specialized by method.
-    pub routers: HashMap<method::Method, Recognizer<Box<dyn Handler>>>,
+    pub routers: HashMap<method::Method, Recognizer<Arc<Route>>>,
     // Routes that accept any method.
-    pub wildcard: Recognizer<Box<dyn Handler>>,
+    pub wildcard: Recognizer<Arc<Route>>,
+    // The routes by method and glob, `None` standing for any method.
+    pub routes: HashMap<(Option<method::Method>, String), Route>,
     // Used in URL generation.
-    pub route_ids: HashMap<String, String>
+    pub route_ids: HashMap<String, String>,
+    // Every registered route, in registration order.
+    pub route_infos: Vec<RouteInfo>,
+    // Whether request paths are normalized before matching.
+    pub normalize_paths: bool,
+    // Default parameter values, by the glob as it was registered.
+    pub defaults: HashMap<String, Vec<(String, String)>>
+}
+
+/// A description of a route registered with a `Router`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RouteInfo {
+    /// The method the route matches, `None` for routes added with `any`.
+    pub method: Option<method::Method>,
+    /// The glob pattern the route matches, e.g. `/users/:id`.
+    pub pattern: String,
+    /// The route id, as used by `url_for`.
+    pub name: String,
+    /// Whether the route was added with `route_if`.
+    pub guarded: bool
 }
 
 /// `Router` provides an interface for creating complex routes as middleware
 /// for the Iron framework.
 pub struct Router {
-    inner: Arc<RouterInner>
+    inner: Arc<RouterInner>,
+    cache: Option<Mutex<MatchCache>>
 }
 
 impl Router {
@@ -39,12 +91,20 @@ impl Router {
             inner: Arc::new(RouterInner {
                 routers: HashMap::new(),
                 wildcard: Recognizer::new(),
-                route_ids: HashMap::new()
-            })
+                routes: HashMap::new(),
+                route_ids: HashMap::new(),
+                route_infos: Vec::new(),
+                normalize_paths: true,
+                defaults: HashMap::new()
+            }),
+            cache: None
         }
     }
 
     fn mut_inner(&mut self) -> &mut RouterInner {
+        if let Some(ref cache) = self.cache {
+            cache.lock().unwrap().clear();
+        }
         Arc::get_mut(&mut self.inner).expect("Cannot modify router at this point.")
     }
 
@@ -66,23 +126,196 @@ impl Router {
     /// `route_id` is a unique name for your route, and is used when generating an URL with
     /// `url_for`.
     ///
+    /// A trailing parameter can be made optional with a `?`, e.g.
+    /// `/posts/:page?` matches both `/posts` and `/posts/3`. Use `defaults`
+    /// to give it a value when it is left out. `url_for` always takes it.
+    /// Panics if a route for the same method was registered with another
+    /// pattern matching the same paths, e.g. `/posts` or `/posts/:page`.
+    ///
     /// The controller provided to route can be any `Handler`, which allows
     /// extreme flexibility when handling routes. For instance, you could provide
     /// a `Chain`, a `Handler`, which contains an authorization middleware and
     /// a controller function, so that you can confirm that the request is
     /// authorized for this route before handling it.
     pub fn route<S: AsRef<str>, H: Handler, I: AsRef<str>>(&mut self, method: method::Method, glob: S, handler: H, route_id: I) -> &mut Router {
-        self.mut_inner().routers
-            .entry(method)
-            .or_insert(Recognizer::new())
-            .add(glob.as_ref(), Box::new(handler));
-        self.route_id(route_id.as_ref(), glob.as_ref());
+        self.add(Some(method), glob.as_ref(), None, Arc::new(handler), route_id.as_ref());
+        self
+    }
+
+    /// Like route, but the route only matches requests for which `guard`
+    /// returns `true`.
+    ///
+    /// Guarded routes for the same method and glob pattern are tried in the
+    /// order they were added, before the unguarded route for that pattern.
+    /// If no guard passes and there is no unguarded route, the request is
+    /// answered as if no route matched. This allows routing on arbitrary
+    /// request state, e.g. for A/B tests:
+    ///
+    /// ```ignore
+    /// router.route_if(Method::GET, "/", |req: &Request| req.headers.contains_key("x-beta"), beta, "index_beta");
+    /// router.get("/", index, "index");
+    /// ```
+    pub fn route_if<S, G, H, I>(&mut self, method: method::Method, glob: S, guard: G, handler: H, route_id: I) -> &mut Router
+        where S: AsRef<str>, G: Fn(&Request) -> bool + Send + Sync + 'static, H: Handler, I: AsRef<str> {
+        self.add(Some(method), glob.as_ref(), Some(Arc::new(guard)), Arc::new(handler), route_id.as_ref());
         self
     }
 
+    fn add(&mut self, method: Option<method::Method>, glob: &str, guard: Option<Arc<Guard>>,
+           handler: Arc<dyn Handler>, route_id: &str) {
+        // Paths are routed by the globs they expand to, which must not be
+        // shared with routes registered under another pattern.
+        let globs = expand(glob);
+        for path in &globs {
+            let key = (method.clone(), path.to_string());
+            if let Some(other) = self.inner.routes.get(&key).filter(|other| other.pattern != glob) {
+                panic!("Route {} conflicts with route {}", glob, other.pattern);
+            }
+        }
+        self.route_id(route_id, globs.last().unwrap());
+
+        let inner = self.mut_inner();
+        let info = RouteInfo {
+            method: method.clone(),
+            pattern: glob.to_owned(),
+            name: route_id.to_owned(),
+            guarded: guard.is_some()
+        };
+        // A new unguarded route replaces the previous one.
+        if !info.guarded {
+            inner.route_infos.retain(|other| {
+                other.guarded || other.method != info.method || other.pattern != info.pattern
+            });
+        }
+        inner.route_infos.push(info);
+
+        for path in globs {
+            self.add_glob(method.clone(), path, glob, guard.clone(), handler.clone());
+        }
+    }
+
+    // Add the route for `glob`, which was registered as `pattern`.
+    fn add_glob(&mut self, method: Option<method::Method>, glob: &str, pattern: &str,
+                guard: Option<Arc<Guard>>, handler: Arc<dyn Handler>) {
+        let inner = self.mut_inner();
+        let route = inner.routes.entry((method.clone(), glob.to_owned())).or_default();
+        route.pattern = pattern.to_owned();
+        match guard {
+            Some(guard) => route.guarded.push((guard, handler)),
+            None => route.unguarded = Some(handler)
+        }
+
+        let route = Arc::new(route.clone());
+        match method {
+            Some(method) => inner.routers.entry(method).or_insert_with(Recognizer::new).add(glob, route),
+            None => inner.wildcard.add(glob, route)
+        }
+    }
+
+    /// Set default values for parameters of the routes registered with
+    /// `glob`, for requests whose path leaves them out.
+    ///
+    /// `glob` is the pattern exactly as it was passed when registering the
+    /// routes, which must come first. Panics if no route was registered with
+    /// it.
+    ///
+    /// This gives optional parameters a value, so the handler always sees
+    /// one:
+    ///
+    /// ```ignore
+    /// router.get("/posts/:page?", list_posts, "posts");
+    /// router.defaults("/posts/:page?", &[("page", "1")]);
+    /// ```
+    pub fn defaults<S: AsRef<str>>(&mut self, glob: S, defaults: &[(&str, &str)]) -> &mut Router {
+        let glob = glob.as_ref();
+        if !self.inner.route_infos.iter().any(|info| info.pattern == glob) {
+            panic!("Defaults for unregistered route: {}", glob);
+        }
+        let defaults = defaults.iter().map(|&(name, value)| (name.to_owned(), value.to_owned())).collect();
+        self.mut_inner().defaults.insert(glob.to_owned(), defaults);
+        self
+    }
+
+    /// Cache up to `capacity` resolved routes, skipping route matching for
+    /// repeated requests to the same path.
+    ///
+    /// Routes are cached by method and full path, together with the params
+    /// captured from that path. The least recently used route is evicted
+    /// once the cache is full. A capacity of `0` disables the cache, which is
+    /// the default.
+    pub fn match_cache(&mut self, capacity: usize) -> &mut Router {
+        self.cache = if capacity > 0 {
+            Some(Mutex::new(MatchCache::new(capacity)))
+        } else {
+            None
+        };
+        self
+    }
+
+    /// Enable or disable path normalization, which is enabled by default.
+    ///
+    /// When enabled, request paths are normalized before matching: duplicate
+    /// slashes are collapsed and `.` and `..` segments are resolved, with a
+    /// `..` at the root staying there. Request URLs have their dot segments
+    /// resolved when they are parsed, so only duplicate slashes are left to
+    /// normalize for them.
+    pub fn normalize_paths(&mut self, enabled: bool) -> &mut Router {
+        self.mut_inner().normalize_paths = enabled;
+        self
+    }
+
+    /// All registered routes, in the order they were added.
+    ///
+    /// ```
+    /// # extern crate iron;
+    /// # extern crate router;
+    /// # use router::Router;
+    /// # use iron::prelude::*;
+    /// # use iron::StatusCode;
+    /// # fn main() {
+    /// let mut router = Router::new();
+    /// router.get("/users/:id", |_: &mut Request| Ok(Response::with(StatusCode::OK)), "user");
+    /// assert_eq!(router.routes()[0].name, "user");
+    /// # }
+    /// ```
+    pub fn routes(&self) -> Vec<RouteInfo> {
+        self.inner.route_infos.clone()
+    }
+
+    /// Check that no two routes are ambiguous.
+    ///
+    /// Two routes are ambiguous if they match the same method and some path
+    /// matches both glob patterns, while neither is preferred because they
+    /// have as many static, `:param` and `*` segments, e.g. `/:a/b` and
+    /// `/x/:b` both matching `/x/b`. Which of them handles such a path is
+    /// left to chance, so this is best called after registering all routes,
+    /// e.g. in a test.
+    ///
+    /// Guarded routes for the same pattern are tried in order and thus not
+    /// ambiguous.
+    pub fn validate(&self) -> Result<(), AmbiguousRoutes> {
+        let routes = &self.inner.route_infos;
+        let mut conflicts = vec![];
+        for (i, a) in routes.iter().enumerate() {
+            for b in &routes[i + 1..] {
+                let ambiguous = expand(&a.pattern).iter()
+                    .any(|x| expand(&b.pattern).iter().any(|y| ambiguous(x, y)));
+                if a.method == b.method && a.pattern != b.pattern && ambiguous {
+                    conflicts.push((a.clone(), b.clone()));
+                }
+            }
+        }
+
+        if conflicts.is_empty() {
+            Ok(())
+        } else {
+            Err(AmbiguousRoutes(conflicts))
+        }
+    }
+
     fn route_id(&mut self, id: &str, glob: &str) {
         let inner = self.mut_inner();
-        let ref mut route_ids = inner.route_ids;
+        let route_ids = &mut inner.route_ids;
 
         match route_ids.get(id) {
             Some(other_glob) if glob != other_glob => panic!("Duplicate route_id: {}", id),
@@ -130,19 +363,90 @@ impl Router {
     /// Route will match any method, including gibberish.
     /// In case of ambiguity, handlers specific to methods will be preferred.
     pub fn any<S: AsRef<str>, H: Handler, I: AsRef<str>>(&mut self, glob: S, handler: H, route_id: I) -> &mut Router {
-        self.mut_inner().wildcard.add(glob.as_ref(), Box::new(handler));
-        self.route_id(route_id.as_ref(), glob.as_ref());
+        self.add(None, glob.as_ref(), None, Arc::new(handler), route_id.as_ref());
+        self
+    }
+
+    /// Add the routes registered by `routes` below the path `prefix`, all
+    /// sharing the middleware linked to the group.
+    ///
+    /// Middleware linked anywhere within `routes` applies to every route of
+    /// the group, and only to those:
+    ///
+    /// ```ignore
+    /// router.group("/admin", |admin| {
+    ///     admin.link_before(RequireAdmin);
+    ///     admin.get("/users", list_users, "admin_users");
+    ///     admin.delete("/users/:id", delete_user, "admin_delete_user");
+    /// });
+    /// ```
+    pub fn group<S: AsRef<str>, F: FnOnce(&mut RouteGroup)>(&mut self, prefix: S, routes: F) -> &mut Router {
+        let mut group = RouteGroup::new(prefix.as_ref());
+        routes(&mut group);
+
+        for route in group.routes {
+            let mut chain = Chain::new(route.handler);
+            for before in &group.befores {
+                chain.link_before(before.clone());
+            }
+            for after in &group.afters {
+                chain.link_after(after.clone());
+            }
+            self.add(route.method, &route.glob, None, Arc::new(chain), &route.route_id);
+        }
+        for (glob, defaults) in group.defaults {
+            let defaults: Vec<_> = defaults.iter().map(|(name, value)| (&**name, &**value)).collect();
+            self.defaults(glob, &defaults);
+        }
+        self
+    }
+
+    /// Redirect requests for the legacy path `old_glob` to `new_glob` with a
+    /// `308 Permanent Redirect`, which clients follow with the same method
+    /// and body.
+    ///
+    /// Parameters of `old_glob` are substituted into `new_glob` by name, and
+    /// the query string is kept. The alias matches any method and uses
+    /// `old_glob` as its route id.
+    ///
+    /// ```ignore
+    /// router.alias("/posts/:id", "/articles/:id");
+    /// ```
+    ///
+    /// Panics if `new_glob` has a parameter missing from `old_glob`.
+    pub fn alias<S: AsRef<str>, T: AsRef<str>>(&mut self, old_glob: S, new_glob: T) -> &mut Router {
+        let (old_glob, new_glob) = (old_glob.as_ref(), new_glob.as_ref());
+        for key in new_glob.split('/').filter_map(param_name) {
+            if !old_glob.split('/').filter_map(param_name).any(|old_key| old_key == key) {
+                panic!("Alias target {} has parameter {} missing from {}", new_glob, key, old_glob);
+            }
+        }
+
+        let alias = Alias { target: new_glob.to_owned() };
+        self.add(None, old_glob, None, Arc::new(alias), old_glob);
         self
     }
 
     fn recognize(&self, method: &method::Method, path: &str)
-                     -> Option<Match<&Box<dyn Handler>>> {
-        self.inner.routers.get(method).and_then(|router| router.recognize(path).ok())
+                     -> Option<Match<Arc<Route>>> {
+        if let Some(ref cache) = self.cache {
+            if let Some((handler, params)) = cache.lock().unwrap().get(method, path) {
+                return Some(Match { handler, params });
+            }
+        }
+
+        let matched = self.inner.routers.get(method).and_then(|router| router.recognize(path).ok())
             .or(self.inner.wildcard.recognize(path).ok())
+            .map(|matched| Match { handler: matched.handler.clone(), params: matched.params });
+
+        if let (Some(cache), Some(matched)) = (&self.cache, &matched) {
+            cache.lock().unwrap().insert(method, path, matched.handler.clone(), matched.params.clone());
+        }
+        matched
     }
 
     fn handle_options(&self, path: &str) -> Response {
-        static METHODS: &'static [method::Method] =
+        static METHODS: &[method::Method] =
             &[Method::GET, Method::POST, Method::PUT,
               Method::DELETE, Method::HEAD, Method::PATCH];
 
@@ -150,11 +454,11 @@ impl Router {
         let mut options = vec![];
 
         for method in METHODS.iter() {
-            self.inner.routers.get(method).map(|router| {
-                if let Some(_) = router.recognize(path).ok() {
+            if let Some(router) = self.inner.routers.get(method) {
+                if router.recognize(path).is_ok() {
                     options.push(method.clone());
                 }
-            });
+            }
         }
         // If GET is there, HEAD is also there.
         if options.contains(&Method::GET) && !options.contains(&Method::HEAD) {
@@ -196,10 +500,43 @@ impl Router {
 
     fn handle_method(&self, req: &mut Request, path: &str) -> Option<IronResult<Response>> {
         if let Some(matched) = self.recognize(&req.method, path) {
-            req.extensions.insert::<Router>(matched.params);
+            let handler = matched.handler.handler(req)?;
+            let mut params = matched.params;
+            for (name, value) in self.inner.defaults.get(&matched.handler.pattern).into_iter().flatten() {
+                if params.find(name).is_none() {
+                    params.insert(name.clone(), value.clone());
+                }
+            }
+            req.extensions.insert::<Router>(params);
             req.extensions.insert::<RouterInner>(self.inner.clone());
-            Some(matched.handler.handle(req))
-        } else { self.redirect_slash(req).and_then(|redirect| Some(Err(redirect))) }
+            Some(handler.handle(req))
+        } else { self.redirect_slash(req).map(Err) }
+    }
+}
+
+// Redirects requests for an aliased route to its target.
+struct Alias {
+    target: String
+}
+
+impl Handler for Alias {
+    fn handle(&self, req: &mut Request) -> IronResult<Response> {
+        let mut params = req.extensions.get::<Router>()
+            .map(|params| params.iter().map(|(k, v)| (k.to_owned(), v.to_owned())).collect())
+            .unwrap_or_default();
+
+        let mut url = req.url.clone();
+        match req.base_path() {
+            Some(base_path) => set_path(url.as_mut(), &format!("{}{}", base_path, self.target), &mut params),
+            None => set_path(url.as_mut(), &self.target, &mut params)
+        }
+        Ok(Response::with((StatusCode::PERMANENT_REDIRECT, Redirect(url))))
+    }
+}
+
+impl Default for Router {
+    fn default() -> Router {
+        Router::new()
     }
 }
 
@@ -207,9 +544,94 @@ impl Key for Router { type Value = Params; }
 
 impl Key for RouterInner { type Value = Arc<RouterInner>; }
 
+// Collapses empty segments and resolves dot segments, keeping a trailing slash.
+fn normalize_path(segments: &[&str]) -> String {
+    let mut normalized = vec![];
+    let last = segments.len().saturating_sub(1);
+
+    for (i, segment) in segments.iter().enumerate() {
+        match *segment {
+            "" if i == last => normalized.push(""),
+            "" | "." => (),
+            ".." => { normalized.pop(); },
+            segment => normalized.push(segment)
+        }
+    }
+
+    normalized.join("/")
+}
+
+// The globs without and with the optional trailing parameter of `glob`, if
+// it has one, e.g. `/posts` and `/posts/:page` for `/posts/:page?`.
+fn optional_param(glob: &str) -> Option<(&str, &str)> {
+    let with = glob.strip_suffix('?')?;
+    let slash = with.rfind('/')?;
+    if !with[slash + 1..].starts_with(':') {
+        return None;
+    }
+    match &with[..slash] {
+        "" => Some(("/", with)),
+        without => Some((without, with))
+    }
+}
+
+// The globs the recognizer is given for the registered `glob`, the one with
+// the optional parameter coming last.
+fn expand(glob: &str) -> Vec<&str> {
+    match optional_param(glob) {
+        Some((without, with)) => vec![without, with],
+        None => vec![glob]
+    }
+}
+
+// The segments of `glob` as the recognizer sees them.
+fn glob_segments(glob: &str) -> Vec<&str> {
+    glob.strip_prefix('/').unwrap_or(glob).split('/').collect()
+}
+
+// Whether the globs `a` and `b` match some path alike, so the recognizer has
+// no preference between them.
+fn ambiguous(a: &str, b: &str) -> bool {
+    // The numbers of static, `:param` and `*` segments rank routes.
+    fn rank(segments: &[&str]) -> (usize, usize, usize) {
+        let dynamics = segments.iter().filter(|s| s.starts_with(':')).count();
+        let stars = segments.iter().filter(|s| s.starts_with('*')).count();
+        (segments.len() - dynamics - stars, dynamics, stars)
+    }
+
+    let (a, b) = (glob_segments(a), glob_segments(b));
+    rank(&a) == rank(&b) && overlap(&a, &b)
+}
+
+// Whether some path matches both lists of glob segments.
+//
+// A `:param` segment matches any non-empty segment, and a `*` segment one or
+// more segments.
+fn overlap(a: &[&str], b: &[&str]) -> bool {
+    match (a.first(), b.first()) {
+        (None, None) => true,
+        (Some(x), _) if x.starts_with('*') => (1..=b.len()).any(|i| overlap(&a[1..], &b[i..])),
+        (_, Some(y)) if y.starts_with('*') => (1..=a.len()).any(|i| overlap(&a[i..], &b[1..])),
+        (Some(x), Some(y)) => {
+            let matches = match (x.starts_with(':'), y.starts_with(':')) {
+                (true, true) => true,
+                (true, false) => !y.is_empty(),
+                (false, true) => !x.is_empty(),
+                (false, false) => x == y
+            };
+            matches && overlap(&a[1..], &b[1..])
+        }
+        _ => false
+    }
+}
+
 impl Handler for Router {
     fn handle(&self, req: &mut Request) -> IronResult<Response> {
-        let path = req.url.path().join("/");
+        let path = if self.inner.normalize_paths {
+            normalize_path(&req.url.path())
+        } else {
+            req.url.path().join("/")
+        };
 
         self.handle_method(req, &path).unwrap_or_else(||
             match req.method {
@@ -257,10 +679,38 @@ impl Error for TrailingSlash {
     fn description(&self) -> &str { "Trailing Slash" }
 }
 
+/// The error returned by `Router::validate`, listing the pairs of
+/// ambiguous routes.
+#[derive(Debug, PartialEq, Eq)]
+pub struct AmbiguousRoutes(pub Vec<(RouteInfo, RouteInfo)>);
+
+impl fmt::Display for AmbiguousRoutes {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("Ambiguous routes:")?;
+        for (a, b) in &self.0 {
+            let method = a.method.as_ref().map_or("*", |method| method.as_str());
+            write!(f, " {} {} and {};", method, a.pattern, b.pattern)?;
+        }
+        Ok(())
+    }
+}
+
+impl Error for AmbiguousRoutes {
+    fn description(&self) -> &str { "Ambiguous Routes" }
+}
+
 #[cfg(test)]
 mod test {
-    use super::Router;
-    use iron::{headers, method, Method, StatusCode, Request, Response};
+    use super::{normalize_path, NoRoute, RouteInfo, Router};
+    use iron::{headers, IronError, method, Method, StatusCode, Request, Response, Handler, Protocol};
+    use iron::request::{Body, HttpRequest};
+
+    fn get(path: &str) -> HttpRequest<Body> {
+        let mut req = HttpRequest::new(Body::empty());
+        *req.uri_mut() = path.parse().unwrap();
+        req.headers_mut().insert(headers::HOST, "localhost".parse().unwrap());
+        req
+    }
 
     #[test]
     fn test_handle_options_post() {
@@ -352,4 +802,289 @@ mod test {
         assert!(router.recognize(&Method::PUT, "/foo").is_none());
         assert!(router.recognize(&Method::PUT, "/upload/foo").is_some());
     }
+
+    #[test]
+    fn test_normalize_path() {
+        assert_eq!(normalize_path(&["a", "", "b"]), "a/b");
+        assert_eq!(normalize_path(&["a", ".", "b"]), "a/b");
+        assert_eq!(normalize_path(&["a", "..", "b"]), "b");
+        assert_eq!(normalize_path(&["a", "b", ""]), "a/b/");
+        assert_eq!(normalize_path(&["..", "etc", "passwd"]), "etc/passwd");
+        assert_eq!(normalize_path(&["a", "..", "..", "b"]), "b");
+    }
+
+    #[test]
+    fn test_dot_segments_dispatch() {
+        let mut router = Router::new();
+        router.get("/b", |_: &mut Request| Ok(Response::with((StatusCode::OK, ""))), "b");
+
+        for path in &["/a/../b", "/a/./../b", "/../b", "/a/%2e%2e/b"] {
+            let mut req = Request::from_http(get(path), None, None, &Protocol::http()).unwrap();
+            assert_eq!(router.handle(&mut req).unwrap().status, Some(StatusCode::OK), "{}", path);
+        }
+    }
+
+    #[test]
+    fn test_duplicate_slashes_match() {
+        let mut router = Router::new();
+        router.get("/a/b", |_: &mut Request| Ok(Response::with((StatusCode::OK, ""))), "ab");
+
+        {
+            let mut req = Request::from_http(get("/a//b"), None, None, &Protocol::http()).unwrap();
+            assert_eq!(router.handle(&mut req).unwrap().status, Some(StatusCode::OK));
+        }
+
+        router.normalize_paths(false);
+        let mut req = Request::from_http(get("/a//b"), None, None, &Protocol::http()).unwrap();
+        assert!(router.handle(&mut req).is_err());
+    }
+
+    #[test]
+    fn test_match_cache() {
+        let mut router = Router::new();
+        router.get("/users/:id", |req: &mut Request| {
+            let id = req.extensions.get::<Router>().unwrap().find("id").unwrap().to_owned();
+            Ok(Response::with((StatusCode::OK, id)))
+        }, "user");
+        router.match_cache(16);
+
+        let handle = |path: &str| {
+            let mut req = Request::from_http(get(path), None, None, &Protocol::http()).unwrap();
+            let mut res = router.handle(&mut req).unwrap();
+            let mut body = vec![];
+            res.body.as_mut().unwrap().write_body(&mut body).unwrap();
+            String::from_utf8(body).unwrap()
+        };
+
+        assert_eq!(handle("/users/1"), "1");
+        assert_eq!(handle("/users/1"), "1");
+        assert_eq!(handle("/users/2"), "2");
+        assert_eq!(handle("/users/1"), "1");
+
+        let cache = router.cache.as_ref().unwrap().lock().unwrap();
+        assert_eq!(cache.hits, 2);
+        assert_eq!(cache.len(), 2);
+    }
+
+    fn body(router: &Router, req: HttpRequest<Body>) -> Option<String> {
+        let mut req = Request::from_http(req, None, None, &Protocol::http()).unwrap();
+        let mut res = router.handle(&mut req).ok()?;
+        let mut body = vec![];
+        res.body.as_mut().unwrap().write_body(&mut body).unwrap();
+        Some(String::from_utf8(body).unwrap())
+    }
+
+    fn beta(mut req: HttpRequest<Body>) -> HttpRequest<Body> {
+        req.headers_mut().insert("x-beta", "1".parse().unwrap());
+        req
+    }
+
+    #[test]
+    fn test_guarded_route() {
+        let mut router = Router::new();
+        router.route_if(Method::GET, "/", |req: &Request| req.headers.contains_key("x-beta"),
+                        |_: &mut Request| Ok(Response::with((StatusCode::OK, "beta"))), "index_beta");
+
+        assert_eq!(body(&router, beta(get("/"))), Some("beta".to_owned()));
+        assert_eq!(body(&router, get("/")), None);
+
+        router.get("/", |_: &mut Request| Ok(Response::with((StatusCode::OK, "stable"))), "index");
+        assert_eq!(body(&router, beta(get("/"))), Some("beta".to_owned()));
+        assert_eq!(body(&router, get("/")), Some("stable".to_owned()));
+    }
+
+    #[test]
+    fn test_defaults() {
+        let mut router = Router::new();
+        router.get("/posts/:page?", |req: &mut Request| {
+            let page = req.extensions.get::<Router>().unwrap().find("page").unwrap().to_owned();
+            Ok(Response::with((StatusCode::OK, page)))
+        }, "posts");
+        router.defaults("/posts/:page?", &[("page", "1")]);
+
+        assert_eq!(body(&router, get("/posts")), Some("1".to_owned()));
+        assert_eq!(body(&router, get("/posts/3")), Some("3".to_owned()));
+        assert_eq!(body(&router, get("/posts/3/4")), None);
+    }
+
+    #[test]
+    fn test_optional_param() {
+        assert_eq!(super::optional_param("/posts/:page?"), Some(("/posts", "/posts/:page")));
+        assert_eq!(super::optional_param("/:page?"), Some(("/", "/:page")));
+        assert_eq!(super::optional_param("/posts/:page"), None);
+        assert_eq!(super::optional_param("/posts?"), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Route /posts/:page? conflicts with route /posts")]
+    fn test_optional_param_after_route() {
+        let ok = |_: &mut Request| Ok(Response::with(StatusCode::OK));
+        let mut router = Router::new();
+        router.get("/posts", ok, "all_posts");
+        router.get("/posts/:page?", ok, "posts");
+    }
+
+    #[test]
+    #[should_panic(expected = "Route /posts conflicts with route /posts/:page?")]
+    fn test_route_after_optional_param() {
+        let ok = |_: &mut Request| Ok(Response::with(StatusCode::OK));
+        let mut router = Router::new();
+        router.get("/posts/:page?", ok, "posts");
+        router.get("/posts", ok, "all_posts");
+    }
+
+    #[test]
+    fn test_optional_param_routes() {
+        let ok = |_: &mut Request| Ok(Response::with(StatusCode::OK));
+        let mut router = Router::new();
+        router.get("/posts/:page?", ok, "posts");
+        router.post("/posts", ok, "create_post");
+        router.get("/:section/3", ok, "section");
+
+        assert_eq!(router.routes().len(), 3);
+        assert_eq!(router.routes()[0].pattern, "/posts/:page?");
+        let err = router.validate().unwrap_err();
+        assert_eq!(err.to_string(), "Ambiguous routes: GET /posts/:page? and /:section/3;");
+    }
+
+    #[test]
+    #[should_panic(expected = "Defaults for unregistered route: /post/:page?")]
+    fn test_defaults_unregistered() {
+        let mut router = Router::new();
+        router.get("/posts/:page?", |_: &mut Request| Ok(Response::with(StatusCode::OK)), "posts");
+        router.defaults("/post/:page?", &[("page", "1")]);
+    }
+
+    #[test]
+    fn test_group_defaults() {
+        let mut router = Router::new();
+        router.group("/blog", |blog| {
+            blog.get("/posts/:page?", |req: &mut Request| {
+                let page = req.extensions.get::<Router>().unwrap().find("page").unwrap().to_owned();
+                Ok(Response::with((StatusCode::OK, page)))
+            }, "posts");
+            blog.defaults("/posts/:page?", &[("page", "1")]);
+        });
+
+        assert_eq!(body(&router, get("/blog/posts")), Some("1".to_owned()));
+        assert_eq!(body(&router, get("/blog/posts/3")), Some("3".to_owned()));
+    }
+
+    #[test]
+    fn test_group() {
+        let mut router = Router::new();
+        router.group("/admin", |admin| {
+            admin.link_before(|req: &mut Request| {
+                if req.headers.contains_key("x-beta") {
+                    Ok(())
+                } else {
+                    Err(IronError::new(NoRoute, StatusCode::FORBIDDEN))
+                }
+            });
+            admin.get("/", |_: &mut Request| Ok(Response::with((StatusCode::OK, "dashboard"))), "admin");
+            admin.get("/users/:id", |req: &mut Request| {
+                let id = req.extensions.get::<Router>().unwrap().find("id").unwrap().to_owned();
+                Ok(Response::with((StatusCode::OK, id)))
+            }, "admin_user");
+        });
+        router.get("/users/:id", |_: &mut Request| Ok(Response::with((StatusCode::OK, "user"))), "user");
+
+        assert_eq!(body(&router, beta(get("/admin"))), Some("dashboard".to_owned()));
+        assert_eq!(body(&router, beta(get("/admin/users/7"))), Some("7".to_owned()));
+        assert_eq!(body(&router, get("/users/7")), Some("user".to_owned()));
+
+        let mut req = Request::from_http(get("/admin/users/7"), None, None, &Protocol::http()).unwrap();
+        let err = router.handle(&mut req).err().unwrap();
+        assert_eq!(err.response.status, Some(StatusCode::FORBIDDEN));
+    }
+
+    fn location(router: &Router, req: HttpRequest<Body>) -> (StatusCode, String) {
+        let mut req = Request::from_http(req, None, None, &Protocol::http()).unwrap();
+        let res = router.handle(&mut req).unwrap();
+        (res.status.unwrap(), res.headers[headers::LOCATION].to_str().unwrap().to_owned())
+    }
+
+    #[test]
+    fn test_alias() {
+        let mut router = Router::new();
+        router.alias("/about-us", "/about");
+        router.alias("/posts/:id/comments", "/articles/:id/comments");
+
+        assert_eq!(location(&router, get("/about-us")),
+                   (StatusCode::PERMANENT_REDIRECT, "http://localhost/about".to_owned()));
+
+        let mut post = get("/posts/42/comments?page=2");
+        *post.method_mut() = Method::POST;
+        assert_eq!(location(&router, post),
+                   (StatusCode::PERMANENT_REDIRECT, "http://localhost/articles/42/comments?page=2".to_owned()));
+    }
+
+    #[test]
+    #[should_panic(expected = "Alias target /articles/:slug has parameter slug missing from /posts/:id")]
+    fn test_alias_missing_param() {
+        Router::new().alias("/posts/:id", "/articles/:slug");
+    }
+
+    #[test]
+    fn test_routes() {
+        let ok = |_: &mut Request| Ok(Response::with(StatusCode::OK));
+        let mut router = Router::new();
+        router.get("/users/:id", ok, "user");
+        router.post("/users", ok, "create_user");
+        router.any("/health", ok, "health");
+        router.route_if(Method::GET, "/", |_: &Request| true, ok, "index_beta");
+        router.get("/", ok, "index");
+        router.get("/", ok, "index");
+
+        let route = |method: Option<Method>, pattern: &str, name: &str, guarded| RouteInfo {
+            method, pattern: pattern.to_owned(), name: name.to_owned(), guarded
+        };
+        assert_eq!(router.routes(), vec![
+            route(Some(Method::GET), "/users/:id", "user", false),
+            route(Some(Method::POST), "/users", "create_user", false),
+            route(None, "/health", "health", false),
+            route(Some(Method::GET), "/", "index_beta", true),
+            route(Some(Method::GET), "/", "index", false)
+        ]);
+    }
+
+    #[test]
+    fn test_validate_ambiguous() {
+        let ok = |_: &mut Request| Ok(Response::with(StatusCode::OK));
+        let mut router = Router::new();
+        router.get("/:a/b", ok, "a_b");
+        router.get("/x/:b", ok, "x_b");
+        router.post("/x/:b", ok, "post_x_b");
+
+        let err = router.validate().unwrap_err();
+        assert_eq!(err.0.len(), 1);
+        assert_eq!((&*err.0[0].0.pattern, &*err.0[0].1.pattern), ("/:a/b", "/x/:b"));
+        assert_eq!(err.to_string(), "Ambiguous routes: GET /:a/b and /x/:b;");
+    }
+
+    #[test]
+    fn test_validate_clean() {
+        let ok = |_: &mut Request| Ok(Response::with(StatusCode::OK));
+        let mut router = Router::new();
+        router.get("/users", ok, "users");
+        router.get("/users/:id", ok, "user");
+        router.get("/users/me", ok, "me");
+        router.get("/users/:id/posts", ok, "user_posts");
+        router.get("/files/*path", ok, "files");
+        router.post("/users/:name", ok, "create_user");
+        router.any("/users/:id", ok, "any_user");
+        router.route_if(Method::GET, "/", |_: &Request| true, ok, "index_beta");
+        router.get("/", ok, "index");
+
+        assert_eq!(router.validate(), Ok(()));
+    }
+
+    #[test]
+    fn test_ambiguous() {
+        assert!(super::ambiguous("/users/:id", "/users/:name"));
+        assert!(super::ambiguous("/files/*path", "/*dir/images"));
+        assert!(!super::ambiguous("/a/:x", "/b/:x"));
+        assert!(!super::ambiguous("/:a/", "/x/:b"));
+        assert!(!super::ambiguous("/:a/:b", "/:a"));
+    }
 }
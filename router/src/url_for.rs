@@ -16,37 +16,51 @@ pub fn url_for(request: &Request, route_id: &str, params: HashMap<String, String
     let glob = inner.route_ids.get(route_id).expect("No route with that ID");
 
     let mut url = request.url.clone();
-    url_for_impl(url.as_mut(), glob, params);
+    match request.base_path() {
+        Some(base_path) => url_for_impl(url.as_mut(), &format!("{}{}", base_path, glob), params),
+        None => url_for_impl(url.as_mut(), glob, params)
+    }
     url
 }
 
 fn url_for_impl(url: &mut Url, glob: &str, mut params: HashMap<String, String>) {
-    {
-        let mut url_path_segments = url.path_segments_mut().unwrap();
-        url_path_segments.clear();
-        for path_segment in glob.split('/') {
-            if path_segment.len() > 1 && (path_segment.starts_with(':') || path_segment.starts_with('*')) {
-                let key = &path_segment[1..];
-                match params.remove(key) {
-                    Some(x) => url_path_segments.push(&x),
-                    None => panic!("No value for key {}", key)
-                };
-            } else {
-                url_path_segments.push(path_segment);
-            }
-        }
-    }
+    set_path(url, glob, &mut params);
 
     // Now add on the remaining parameters that had no path match.
     url.set_query(None);
     if !params.is_empty() {
         url.query_pairs_mut()
-            .extend_pairs(params.into_iter());
+            .extend_pairs(params);
     }
 
     url.set_fragment(None);
 }
 
+// Replaces the path of `url` with `glob`, taking the values of its parameters
+// out of `params`.
+pub(crate) fn set_path(url: &mut Url, glob: &str, params: &mut HashMap<String, String>) {
+    let mut url_path_segments = url.path_segments_mut().unwrap();
+    url_path_segments.clear();
+    for path_segment in glob.split('/') {
+        match param_name(path_segment) {
+            Some(key) => match params.remove(key) {
+                Some(x) => url_path_segments.push(&x),
+                None => panic!("No value for key {}", key)
+            },
+            None => url_path_segments.push(path_segment)
+        };
+    }
+}
+
+// The name of the parameter `path_segment` of a glob stands for, if any.
+pub(crate) fn param_name(path_segment: &str) -> Option<&str> {
+    if path_segment.len() > 1 && (path_segment.starts_with(':') || path_segment.starts_with('*')) {
+        Some(&path_segment[1..])
+    } else {
+        None
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::url_for_impl;